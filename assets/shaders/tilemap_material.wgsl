@@ -0,0 +1,27 @@
+#import bevy_entitiles::common::{VertexOutput, tilemap}
+#import bevy_entitiles::functions::{get_tile_color, get_shade, sample_layer}
+
+struct WaterMaterial {
+    tint: vec4<f32>,
+    amplitude: f32,
+    frequency: f32,
+}
+
+@group(#{MATERIAL_BIND_GROUP}) @binding(0)
+var<uniform> material: WaterMaterial;
+
+@fragment
+fn tilemap_fragment(input: VertexOutput) -> @location(0) vec4<f32> {
+#ifdef PURE_COLOR
+    return get_tile_color(input) * get_shade(input) * material.tint;
+#else
+    // Distort the uv of the first layer to make the tiles look like water.
+    var distorted = input;
+    let wave = sin(input.position.y * material.frequency + tilemap.time * 3.);
+    distorted.uv.x = fract(input.uv.x + wave * material.amplitude);
+
+    var color = get_tile_color(distorted);
+    color = vec4<f32>(color.rgb * material.tint.rgb, color.a);
+    return color * get_shade(input);
+#endif
+}
@@ -0,0 +1,16 @@
+<?xml version="1.0" encoding="UTF-8"?>
+<tileset version="1.10" tiledversion="1.10.2" name="square" tilewidth="16" tileheight="16" tilecount="4" columns="2">
+ <image source="../test_square.png" width="32" height="32"/>
+ <tile id="1" type="Wall">
+  <properties>
+   <property name="solid" type="bool" value="true"/>
+  </properties>
+ </tile>
+ <tile id="3">
+  <animation>
+   <frame tileid="3" duration="500"/>
+   <frame tileid="2" duration="250"/>
+   <frame tileid="0" duration="250"/>
+  </animation>
+ </tile>
+</tileset>
@@ -15,7 +15,7 @@ use bevy_entitiles::{
             TileRenderSize, TilemapRotation, TilemapSlotSize, TilemapStorage, TilemapTexture,
             TilemapTextureDescriptor, TilemapType,
         },
-        tile::TileBuilder,
+        tile::{TileBuilder, TileFlip, TileLayer},
     },
     EntiTilesPlugin,
 };
@@ -70,5 +70,19 @@ fn setup(mut commands: Commands, asset_server: Res<AssetServer>) {
         TileBuilder::new().with_animation(anim_b),
     );
 
+    // An animated layer under a static one.
+    tilemap.storage.fill_rect(
+        &mut commands,
+        TileArea::new(IVec2 { x: 0, y: 21 }, UVec2 { x: 20, y: 5 }),
+        TileBuilder::new()
+            .with_layer(
+                0,
+                TileLayer::new()
+                    .with_animation(anim_a)
+                    .with_flip(TileFlip::Horizontal),
+            )
+            .with_layer(1, TileLayer::new().with_texture_index(3)),
+    );
+
     commands.entity(entity).insert(tilemap);
 }
@@ -1,8 +1,12 @@
 use bevy::{
     app::PluginGroup,
     math::{IVec2, Vec4},
-    prelude::{App, AssetServer, Camera2dBundle, Commands, Res, Startup, UVec2, Vec2},
+    prelude::{
+        App, AssetServer, Camera2dBundle, Commands, Res, SpriteBundle, Startup, Transform, UVec2,
+        Vec2,
+    },
     render::{color::Color, render_resource::FilterMode},
+    sprite::{Anchor, Sprite},
     window::{PresentMode, Window, WindowPlugin},
     DefaultPlugins,
 };
@@ -11,10 +15,14 @@ use bevy_entitiles::{
     tilemap::{
         bundles::{PureColorTilemapBundle, TilemapBundle},
         map::{
-            TileRenderSize, TilemapName, TilemapRotation, TilemapSlotSize, TilemapStorage,
-            TilemapTexture, TilemapTextureDescriptor, TilemapTransform, TilemapType,
+            TileRenderSize, TilemapDepthMode, TilemapName, TilemapRotation, TilemapSlotSize,
+            TilemapStorage, TilemapTexture, TilemapTextureDescriptor, TilemapTextureMode,
+            TilemapTransform, TilemapType,
+        },
+        tile::{
+            LayerUpdater, TileBuilder, TileFlip, TileLayer, TileLayerPosition, TileRotation,
+            TileUpdater,
         },
-        tile::{LayerUpdater, TileBuilder, TileFlip, TileLayer, TileLayerPosition, TileUpdater},
     },
     EntiTilesPlugin,
 };
@@ -83,6 +91,16 @@ fn setup(mut commands: Commands, assets_server: Res<AssetServer>) {
             .with_color(Color::BLUE.into()),
     );
 
+    // A tile that is twice as tall as the others, growing upwards from its slot.
+    tilemap.storage.set(
+        &mut commands,
+        IVec2 { x: 16, y: 8 },
+        TileBuilder::new()
+            .with_layer(0, TileLayer::new().with_texture_index(1))
+            .with_render_size(Vec2 { x: 16., y: 32. })
+            .with_anchor(Vec2 { x: 0., y: 0. }),
+    );
+
     tilemap.storage.set(
         &mut commands,
         IVec2 { x: 1, y: 1 },
@@ -116,6 +134,17 @@ fn setup(mut commands: Commands, assets_server: Res<AssetServer>) {
         ),
     );
 
+    tilemap.storage.set(
+        &mut commands,
+        IVec2 { x: 1, y: 4 },
+        TileBuilder::new().with_layer(
+            0,
+            TileLayer::new()
+                .with_texture_index(1)
+                .with_rotation(TileRotation::Cw90),
+        ),
+    );
+
     tilemap.storage.update_rect(
         &mut commands,
         TileArea::new(IVec2 { x: 1, y: 3 }, UVec2 { x: 3, y: 3 }),
@@ -143,7 +172,8 @@ fn setup(mut commands: Commands, assets_server: Res<AssetServer>) {
                 UVec2 { x: 32, y: 32 },
                 UVec2 { x: 32, y: 16 },
                 FilterMode::Nearest,
-            ),
+            )
+            .with_mode(TilemapTextureMode::Atlas),
             TilemapRotation::None,
         ),
         tilemap_transform: TilemapTransform {
@@ -159,7 +189,23 @@ fn setup(mut commands: Commands, assets_server: Res<AssetServer>) {
         TileBuilder::new().with_layer(0, TileLayer::new().with_texture_index(0)),
     );
 
-    commands.entity(entity).insert(tilemap);
+    // Sort the tiles by their y position, so the sprite below is drawn
+    // behind the tiles that are lower on the screen.
+    commands
+        .entity(entity)
+        .insert((tilemap, TilemapDepthMode::YSort { scale: 0.001 }));
+
+    let sprite_pos = Vec2 { x: -400., y: 80. };
+    commands.spawn(SpriteBundle {
+        sprite: Sprite {
+            color: Color::ORANGE_RED,
+            custom_size: Some(Vec2 { x: 8., y: 24. }),
+            anchor: Anchor::BottomCenter,
+            ..Default::default()
+        },
+        transform: Transform::from_translation(sprite_pos.extend(-sprite_pos.y * 0.001)),
+        ..Default::default()
+    });
 
     let entity = commands.spawn_empty().id();
     let mut tilemap = PureColorTilemapBundle {
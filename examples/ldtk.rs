@@ -8,7 +8,7 @@
 
 use bevy::{
     app::{App, PluginGroup, Startup, Update},
-    asset::{AssetServer, Assets},
+    asset::AssetServer,
     core_pipeline::core_2d::Camera2dBundle,
     ecs::{
         component::Component,
@@ -19,8 +19,7 @@ use bevy::{
     input::{keyboard::KeyCode, Input},
     math::Vec2,
     reflect::Reflect,
-    render::{mesh::Mesh, render_resource::FilterMode, texture::ImagePlugin, view::Msaa},
-    sprite::TextureAtlas,
+    render::{render_resource::FilterMode, texture::ImagePlugin, view::Msaa},
     utils::HashMap,
     DefaultPlugins,
 };
@@ -30,13 +29,13 @@ use bevy_entitiles::{
         events::LdtkEvent,
         json::{field::FieldInstance, level::EntityInstance, EntityRef},
         layer::physics::LdtkPhysicsLayer,
+        parallax::LdtkParallaxCamera,
         resources::{LdtkAdditionalLayers, LdtkAssets, LdtkLevelManager, LdtkLoadConfig},
-        sprite::LdtkEntityMaterial,
     },
     tilemap::physics::PhysicsTile,
     EntiTilesPlugin,
 };
-use bevy_entitiles_derive::{LdtkEntity, LdtkEntityTag, LdtkEnum};
+use bevy_entitiles_derive::{LdtkEntity, LdtkEntityTag, LdtkEnum, LdtkLayer, LdtkLevel};
 use bevy_xpbd_2d::{
     components::{Collider, Friction, LinearVelocity, Mass, RigidBody},
     plugins::{debug::PhysicsDebugConfig, PhysicsDebugPlugin, PhysicsPlugins},
@@ -60,6 +59,7 @@ fn main() {
         .register_type::<Teleport>()
         .register_type::<Player>()
         .register_type::<Item>()
+        .register_type::<Room>()
         // turn off msaa to avoid the white lines between tiles
         .insert_resource(Msaa::Off)
         .insert_resource(Gravity(Vec2::new(0., -98.)))
@@ -68,7 +68,7 @@ fn main() {
             // replace the filename with grid_vania.ldtk before running
             // this file uses finalbossblues-icons_full_16 and it only exists
             // in my local disk.
-            file_path: "assets/ldtk/ignore grid_vania.ldtk".to_string(),
+            file_path: "ldtk/ignore grid_vania.ldtk".to_string(),
             asset_path_prefix: "ldtk/".to_string(),
             filter_mode: FilterMode::Nearest,
             ignore_unregistered_entities: true,
@@ -106,11 +106,16 @@ fn main() {
         .register_ldtk_entity_tag::<Actor>("actor")
         .register_ldtk_entity_tag::<Loot>("loot")
         .register_ldtk_entity_tag::<Region>("region")
+        // this will be inserted on every level
+        .register_ldtk_level::<Room>()
+        // and this on the layers with this identifier
+        .register_ldtk_layer::<WallShadows>("Wall_shadows")
         .run();
 }
 
 fn setup(mut commands: Commands) {
-    commands.spawn(Camera2dBundle::default());
+    // the parallax layers will scroll with this camera
+    commands.spawn((Camera2dBundle::default(), LdtkParallaxCamera));
 }
 
 macro_rules! level_control {
@@ -147,22 +152,11 @@ fn hot_reload(
     input: Res<Input<KeyCode>>,
     mut manager: ResMut<LdtkLevelManager>,
     config: Res<LdtkLoadConfig>,
-    mut assets: ResMut<LdtkAssets>,
     asset_server: Res<AssetServer>,
-    mut atlas_assets: ResMut<Assets<TextureAtlas>>,
-    mut entity_material_assets: ResMut<Assets<LdtkEntityMaterial>>,
-    mut mesh_assets: ResMut<Assets<Mesh>>,
 ) {
     if input.just_pressed(KeyCode::Return) {
-        manager.reload_json(&config);
-        assets.initialize(
-            &config,
-            &manager,
-            &asset_server,
-            &mut atlas_assets,
-            &mut entity_material_assets,
-            &mut mesh_assets,
-        );
+        // the changed levels will be reloaded once the file is loaded
+        manager.reload_json(&config, &asset_server);
         println!("Hot reloaded!")
     }
 }
@@ -176,6 +170,9 @@ fn events(mut ldtk_events: EventReader<LdtkEvent>) {
             LdtkEvent::LevelUnloaded(level) => {
                 println!("Level unloaded: {}", level.identifier);
             }
+            LdtkEvent::LoadFailed(failed) => {
+                println!("Failed to load {}: {}", failed.level, failed.error);
+            }
         }
     }
 }
@@ -263,6 +260,31 @@ pub enum ItemType {
     VorpalBlade,
 }
 
+#[derive(LdtkEnum, Reflect, Clone, Copy, Debug)]
+#[wrapper_derive(Reflect, Default)]
+pub enum RoomType {
+    Entrance,
+    Exit,
+    Shop,
+    Boss,
+    Save,
+}
+
+// the custom fields of levels works the same as entities
+#[derive(Component, LdtkLevel, Reflect)]
+pub struct Room {
+    #[ldtk_name = "roomType"]
+    pub ty: RoomTypeOption,
+}
+
+// layers don't have custom fields, so the fields are mapped to the metadata of the layer
+#[derive(Component, LdtkLayer, Reflect)]
+pub struct WallShadows {
+    pub iid: String,
+    #[ldtk_name = "opacity"]
+    pub alpha: f32,
+}
+
 #[derive(Component, LdtkEntity, Default, Reflect)]
 #[spawn_sprite]
 // this means the entity will not disappear when the level is unloaded
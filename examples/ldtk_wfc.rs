@@ -49,7 +49,7 @@ fn main() {
                 .collect(),
         ))
         .insert_resource(LdtkLoadConfig {
-            file_path: "assets/ldtk/wfc_source.ldtk".to_string(),
+            file_path: "ldtk/wfc_source.ldtk".to_string(),
             asset_path_prefix: "ldtk/".to_string(),
             filter_mode: FilterMode::Nearest,
             ..Default::default()
@@ -122,6 +122,7 @@ fn save_and_load(
                 mode: TilemapSaverMode::Tilemap,
                 layers: TilemapLayer::all(),
                 texture_path: Some("test_isometric.png".to_string()),
+                extra_texture_paths: Vec::new(),
                 remove_after_save: true,
             });
             println!("Saved tilemap!");
@@ -0,0 +1,112 @@
+use bevy::{
+    app::{App, PluginGroup, Startup, Update},
+    asset::{AssetServer, Handle},
+    core_pipeline::core_2d::Camera2dBundle,
+    ecs::{
+        component::Component,
+        event::EventReader,
+        system::{Commands, EntityCommands, Res, ResMut},
+    },
+    input::{keyboard::KeyCode, Input},
+    math::Vec2,
+    render::{
+        color::Color,
+        texture::{Image, ImagePlugin},
+        view::{InheritedVisibility, Msaa, ViewVisibility, Visibility},
+    },
+    sprite::Sprite,
+    transform::components::GlobalTransform,
+    DefaultPlugins,
+};
+use bevy_entitiles::{
+    tiled::{
+        app_ext::TiledAppExt,
+        events::TiledEvent,
+        map::{TiledMap, TiledObjectInstance, TiledProperty},
+        resources::{TiledLoadConfig, TiledMapManager},
+        traits::TiledObject,
+    },
+    EntiTilesPlugin,
+};
+use helpers::EntiTilesHelpersPlugin;
+
+mod helpers;
+
+fn main() {
+    App::new()
+        .add_plugins((
+            DefaultPlugins.set(ImagePlugin::default_nearest()),
+            EntiTilesPlugin,
+            EntiTilesHelpersPlugin::default(),
+        ))
+        .add_systems(Startup, setup)
+        .add_systems(Update, (load, events))
+        // turn off msaa to avoid the white lines between tiles
+        .insert_resource(Msaa::Off)
+        .insert_resource(TiledLoadConfig {
+            map_folder: "assets/tiled".to_string(),
+            asset_path_prefix: "tiled".to_string(),
+            ..Default::default()
+        })
+        .register_tiled_object::<Marker>("Marker")
+        .run();
+}
+
+fn setup(mut commands: Commands, mut manager: ResMut<TiledMapManager>) {
+    commands.spawn(Camera2dBundle::default());
+    manager.load(&mut commands, "square.tmx".to_string(), None);
+}
+
+fn load(mut commands: Commands, input: Res<Input<KeyCode>>, mut manager: ResMut<TiledMapManager>) {
+    if input.just_pressed(KeyCode::Key1) {
+        manager.switch_to(&mut commands, "square.tmx".to_string(), None);
+    }
+    if input.just_pressed(KeyCode::Key2) {
+        manager.switch_to(&mut commands, "isometric.tmj".to_string(), None);
+    }
+    if input.just_pressed(KeyCode::Space) {
+        manager.unload_all(&mut commands);
+    }
+}
+
+fn events(mut tiled_events: EventReader<TiledEvent>) {
+    for event in tiled_events.read() {
+        match event {
+            TiledEvent::MapLoaded(map) => println!("Map loaded: {}", map),
+            TiledEvent::MapUnloaded(map) => println!("Map unloaded: {}", map),
+        }
+    }
+}
+
+#[derive(Component)]
+struct Marker;
+
+impl TiledObject for Marker {
+    fn initialize(
+        commands: &mut EntityCommands,
+        object: &TiledObjectInstance,
+        _map: &TiledMap,
+        _asset_server: &AssetServer,
+    ) {
+        let color = match object.properties.get("color") {
+            Some(TiledProperty::Color(color)) => Color::rgba(color.x, color.y, color.z, color.w),
+            _ => Color::WHITE,
+        };
+
+        // the entity already has the transform of the object,
+        // so the components of `SpriteBundle` are inserted one by one
+        commands.insert((
+            Marker,
+            Sprite {
+                color,
+                custom_size: Some(Vec2::splat(6.)),
+                ..Default::default()
+            },
+            Handle::<Image>::default(),
+            GlobalTransform::default(),
+            Visibility::default(),
+            InheritedVisibility::default(),
+            ViewVisibility::default(),
+        ));
+    }
+}
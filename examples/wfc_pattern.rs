@@ -101,6 +101,7 @@ fn setup(mut commands: Commands) {
             mode: TilemapSaverMode::MapPattern,
             layers: TilemapLayer::COLOR,
             texture_path: None,
+            extra_texture_paths: Vec::new(),
             remove_after_save: true,
         });
     });
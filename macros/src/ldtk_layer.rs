@@ -0,0 +1,54 @@
+use crate::ldtk_level::expand_fields_ctor;
+
+static CALLBACK_ATTR: &str = "callback";
+
+pub fn expand_ldtk_layer_derive(input: syn::DeriveInput) -> proc_macro::TokenStream {
+    let ty = &input.ident;
+    let attrs = &input.attrs;
+
+    let callback_attr = attrs
+        .iter()
+        .find(|attr| attr.path().get_ident().unwrap() == CALLBACK_ATTR);
+    let callback = {
+        if let Some(attr) = callback_attr {
+            match &attr.meta {
+                syn::Meta::List(meta) => {
+                    let func = &meta.tokens;
+                    quote::quote!(
+                        #func(commands, layer, asset_server, ldtk_manager, ldtk_assets);
+                    )
+                }
+                _ => {
+                    panic!("Callback attribute must be a list of functions!");
+                }
+            }
+        } else {
+            quote::quote!()
+        }
+    };
+
+    let (ctor, use_fields) = expand_fields_ctor(&input.data, "LdtkLayer");
+    let fields = if use_fields {
+        quote::quote!(let fields = layer.get_metadata_fields();)
+    } else {
+        quote::quote!()
+    };
+
+    quote::quote! {
+        impl bevy_entitiles::ldtk::traits::LdtkLayer for #ty {
+            fn initialize(
+                commands: &mut bevy::ecs::system::EntityCommands,
+                layer: &bevy_entitiles::ldtk::json::level::LayerInstance,
+                asset_server: &bevy::prelude::AssetServer,
+                ldtk_manager: &bevy_entitiles::ldtk::resources::LdtkLevelManager,
+                ldtk_assets: &bevy_entitiles::ldtk::resources::LdtkAssets,
+            ) {
+                #callback
+
+                #fields
+                commands.insert(#ctor);
+            }
+        }
+    }
+    .into()
+}
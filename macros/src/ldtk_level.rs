@@ -0,0 +1,113 @@
+use crate::ldtk_entity::{expand_entity_fields, expand_entity_fields_rename};
+
+static LDTK_DEFAULT_ATTR: &str = "ldtk_default";
+static LDTK_NAME_ATTR: &str = "ldtk_name";
+static CALLBACK_ATTR: &str = "callback";
+
+pub fn expand_ldtk_level_derive(input: syn::DeriveInput) -> proc_macro::TokenStream {
+    let ty = &input.ident;
+    let attrs = &input.attrs;
+
+    let callback_attr = attrs
+        .iter()
+        .find(|attr| attr.path().get_ident().unwrap() == CALLBACK_ATTR);
+    let callback = {
+        if let Some(attr) = callback_attr {
+            match &attr.meta {
+                syn::Meta::List(meta) => {
+                    let func = &meta.tokens;
+                    quote::quote!(
+                        #func(commands, level, fields, asset_server, ldtk_manager, ldtk_assets);
+                    )
+                }
+                _ => {
+                    panic!("Callback attribute must be a list of functions!");
+                }
+            }
+        } else {
+            quote::quote!()
+        }
+    };
+
+    let (ctor, _) = expand_fields_ctor(&input.data, "LdtkLevel");
+
+    quote::quote! {
+        impl bevy_entitiles::ldtk::traits::LdtkLevel for #ty {
+            fn initialize(
+                commands: &mut bevy::ecs::system::EntityCommands,
+                level: &bevy_entitiles::ldtk::json::level::Level,
+                fields: &bevy::utils::HashMap<String, bevy_entitiles::ldtk::json::field::FieldInstance>,
+                asset_server: &bevy::prelude::AssetServer,
+                ldtk_manager: &bevy_entitiles::ldtk::resources::LdtkLevelManager,
+                ldtk_assets: &bevy_entitiles::ldtk::resources::LdtkAssets,
+            ) {
+                #callback
+
+                commands.insert(#ctor);
+            }
+        }
+    }
+    .into()
+}
+
+/// Expand the constructor of the struct from the `fields`, and whether `fields` is used.
+pub fn expand_fields_ctor(data: &syn::Data, derive_name: &str) -> (proc_macro2::TokenStream, bool) {
+    let syn::Data::Struct(data_struct) = data else {
+        panic!("{} can only be derived for structs", derive_name);
+    };
+
+    if data_struct.fields.is_empty() {
+        return (quote::quote!(Self), false);
+    }
+
+    let syn::Fields::Named(fields) = &data_struct.fields else {
+        panic!(
+            "{} can only be derived for structs with named fields!",
+            derive_name
+        );
+    };
+    let fields = &fields.named;
+    let mut fields_cton = Vec::new();
+
+    for field in fields.iter() {
+        let field_name = field.ident.as_ref().unwrap();
+        let field_type = &field.ty;
+
+        let attr = field
+            .attrs
+            .iter()
+            .find(|attr| attr.path().get_ident().unwrap() == LDTK_DEFAULT_ATTR);
+        if attr.is_some() {
+            continue;
+        }
+
+        let attr = field
+            .attrs
+            .iter()
+            .find(|attr| attr.path().get_ident().unwrap() == LDTK_NAME_ATTR);
+        if let Some(attr) = attr {
+            fields_cton.push(expand_entity_fields_rename(
+                field_name, field_type, &attr.meta,
+            ));
+            continue;
+        }
+
+        fields_cton.push(expand_entity_fields(field_name, field_type));
+    }
+
+    let default = if fields_cton.len() < fields.len() {
+        quote::quote!(..Default::default())
+    } else {
+        quote::quote!()
+    };
+
+    (
+        quote::quote!(
+            Self {
+                #(#fields_cton)*
+                #default
+            }
+        ),
+        !fields_cton.is_empty(),
+    )
+}
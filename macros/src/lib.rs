@@ -1,6 +1,8 @@
 mod ldtk_entity;
 mod ldtk_entity_tag;
 mod ldtk_enum;
+mod ldtk_layer;
+mod ldtk_level;
 
 #[proc_macro_derive(
     LdtkEntity,
@@ -19,3 +21,13 @@ pub fn derive_ldtk_enums(input: proc_macro::TokenStream) -> proc_macro::TokenStr
 pub fn derive_ldtk_entity_tags(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
     ldtk_entity_tag::expand_ldtk_entity_tag_derive(syn::parse(input).unwrap())
 }
+
+#[proc_macro_derive(LdtkLevel, attributes(ldtk_default, ldtk_name, callback))]
+pub fn derive_ldtk_levels(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    ldtk_level::expand_ldtk_level_derive(syn::parse(input).unwrap())
+}
+
+#[proc_macro_derive(LdtkLayer, attributes(ldtk_default, ldtk_name, callback))]
+pub fn derive_ldtk_layers(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    ldtk_layer::expand_ldtk_layer_derive(syn::parse(input).unwrap())
+}
@@ -17,7 +17,7 @@ use rand::{
 
 use crate::{
     math::{extension::TileIndex, TileArea},
-    serializing::pattern::TilemapPattern,
+    serializing::{map::PATTERN_EXT, pattern::TilemapPattern},
     tilemap::{
         bundles::{PureColorTilemapBundle, TilemapBundle},
         map::{
@@ -168,24 +168,25 @@ impl WfcSource {
     /// ```
     /// C
     /// └── wfc_patterns
-    ///     ├── wfc_pattern_0.ron
-    ///     ├── wfc_pattern_1.ron
+    ///     ├── wfc_pattern_0.pattern.ron
+    ///     ├── wfc_pattern_1.pattern.ron
     ///     ..
     /// ```
     /// So the `directory`= `C:\\wfc_patterns`, `prefix` = `wfc_pattern_`.
+    /// Patterns saved as `wfc_pattern_0.ron` before are also accepted.
     pub fn from_pattern_path(directory: String, prefix: String, conn_rules: &WfcRules) -> Self {
         let n = conn_rules.0.len();
         let mut patterns = Vec::with_capacity(n);
 
         for idx in 0..n {
-            let serialized_pattern: TilemapPattern = ron::from_str(
-                std::fs::read_to_string(
-                    Path::new(&directory).join(format!("{}{}.ron", prefix, idx)),
-                )
-                .unwrap()
-                .as_str(),
-            )
-            .unwrap();
+            let mut pattern_path =
+                Path::new(&directory).join(format!("{}{}.{}", prefix, idx, PATTERN_EXT));
+            if !pattern_path.exists() {
+                pattern_path = Path::new(&directory).join(format!("{}{}.ron", prefix, idx));
+            }
+
+            let serialized_pattern: TilemapPattern =
+                ron::from_str(std::fs::read_to_string(pattern_path).unwrap().as_str()).unwrap();
             patterns.push(serialized_pattern);
         }
 
@@ -743,7 +744,10 @@ pub fn wfc_applier(
                 #[cfg(feature = "ldtk")]
                 WfcSource::LdtkMapPattern(mode) => {
                     use crate::ldtk::resources::LdtkWfcManager;
-                    use bevy::{hierarchy::DespawnRecursiveExt, log::warn};
+                    use bevy::{
+                        hierarchy::DespawnRecursiveExt,
+                        log::{error, warn},
+                    };
 
                     let Some(patterns) = &ldtk_patterns else {
                         return;
@@ -785,7 +789,14 @@ pub fn wfc_applier(
                                 .collect::<Vec<_>>();
 
                             wfc_data.data.iter().enumerate().for_each(|(i, e)| {
-                                let (p, bg) = patterns.get_with_index(*e);
+                                let Some((p, bg)) = patterns.get_with_index(*e) else {
+                                    error!(
+                                        "Failed to apply the wfc result! \
+                                        The pattern {} is not found.",
+                                        e
+                                    );
+                                    return;
+                                };
                                 let ptn_idx = wfc_data.elem_idx_to_grid(i);
 
                                 let mut bg = bg.clone();
@@ -3,25 +3,33 @@ use bevy::{
     ecs::{bundle::Bundle, component::Component},
 };
 
-use super::traits::{
-    LdtkEntity, LdtkEntityRegistry, LdtkEntityTag, LdtkEntityTagRegistry, PhantomLdtkEntity,
-    PhantomLdtkEntityTag,
+use super::{
+    prefab::LdtkPrefab,
+    traits::{
+        LdtkEntity, LdtkEntityRegistry, LdtkEntityTag, LdtkEntityTagRegistry, LdtkLayer,
+        LdtkLayerRegistry, LdtkLevel, LdtkLevelRegistry, PhantomLdtkEntity, PhantomLdtkEntityTag,
+        PhantomLdtkLayer, PhantomLdtkLevel,
+    },
 };
 
 pub trait AppExt {
     fn register_ldtk_entity<T: LdtkEntity + Bundle>(&mut self, ident: &str) -> &mut App;
+    /// Spawn the entities with the identifier from the scene of the prefab.
+    fn register_ldtk_prefab(&mut self, ident: &str, prefab: LdtkPrefab) -> &mut App;
     fn register_ldtk_entity_tag<T: LdtkEntityTag + Component>(&mut self, tag: &str) -> &mut App;
+    /// The components are inserted on every level.
+    fn register_ldtk_level<T: LdtkLevel + Bundle>(&mut self) -> &mut App;
+    fn register_ldtk_layer<T: LdtkLayer + Bundle>(&mut self, ident: &str) -> &mut App;
 }
 
 impl AppExt for App {
     fn register_ldtk_entity<T: LdtkEntity + Bundle>(&mut self, ident: &str) -> &mut App {
-        match self.world.get_non_send_resource_mut::<LdtkEntityRegistry>() {
+        match self.world.get_resource_mut::<LdtkEntityRegistry>() {
             Some(mut mapper) => {
                 mapper.insert(ident.to_string(), Box::new(PhantomLdtkEntity::<T>::new()));
             }
             None => {
-                self.world
-                    .insert_non_send_resource(LdtkEntityRegistry::default());
+                self.world.insert_resource(LdtkEntityRegistry::default());
                 self.register_ldtk_entity::<T>(ident);
             }
         }
@@ -29,21 +37,59 @@ impl AppExt for App {
         self
     }
 
+    fn register_ldtk_prefab(&mut self, ident: &str, prefab: LdtkPrefab) -> &mut App {
+        match self.world.get_resource_mut::<LdtkEntityRegistry>() {
+            Some(mut mapper) => {
+                mapper.insert(ident.to_string(), Box::new(prefab));
+            }
+            None => {
+                self.world.insert_resource(LdtkEntityRegistry::default());
+                self.register_ldtk_prefab(ident, prefab);
+            }
+        }
+
+        self
+    }
+
     fn register_ldtk_entity_tag<T: LdtkEntityTag + Component>(&mut self, tag: &str) -> &mut App {
-        match self
-            .world
-            .get_non_send_resource_mut::<LdtkEntityTagRegistry>()
-        {
+        match self.world.get_resource_mut::<LdtkEntityTagRegistry>() {
             Some(mut mapper) => {
                 mapper.insert(tag.to_string(), Box::new(PhantomLdtkEntityTag::<T>::new()));
             }
             None => {
-                self.world
-                    .insert_non_send_resource(LdtkEntityTagRegistry::default());
+                self.world.insert_resource(LdtkEntityTagRegistry::default());
                 self.register_ldtk_entity_tag::<T>(tag);
             }
         }
 
         self
     }
+
+    fn register_ldtk_level<T: LdtkLevel + Bundle>(&mut self) -> &mut App {
+        match self.world.get_resource_mut::<LdtkLevelRegistry>() {
+            Some(mut registry) => {
+                registry.push(Box::new(PhantomLdtkLevel::<T>::new()));
+            }
+            None => {
+                self.world.insert_resource(LdtkLevelRegistry::default());
+                self.register_ldtk_level::<T>();
+            }
+        }
+
+        self
+    }
+
+    fn register_ldtk_layer<T: LdtkLayer + Bundle>(&mut self, ident: &str) -> &mut App {
+        match self.world.get_resource_mut::<LdtkLayerRegistry>() {
+            Some(mut mapper) => {
+                mapper.insert(ident.to_string(), Box::new(PhantomLdtkLayer::<T>::new()));
+            }
+            None => {
+                self.world.insert_resource(LdtkLayerRegistry::default());
+                self.register_ldtk_layer::<T>(ident);
+            }
+        }
+
+        self
+    }
 }
@@ -1,11 +1,21 @@
 use bevy::{
     ecs::{component::Component, entity::Entity, system::Commands},
-    math::Vec2,
+    math::{IVec2, Vec2},
     reflect::Reflect,
+    tasks::Task,
     utils::HashMap,
 };
 
-use super::resources::LdtkGlobalEntityRegistry;
+use crate::tilemap::tile::TileBuilder;
+
+use super::{
+    json::{
+        field::{FieldInstance, FieldValue},
+        EntityRef,
+    },
+    layer::LdtkLayers,
+    resources::{LdtkGlobalEntityRegistry, SharedLevel},
+};
 
 #[derive(Reflect, Default, Clone, Copy, PartialEq, Eq)]
 pub enum LdtkLoaderMode {
@@ -17,6 +27,7 @@ pub enum LdtkLoaderMode {
 #[derive(Component, Reflect, Default)]
 pub struct LdtkLoader {
     pub(crate) level: String,
+    pub(crate) world: Option<String>,
     pub(crate) mode: LdtkLoaderMode,
     pub(crate) trans_ovrd: Option<Vec2>,
 }
@@ -24,6 +35,41 @@ pub struct LdtkLoader {
 #[derive(Component, Reflect, Default)]
 pub struct LdtkUnloader;
 
+/// The layers of the level being built on the `AsyncComputeTaskPool`.
+#[derive(Component)]
+pub struct LdtkLoadingTask {
+    pub(crate) task: Task<(SharedLevel, LdtkLayers)>,
+    pub(crate) world_iid: String,
+}
+
+/// The tiles waiting to be spawned, tilemap to the tiles.
+#[derive(Component, Default)]
+pub struct LdtkPendingTiles(pub(crate) Vec<(Entity, HashMap<IVec2, TileBuilder>)>);
+
+/// The loading progress of a level.
+///
+/// This is on the level entity until `LdtkEvent::LevelLoaded` is sent.
+#[derive(Component, Debug, Clone, Copy, Default, Reflect)]
+pub struct LdtkLoadingProgress {
+    /// The layers are built and the tiles are being spawned.
+    pub is_built: bool,
+    pub spawned_tiles: usize,
+    pub total_tiles: usize,
+}
+
+impl LdtkLoadingProgress {
+    /// The fraction of the tiles spawned, from 0 to 1.
+    pub fn get_fraction(&self) -> f32 {
+        if !self.is_built {
+            0.
+        } else if self.total_tiles == 0 {
+            1.
+        } else {
+            self.spawned_tiles as f32 / self.total_tiles as f32
+        }
+    }
+}
+
 #[derive(Component)]
 pub struct LdtkUnloadLayer;
 
@@ -32,14 +78,19 @@ pub struct LdtkLoadedLevel {
     pub identifier: String,
     pub layers: HashMap<LayerIid, Entity>,
     pub entities: HashMap<EntityIid, Entity>,
+    /// layer iid to the entity with the `LdtkIntGrid` of the layer
+    pub int_grids: HashMap<LayerIid, Entity>,
     pub background: Entity,
 }
 
 impl LdtkLoadedLevel {
     pub fn unload(&self, commands: &mut Commands, global_entities: &LdtkGlobalEntityRegistry) {
-        self.layers.values().for_each(|e| {
-            commands.entity(*e).insert(LdtkUnloadLayer);
-        });
+        self.layers
+            .values()
+            .chain(self.int_grids.values())
+            .for_each(|e| {
+                commands.entity(*e).insert(LdtkUnloadLayer);
+            });
         self.entities
             .iter()
             .filter(|(iid, _)| !global_entities.contains(iid))
@@ -59,6 +110,77 @@ pub struct LdtkTempTransform {
 #[derive(Component, Reflect)]
 pub struct GlobalEntity;
 
+/// The entities referenced by the `EntityRef` fields of an ldtk entity or level.
+///
+/// The references are resolved again whenever ldtk entities are spawned or despawned,
+/// so references to the levels that are not loaded yet will be resolved after they are loaded.
+#[derive(Component, Debug, Clone, Reflect)]
+pub struct LdtkEntityRefs {
+    /// field identifier to the references
+    pub refs: HashMap<String, Vec<EntityRef>>,
+    /// field identifier to the referenced entities.
+    /// `None` if the entity is not spawned.
+    pub entities: HashMap<String, Vec<Option<Entity>>>,
+}
+
+impl LdtkEntityRefs {
+    /// Returns `None` if there's no `EntityRef` field.
+    pub fn new(fields: &HashMap<String, FieldInstance>) -> Option<Self> {
+        let refs = fields
+            .iter()
+            .filter_map(|(ident, field)| match &field.value {
+                Some(FieldValue::EntityRef(r)) => Some((ident.clone(), vec![r.clone()])),
+                Some(FieldValue::EntityRefArray(r)) => Some((ident.clone(), r.clone())),
+                _ => None,
+            })
+            .collect::<HashMap<_, _>>();
+
+        if refs.is_empty() {
+            return None;
+        }
+
+        Some(Self {
+            entities: refs
+                .iter()
+                .map(|(ident, r)| (ident.clone(), vec![None; r.len()]))
+                .collect(),
+            refs,
+        })
+    }
+
+    /// Get the first entity referenced by the field.
+    #[inline]
+    pub fn get(&self, field: &str) -> Option<Entity> {
+        self.entities
+            .get(field)
+            .and_then(|entities| entities.first().copied().flatten())
+    }
+
+    /// Get all the entities referenced by the field.
+    #[inline]
+    pub fn get_all(&self, field: &str) -> &[Option<Entity>] {
+        self.entities.get(field).map_or(&[], |entities| entities)
+    }
+
+    /// All the references are resolved to spawned entities.
+    pub fn is_resolved(&self) -> bool {
+        self.entities
+            .values()
+            .all(|entities| entities.iter().all(|e| e.is_some()))
+    }
+
+    pub(crate) fn resolve(&mut self, entities: &HashMap<&EntityIid, Entity>) {
+        for (ident, refs) in self.refs.iter() {
+            let Some(resolved) = self.entities.get_mut(ident) else {
+                continue;
+            };
+            refs.iter().zip(resolved.iter_mut()).for_each(|(r, e)| {
+                *e = entities.get(&EntityIid(r.entity_iid.clone())).copied();
+            });
+        }
+    }
+}
+
 #[derive(Component, Debug, Reflect, Hash, Eq, PartialEq, Clone)]
 pub struct EntityIid(pub String);
 
@@ -0,0 +1,68 @@
+use std::fmt::Display;
+
+use bevy::reflect::Reflect;
+
+/// The reasons a level, or a part of it, failed to load.
+///
+/// Errors of layers and entities don't stop the rest of the level from loading.
+#[derive(Debug, Clone, Reflect)]
+pub enum LdtkError {
+    /// The ldtk file at the path could not be loaded.
+    JsonNotLoaded(String),
+    /// The external level file of the level could not be loaded.
+    LevelNotLoaded(String),
+    /// There's no level with the identifier or iid.
+    LevelNotFound(String),
+    /// The tileset used by the layer doesn't exist or has no image.
+    MissingTileset { layer: String, tileset_uid: i32 },
+    /// The tileset used by the sprite of the entity doesn't exist or has no image.
+    MissingEntityTileset { entity: String, tileset_uid: i32 },
+    /// The layer is used as a path layer but it's not an IntGrid layer.
+    NotIntGridLayer(String),
+    /// The entity is not registered using `App::register_ldtk_entity`.
+    UnregisteredEntity(String),
+    /// The tag of the entity is not registered using `App::register_ldtk_entity_tag`.
+    UnregisteredEntityTag { entity: String, tag: String },
+}
+
+impl Display for LdtkError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LdtkError::JsonNotLoaded(path) => write!(f, "Could not load the ldtk file {:?}!", path),
+            LdtkError::LevelNotLoaded(level) => {
+                write!(f, "Could not load the level file of {:?}!", level)
+            }
+            LdtkError::LevelNotFound(level) => write!(f, "Could not find the level {:?}!", level),
+            LdtkError::MissingTileset { layer, tileset_uid } => write!(
+                f,
+                "Could not find the tileset {} of the layer {:?}!",
+                tileset_uid, layer
+            ),
+            LdtkError::MissingEntityTileset {
+                entity,
+                tileset_uid,
+            } => write!(
+                f,
+                "Could not find the tileset {} of the entity {:?}!",
+                tileset_uid, entity
+            ),
+            LdtkError::NotIntGridLayer(layer) => {
+                write!(f, "The path layer {:?} is not an IntGrid layer!", layer)
+            }
+            LdtkError::UnregisteredEntity(entity) => write!(
+                f,
+                "Could not find entity type with entity identifier: {}! \
+                You need to register it using App::register_ldtk_entity::<T>() first!",
+                entity
+            ),
+            LdtkError::UnregisteredEntityTag { entity, tag } => write!(
+                f,
+                "Could not find entity tag with tag: {} of the entity {}! \
+                You need to register it using App::register_ldtk_entity_tag::<T>() first!",
+                tag, entity
+            ),
+        }
+    }
+}
+
+impl std::error::Error for LdtkError {}
@@ -1,9 +1,15 @@
 use bevy::{ecs::event::Event, reflect::Reflect};
 
+use super::error::LdtkError;
+
 #[derive(Event)]
 pub enum LdtkEvent {
     LevelLoaded(LevelEvent),
     LevelUnloaded(LevelEvent),
+    /// The level, or some of its layers or entities, failed to load.
+    ///
+    /// `LevelLoaded` is still sent if only some layers or entities failed.
+    LoadFailed(LoadFailedEvent),
 }
 
 #[derive(Reflect, Debug, Clone)]
@@ -11,3 +17,10 @@ pub struct LevelEvent {
     pub identifier: String,
     pub iid: String,
 }
+
+#[derive(Reflect, Debug, Clone)]
+pub struct LoadFailedEvent {
+    /// The identifier or iid of the level, the same as it's passed to `LdtkLevelManager::load`.
+    pub level: String,
+    pub error: LdtkError,
+}
@@ -3,7 +3,7 @@ use serde::{de::Visitor, Deserialize, Serialize};
 
 use crate::ldtk::sprite::{NineSliceBorders, TileRenderMode};
 
-#[derive(Serialize, Deserialize, Debug, Clone, Reflect)]
+#[derive(Serialize, Deserialize, Debug, Clone, Reflect, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct Definitions {
     /// All entities definitions, including their custom fields
@@ -27,13 +27,16 @@ pub struct Definitions {
  * Layer Definition
  */
 
-#[derive(Serialize, Deserialize, Debug, Clone, Reflect)]
+#[derive(Serialize, Deserialize, Debug, Clone, Reflect, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct LayerDef {
     /// Type of the layer (IntGrid, Entities, Tiles or AutoLayer)
     #[serde(rename = "__type")]
     pub ty: LayerType,
 
+    /// Contains all the auto-layer rule definitions.
+    pub auto_rule_groups: Vec<AutoRuleGroup>,
+
     pub auto_source_layer_def_uid: Option<i32>,
 
     /// Opacity of the layer (0 to 1.0)
@@ -96,7 +99,7 @@ pub enum LayerType {
     AutoLayer,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone, Reflect)]
+#[derive(Serialize, Deserialize, Debug, Clone, Reflect, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct IntGridValue {
     pub color: String,
@@ -106,7 +109,7 @@ pub struct IntGridValue {
     pub value: i32,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone, Reflect)]
+#[derive(Serialize, Deserialize, Debug, Clone, Reflect, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct IntGroupValueGroup {
     /// User defined color
@@ -119,11 +122,132 @@ pub struct IntGroupValueGroup {
     pub uid: i32,
 }
 
+#[derive(Serialize, Deserialize, Debug, Clone, Reflect, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct AutoRuleGroup {
+    pub active: bool,
+
+    /// Optional groups are only enabled in the layer instances that list them
+    /// in `optionalRules`.
+    pub is_optional: bool,
+
+    pub name: String,
+
+    pub rules: Vec<AutoRuleDef>,
+
+    pub uid: i32,
+}
+
+/// This complex section isn't meant to be used by game devs at all,
+/// as these rules are completely resolved internally by the editor before any saving.
+/// You should just ignore this part.
+#[derive(Serialize, Deserialize, Debug, Clone, Reflect, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct AutoRuleDef {
+    /// If FALSE, the rule effect isn't applied, and no tiles are generated.
+    pub active: bool,
+
+    pub alpha: f32,
+
+    /// When TRUE, the rule will prevent other rules to be applied in the same cell if it matches
+    /// (TRUE by default).
+    pub break_on_match: bool,
+
+    /// Chances for this rule to be applied (0 to 1)
+    pub chance: f32,
+
+    /// Checker mode Possible values: `None`, `Horizontal`, `Vertical`
+    pub checker: AutoRuleChecker,
+
+    /// If TRUE, allow rule to be matched by flipping its pattern horizontally
+    pub flip_x: bool,
+
+    /// If TRUE, allow rule to be matched by flipping its pattern vertically
+    pub flip_y: bool,
+
+    /// If TRUE, then the rule should be re-evaluated by the editor at one point
+    #[serde(default)]
+    pub invalidated: bool,
+
+    /// Default IntGrid value when checking cells outside of level bounds
+    pub out_of_bounds_value: Option<i32>,
+
+    /// Rule pattern (size x size)
+    pub pattern: Vec<i32>,
+
+    /// If TRUE, enable Perlin filtering to only apply rule on specific random area
+    pub perlin_active: bool,
+
+    /// X pivot of a tile stamp (0-1)
+    pub pivot_x: f32,
+
+    /// Y pivot of a tile stamp (0-1)
+    pub pivot_y: f32,
+
+    /// Pattern width & height. Should only be 1,3,5 or 7.
+    pub size: i32,
+
+    /// Defines how tileIds array is used Possible values: `Single`, `Stamp`
+    pub tile_mode: AutoRuleTileMode,
+
+    /// Array containing all the possible tile IDs rectangles (picked randomly).
+    /// Projects saved before 1.5.0 use `tileIds` instead.
+    #[serde(default)]
+    pub tile_rects_ids: Vec<Vec<i32>>,
+
+    /// Array of all the tile IDs. They are used randomly or as stamps,
+    /// based on `tileMode` value.
+    #[serde(default)]
+    pub tile_ids: Vec<i32>,
+
+    /// Unique Int identifier
+    pub uid: i32,
+
+    /// X cell coord modulo
+    pub x_modulo: i32,
+
+    /// X cell start offset
+    pub x_offset: i32,
+
+    /// Y cell coord modulo
+    pub y_modulo: i32,
+
+    /// Y cell start offset
+    pub y_offset: i32,
+}
+
+impl AutoRuleDef {
+    /// The tile rectangles of the rule, for projects saved in any version.
+    pub fn get_tile_rects(&self) -> Vec<Vec<i32>> {
+        if !self.tile_rects_ids.is_empty() {
+            return self.tile_rects_ids.clone();
+        }
+
+        match self.tile_mode {
+            AutoRuleTileMode::Single => self.tile_ids.iter().map(|id| vec![*id]).collect(),
+            AutoRuleTileMode::Stamp => vec![self.tile_ids.clone()],
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Reflect)]
+pub enum AutoRuleChecker {
+    None,
+    Horizontal,
+    Vertical,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Reflect)]
+pub enum AutoRuleTileMode {
+    Single,
+    Stamp,
+}
+
 /*
  * Entity Definition
  */
 
-#[derive(Serialize, Deserialize, Debug, Clone, Reflect)]
+#[derive(Serialize, Deserialize, Debug, Clone, Reflect, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct EntityDef {
     /// Base entity color
@@ -215,7 +339,7 @@ impl<'de> Visitor<'de> for NineSliceBordersVisitor {
  * Tileset Definition
  */
 
-#[derive(Serialize, Deserialize, Debug, Clone, Reflect)]
+#[derive(Serialize, Deserialize, Debug, Clone, Reflect, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct TilesetDef {
     /// Grid-based height
@@ -269,21 +393,21 @@ pub struct TilesetDef {
     pub uid: i32,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone, Reflect)]
+#[derive(Serialize, Deserialize, Debug, Clone, Reflect, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct CustomData {
     pub data: String,
     pub tile_id: i32,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone, Reflect)]
+#[derive(Serialize, Deserialize, Debug, Clone, Reflect, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct EnumTag {
     pub enum_value_id: String,
     pub tile_ids: Vec<i32>,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone, Reflect)]
+#[derive(Serialize, Deserialize, Debug, Clone, Reflect, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct TilesetRect {
     /// UID of the tileset
@@ -306,7 +430,7 @@ pub struct TilesetRect {
     pub height: i32,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone, Reflect)]
+#[derive(Serialize, Deserialize, Debug, Clone, Reflect, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct EnumTagValue {
     pub tile_ids: Vec<i32>,
@@ -317,7 +441,7 @@ pub struct EnumTagValue {
  * Enum Definition
  */
 
-#[derive(Serialize, Deserialize, Debug, Clone, Reflect)]
+#[derive(Serialize, Deserialize, Debug, Clone, Reflect, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct EnumDef {
     /// Relative path to the external file providing this Enum
@@ -339,7 +463,7 @@ pub struct EnumDef {
     pub values: Vec<EnumValue>,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone, Reflect)]
+#[derive(Serialize, Deserialize, Debug, Clone, Reflect, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct EnumValue {
     /// Optional color
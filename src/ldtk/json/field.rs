@@ -8,7 +8,7 @@ use crate::{ldtk::json::LdtkColor, match_field, match_field_enum, transfer_field
 
 use super::{definitions::TilesetRect, EntityRef, GridPoint};
 
-#[derive(Serialize, Debug, Clone, Reflect)]
+#[derive(Serialize, Debug, Clone, Reflect, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct FieldInstance {
     /// Reference of the Field definition UID
@@ -184,7 +184,7 @@ impl<'de> Deserialize<'de> for FieldInstance {
 /// - For Point, the value is a GridPoint object.
 /// - For Tile, the value is a TilesetRect object.
 /// - For EntityRef, the value is an EntityReferenceInfos object.
-#[derive(Serialize, Deserialize, Debug, Clone, Reflect)]
+#[derive(Serialize, Deserialize, Debug, Clone, Reflect, PartialEq)]
 #[serde(untagged)]
 pub enum FieldValue {
     Integer(i32),
@@ -1,14 +1,14 @@
 use bevy::{
-    ecs::system::EntityCommands, reflect::Reflect, sprite::MaterialMesh2dBundle,
-    transform::components::Transform,
+    asset::Asset, ecs::system::EntityCommands, reflect::Reflect, sprite::MaterialMesh2dBundle,
+    transform::components::Transform, utils::HashMap,
 };
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize};
 
 use crate::ldtk::resources::LdtkAssets;
 
 use super::{
     definitions::{LayerType, TilesetRect},
-    field::FieldInstance,
+    field::{FieldInstance, FieldValue},
     LdtkColor,
 };
 
@@ -16,7 +16,7 @@ use super::{
  * Level
  */
 
-#[derive(Serialize, Deserialize, Debug, Clone, Reflect)]
+#[derive(Asset, Serialize, Deserialize, Debug, Clone, Reflect, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct Level {
     /// Background color of the level (same as `bgColor`, except
@@ -58,10 +58,11 @@ pub struct Level {
     /// An array containing all Layer instances.
     /// ## IMPORTANT:
     /// if the project option "Save levels separately" is enabled,
-    /// this field will be null.
+    /// this field will be empty. Use `LdtkLevelManager::get_level` instead.
     ///
     /// This array is **sorted in display order**: the 1st layer is
     /// the top-most and the last is behind.
+    #[serde(deserialize_with = "null_as_empty")]
     pub layer_instances: Vec<LayerInstance>,
 
     /// Height of the level in pixels
@@ -95,7 +96,13 @@ pub struct Level {
     pub world_y: i32,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone, Reflect)]
+fn null_as_empty<'de, D: Deserializer<'de>, T: Deserialize<'de>>(
+    deserializer: D,
+) -> Result<Vec<T>, D::Error> {
+    Ok(Option::<Vec<T>>::deserialize(deserializer)?.unwrap_or_default())
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Reflect, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct ImagePosition {
     /// An array of 4 float values describing the cropped sub-rectangle
@@ -114,7 +121,7 @@ pub struct ImagePosition {
     pub top_left_px: [i32; 2],
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone, Reflect)]
+#[derive(Serialize, Deserialize, Debug, Clone, Reflect, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct Neighbour {
     /// A single lowercase character tipping on the level location
@@ -133,7 +140,7 @@ pub struct Neighbour {
     pub level_iid: String,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone, Reflect)]
+#[derive(Serialize, Deserialize, Debug, Clone, Reflect, PartialEq)]
 pub enum NeighbourDirection {
     #[serde(rename = "n")]
     North,
@@ -163,7 +170,7 @@ pub enum NeighbourDirection {
  * Layer Instance
  */
 
-#[derive(Serialize, Deserialize, Debug, Clone, Reflect)]
+#[derive(Serialize, Deserialize, Debug, Clone, Reflect, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct LayerInstance {
     /// Grid-based height
@@ -236,6 +243,10 @@ pub struct LayerInstance {
     /// Reference to the UID of the level containing this layer instance
     pub level_id: i32,
 
+    /// An Array containing the UIDs of optional rules that were enabled in this specific layer instance.
+    #[serde(default)]
+    pub optional_rules: Vec<i32>,
+
     /// This layer can use another tileset by overriding the tileset UID here.
     pub override_tileset_uid: Option<i32>,
 
@@ -253,11 +264,76 @@ pub struct LayerInstance {
     /// which contains the total offset value
     pub px_offset_y: i32,
 
+    /// Random seed used for Auto-Layers rendering
+    #[serde(default)]
+    pub seed: i32,
+
     /// Layer instance visibility
     pub visible: bool,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone, Reflect)]
+impl LayerInstance {
+    /// Get the metadata of the layer as fields, named after the fields of `LayerInstance`.
+    /// These are used by `#[derive(LdtkLayer)]`, as layers don't have custom fields.
+    pub fn get_metadata_fields(&self) -> HashMap<String, FieldInstance> {
+        let field = |name: &str, value: Option<FieldValue>| {
+            (
+                name.to_string(),
+                FieldInstance {
+                    def_uid: self.layer_def_uid,
+                    identifier: name.to_string(),
+                    tile: None,
+                    value,
+                },
+            )
+        };
+
+        [
+            field(
+                "identifier",
+                Some(FieldValue::String(self.identifier.clone())),
+            ),
+            field("iid", Some(FieldValue::String(self.iid.clone()))),
+            field("c_wid", Some(FieldValue::Integer(self.c_wid))),
+            field("c_hei", Some(FieldValue::Integer(self.c_hei))),
+            field("grid_size", Some(FieldValue::Integer(self.grid_size))),
+            field("opacity", Some(FieldValue::Float(self.opacity))),
+            field("visible", Some(FieldValue::Bool(self.visible))),
+            field("px_offset_x", Some(FieldValue::Integer(self.px_offset_x))),
+            field("px_offset_y", Some(FieldValue::Integer(self.px_offset_y))),
+            field(
+                "px_total_offset_x",
+                Some(FieldValue::Integer(self.px_total_offset_x)),
+            ),
+            field(
+                "px_total_offset_y",
+                Some(FieldValue::Integer(self.px_total_offset_y)),
+            ),
+            field(
+                "layer_def_uid",
+                Some(FieldValue::Integer(self.layer_def_uid)),
+            ),
+            field("level_id", Some(FieldValue::Integer(self.level_id))),
+            field("seed", Some(FieldValue::Integer(self.seed))),
+            field(
+                "tileset_def_uid",
+                self.tileset_def_uid.map(FieldValue::Integer),
+            ),
+            field(
+                "tileset_rel_path",
+                self.tileset_rel_path.clone().map(FieldValue::String),
+            ),
+            field(
+                "override_tileset_uid",
+                self.override_tileset_uid.map(FieldValue::Integer),
+            ),
+        ]
+        .into_iter()
+        .collect()
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Reflect, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct TileInstance {
     ///	Alpha/opacity of the tile (0-1, defaults to 1)
@@ -290,7 +366,7 @@ pub struct TileInstance {
  * Entity Instance
  */
 
-#[derive(Serialize, Deserialize, Debug, Clone, Reflect)]
+#[derive(Serialize, Deserialize, Debug, Clone, Reflect, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct EntityInstance {
     /// Grid-based coordinates (`[x,y]` format)
@@ -353,13 +429,16 @@ pub struct EntityInstance {
 
 impl EntityInstance {
     pub fn generate_sprite(&self, commands: &mut EntityCommands, assets: &LdtkAssets) {
-        if self.tile.is_none() {
+        let (Some(mesh), Some(material)) = (
+            assets.clone_mesh_handle(&self.iid),
+            assets.clone_material_handle(&self.iid),
+        ) else {
             return;
-        }
+        };
 
         commands.insert(MaterialMesh2dBundle {
-            mesh: assets.clone_mesh_handle(&self.iid),
-            material: assets.clone_material_handle(&self.iid),
+            mesh,
+            material,
             transform: Transform::from_xyz(self.local_pos[0] as f32, -self.local_pos[1] as f32, 0.),
             ..Default::default()
         });
@@ -1,4 +1,4 @@
-use bevy::{math::Vec4, reflect::Reflect, render::color::Color, utils::HashMap};
+use bevy::{asset::Asset, math::Vec4, reflect::Reflect, render::color::Color, utils::HashMap};
 use serde::{de::Visitor, Deserialize, Serialize};
 
 use self::{definitions::Definitions, level::Level};
@@ -8,7 +8,7 @@ pub mod field;
 pub mod level;
 pub mod macros;
 
-#[derive(Serialize, Debug, Clone, Copy, Reflect)]
+#[derive(Serialize, Debug, Clone, Copy, Reflect, PartialEq)]
 pub struct LdtkColor {
     pub r: f32,
     pub g: f32,
@@ -62,7 +62,7 @@ impl<'de> Visitor<'de> for LdtkColorVisitor {
     }
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone, Reflect)]
+#[derive(Asset, Serialize, Deserialize, Debug, Clone, Reflect, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct LdtkJson {
     /// Project background color
@@ -75,6 +75,10 @@ pub struct LdtkJson {
     /// and one file in a sub-folder for each level.
     pub external_levels: bool,
 
+    /// The iid of the world that contains the `levels` if Multi-worlds is disabled.
+    #[serde(default)]
+    pub dummy_world_iid: String,
+
     ///	Unique project identifier
     pub iid: String,
 
@@ -129,7 +133,72 @@ pub struct LdtkJson {
     pub worlds: Vec<World>,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone, Reflect)]
+impl LdtkJson {
+    /// Iterate over the levels of all the worlds.
+    pub fn iter_levels(&self) -> impl Iterator<Item = &Level> {
+        self.levels
+            .iter()
+            .chain(self.worlds.iter().flat_map(|world| world.levels.iter()))
+    }
+
+    /// Find a level by its identifier or iid. All the worlds are searched if `world` is `None`,
+    /// otherwise only the world with the identifier or iid.
+    ///
+    /// Returns the world, which is `None` if Multi-worlds is disabled,
+    /// and the index of the level in that world.
+    pub fn find_level(&self, world: Option<&str>, level: &str) -> Option<(Option<&World>, usize)> {
+        let (world, index) = self.find_level_index(world, level)?;
+        Some((world.map(|world| &self.worlds[world]), index))
+    }
+
+    /// Find a level like `find_level`, but returns the index of the world.
+    pub fn find_level_index(
+        &self,
+        world: Option<&str>,
+        level: &str,
+    ) -> Option<(Option<usize>, usize)> {
+        let find = |levels: &Vec<Level>| {
+            levels
+                .iter()
+                .position(|l| l.identifier == level || l.iid == level)
+        };
+
+        if world.map_or(true, |w| w == self.dummy_world_iid) {
+            if let Some(index) = find(&self.levels) {
+                return Some((None, index));
+            }
+        }
+
+        self.worlds
+            .iter()
+            .enumerate()
+            .filter(|(_, w)| world.map_or(true, |world| w.identifier == world || w.iid == world))
+            .find_map(|(i, w)| find(&w.levels).map(|index| (Some(i), index)))
+    }
+
+    /// Find a level like `find_level` and get it.
+    pub fn get_level(&self, world: Option<&str>, level: &str) -> Option<&Level> {
+        let (world, index) = self.find_level(world, level)?;
+        Some(&self.get_world(world).0[index])
+    }
+
+    /// Get the levels and the layout of the world, or the root of the project if `world` is `None`.
+    pub fn get_world<'a>(
+        &'a self,
+        world: Option<&'a World>,
+    ) -> (&'a Vec<Level>, Option<WorldLayout>) {
+        match world {
+            Some(world) => (&world.levels, world.world_layout),
+            None => (&self.levels, self.world_layout),
+        }
+    }
+
+    pub fn get_world_iid<'a>(&'a self, world: Option<&'a World>) -> &'a str {
+        world.map_or(&self.dummy_world_iid, |world| &world.iid)
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Reflect, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct Toc {
     pub identifier: String,
@@ -139,7 +208,7 @@ pub struct Toc {
     pub instances_data: Vec<TocInstance>,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone, Reflect)]
+#[derive(Serialize, Deserialize, Debug, Clone, Reflect, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct TocInstance {
     /// IID information of this instance
@@ -159,7 +228,7 @@ pub struct TocInstance {
     pub untyped_fields: HashMap<String, TocField>,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone, Reflect)]
+#[derive(Serialize, Deserialize, Debug, Clone, Reflect, PartialEq)]
 #[serde(untagged)]
 pub enum TocField {
     Integer(i32),
@@ -179,7 +248,7 @@ pub enum TocField {
     EntityRefArray(Vec<EntityRef>),
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone, Copy, Reflect)]
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Reflect, PartialEq)]
 pub enum WorldLayout {
     Free,
     GridVania,
@@ -187,7 +256,7 @@ pub enum WorldLayout {
     LinearVertical,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone, Reflect)]
+#[derive(Serialize, Deserialize, Debug, Clone, Reflect, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct World {
     /// Width of the world grid in pixels.
@@ -230,7 +299,7 @@ pub struct EntityRef {
     pub world_iid: String,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone, Reflect)]
+#[derive(Serialize, Deserialize, Debug, Clone, Reflect, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct GridPoint {
     /// X grid-based coordinate
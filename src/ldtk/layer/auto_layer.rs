@@ -0,0 +1,525 @@
+use bevy::{
+    ecs::{
+        change_detection::DetectChangesMut,
+        component::Component,
+        entity::Entity,
+        query::Changed,
+        system::{Commands, Query},
+    },
+    math::{IVec2, Vec4},
+    reflect::Reflect,
+    utils::{HashMap, HashSet},
+};
+
+use crate::{
+    ldtk::json::{
+        definitions::{AutoRuleChecker, AutoRuleDef, LayerDef},
+        level::LayerInstance,
+    },
+    math::aabb::IAabb2d,
+    tilemap::{
+        map::{TilemapLayers, TilemapStorage, TilemapTexture},
+        tile::{TileBuilder, TileLayer},
+    },
+    DEFAULT_LAYER_COUNT,
+};
+
+use super::int_grid::{ldtk_to_index, LdtkIntGrid};
+
+/// Matches any non-empty cell, or only empty cells if it's negative.
+const PATTERN_ANYTHING: i32 = 1000001;
+/// Pattern values larger than this refer to the groups of the IntGrid values.
+const PATTERN_GROUP_MULTIPLIER: i32 = 1000;
+
+/// The auto-layer rules of a tile layer.
+///
+/// The rules are applied again around the cells changed using `LdtkIntGrid::set`,
+/// and the tiles of the tilemap are updated.
+///
+/// Perlin filters, biomes and the pixel offsets of the tiles are not supported.
+#[derive(Component, Debug, Clone, Reflect)]
+pub struct LdtkAutoLayer {
+    /// The entity with the `LdtkIntGrid` the rules are applied to.
+    pub source: Entity,
+    /// The enabled rules, in the order they are applied.
+    pub rules: Vec<AutoRuleDef>,
+    pub seed: i32,
+    pub grid_size: i32,
+    pub tile_size: IVec2,
+    pub tileset_columns: i32,
+}
+
+impl LdtkAutoLayer {
+    pub fn new(
+        layer: &LayerInstance,
+        layer_def: &LayerDef,
+        source: Entity,
+        tileset: &TilemapTexture,
+    ) -> Self {
+        let rules = layer_def
+            .auto_rule_groups
+            .iter()
+            .filter(|group| {
+                group.active && (!group.is_optional || layer.optional_rules.contains(&group.uid))
+            })
+            .flat_map(|group| group.rules.iter())
+            .filter(|rule| rule.active && !rule.invalidated)
+            .cloned()
+            .collect();
+
+        Self {
+            source,
+            rules,
+            seed: layer.seed,
+            grid_size: layer.grid_size,
+            tile_size: tileset.desc.tile_size.as_ivec2(),
+            tileset_columns: (tileset.desc.size.x / tileset.desc.tile_size.x) as i32,
+        }
+    }
+
+    /// Apply the rules around the changed cells and update the tiles.
+    ///
+    /// Returns the largest number of layers of the updated tiles.
+    pub fn apply(
+        &self,
+        commands: &mut Commands,
+        int_grid: &LdtkIntGrid,
+        changed: &[IVec2],
+        storage: &mut TilemapStorage,
+    ) -> usize {
+        let Some(first) = changed.first() else {
+            return 0;
+        };
+
+        // `ldtk_to_index` also converts the indices back to the cells
+        let mut dirty = IAabb2d::splat(ldtk_to_index(*first));
+        changed
+            .iter()
+            .for_each(|index| dirty.expand_to_contain(ldtk_to_index(*index)));
+
+        let radius = self
+            .rules
+            .iter()
+            .map(|r| r.size / 2)
+            .max()
+            .unwrap_or_default();
+        let extent = self.get_stamp_extent();
+        let bounds = IAabb2d {
+            min: IVec2::ZERO,
+            max: int_grid.size - IVec2::ONE,
+        };
+        // the cells whose tiles may change
+        let affected = expand(dirty, radius + extent).intersection(bounds);
+        let updated = affected
+            .into_iter()
+            .map(|cell| self.get_tile_index(cell))
+            .collect::<HashSet<_>>();
+        // the other cells on the updated tiles, if the tiles are larger than the cells
+        let shared = (self.tile_size / self.grid_size - IVec2::ONE)
+            .max(IVec2::ZERO)
+            .max_element();
+        let covered = expand(affected, shared).intersection(bounds);
+        // the cells whose stamps may cover the updated tiles
+        let evaluated = expand(covered, extent).intersection(bounds);
+
+        let mut tiles = HashMap::<IVec2, Vec<(usize, i32, u32, f32)>>::new();
+        for cell in evaluated.into_iter() {
+            for (order, rule) in self.rules.iter().enumerate() {
+                let Some((flip_x, flip_y)) = self.match_rule(rule, int_grid, cell) else {
+                    continue;
+                };
+
+                let rects = rule.get_tile_rects();
+                if rects.is_empty() {
+                    continue;
+                }
+                let rect = &rects
+                    [rand_seed_coords(self.seed + rule.uid, cell, rects.len() as i32) as usize];
+                let flip = flip_x as u32 | (flip_y as u32) << 1;
+
+                self.get_stamp(rect, rule, flip_x, flip_y)
+                    .into_iter()
+                    .map(|(offset, tile)| (cell + offset, tile))
+                    .filter(|(target, _)| bounds.contains(*target))
+                    .map(|(target, tile)| (self.get_tile_index(target), tile))
+                    .filter(|(index, _)| updated.contains(index))
+                    .for_each(|(index, tile)| {
+                        tiles
+                            .entry(index)
+                            .or_default()
+                            .push((order, tile, flip, rule.alpha));
+                    });
+
+                if rule.break_on_match {
+                    break;
+                }
+            }
+        }
+
+        let mut depth = 0;
+        for index in updated {
+            let Some(mut cell_tiles) = tiles.remove(&index) else {
+                storage.remove(commands, index);
+                continue;
+            };
+
+            // the tiles of the first rules are on the top
+            cell_tiles.sort_by(|a, b| b.0.cmp(&a.0).then(a.1.cmp(&b.1)).then(a.2.cmp(&b.2)));
+            // the cells sharing the tile can stamp the same tile
+            cell_tiles.dedup_by(|a, b| (a.0, a.1, a.2) == (b.0, b.1, b.2));
+            depth = depth.max(cell_tiles.len());
+
+            let builder = cell_tiles.iter().enumerate().fold(
+                TileBuilder::new().with_color(Vec4::new(1., 1., 1., cell_tiles[0].3)),
+                |builder, (layer, (_, tile, flip, _))| {
+                    builder.with_layer(
+                        layer,
+                        TileLayer::new()
+                            .with_texture_index(*tile as u32)
+                            .with_flip_raw(*flip),
+                    )
+                },
+            );
+            storage.set(commands, index, builder);
+        }
+
+        depth
+    }
+
+    /// The index of the tile the cell is on.
+    fn get_tile_index(&self, cell: IVec2) -> IVec2 {
+        IVec2::new(
+            cell.x * self.grid_size / self.tile_size.x,
+            -(cell.y * self.grid_size / self.tile_size.y) - 1,
+        )
+    }
+
+    /// Returns the flips the pattern matches with.
+    fn match_rule(
+        &self,
+        rule: &AutoRuleDef,
+        int_grid: &LdtkIntGrid,
+        cell: IVec2,
+    ) -> Option<(bool, bool)> {
+        if !is_on_modulo(rule, cell) {
+            return None;
+        }
+
+        if rule.chance < 1.
+            && rand_seed_coords(self.seed + rule.uid, cell, 100) as f32 >= rule.chance * 100.
+        {
+            return None;
+        }
+
+        [(false, false), (true, false), (false, true), (true, true)]
+            .into_iter()
+            .filter(|(x, y)| (!x || rule.flip_x) && (!y || rule.flip_y))
+            .find(|(x, y)| matches_pattern(rule, int_grid, cell, *x, *y))
+    }
+
+    /// The tiles of the rectangle and their offsets to the cell.
+    fn get_stamp(
+        &self,
+        rect: &[i32],
+        rule: &AutoRuleDef,
+        flip_x: bool,
+        flip_y: bool,
+    ) -> Vec<(IVec2, i32)> {
+        let coords = rect
+            .iter()
+            .map(|tile| IVec2::new(tile % self.tileset_columns, tile / self.tileset_columns))
+            .collect::<Vec<_>>();
+        let min = coords.iter().fold(IVec2::MAX, |acc, c| acc.min(*c));
+        let max = coords.iter().fold(IVec2::MIN, |acc, c| acc.max(*c));
+        let size = (max - min).as_vec2();
+        let pivot = IVec2::new(
+            (rule.pivot_x * size.x) as i32,
+            (rule.pivot_y * size.y) as i32,
+        );
+
+        coords
+            .into_iter()
+            .zip(rect.iter())
+            .map(|(coord, tile)| {
+                let mut offset = coord - min - pivot;
+                if flip_x {
+                    offset.x = -offset.x;
+                }
+                if flip_y {
+                    offset.y = -offset.y;
+                }
+                (offset, *tile)
+            })
+            .collect()
+    }
+
+    /// How far the stamps can reach from the cell.
+    fn get_stamp_extent(&self) -> i32 {
+        self.rules
+            .iter()
+            .flat_map(|rule| rule.get_tile_rects())
+            .map(|rect| {
+                let xs = rect.iter().map(|tile| tile % self.tileset_columns);
+                let ys = rect.iter().map(|tile| tile / self.tileset_columns);
+                let width = xs.clone().max().unwrap_or_default() - xs.min().unwrap_or_default();
+                let height = ys.clone().max().unwrap_or_default() - ys.min().unwrap_or_default();
+                width.max(height)
+            })
+            .max()
+            .unwrap_or_default()
+    }
+}
+
+fn expand(aabb: IAabb2d, amount: i32) -> IAabb2d {
+    IAabb2d {
+        min: aabb.min - amount,
+        max: aabb.max + amount,
+    }
+}
+
+fn is_on_modulo(rule: &AutoRuleDef, cell: IVec2) -> bool {
+    let x_modulo = rule.x_modulo.max(1);
+    let y_modulo = rule.y_modulo.max(1);
+
+    let x = match rule.checker {
+        AutoRuleChecker::Horizontal => cell.x + (cell.y / y_modulo) % 2,
+        _ => cell.x,
+    };
+    let y = match rule.checker {
+        AutoRuleChecker::Vertical => cell.y + (cell.x / x_modulo) % 2,
+        _ => cell.y,
+    };
+
+    (x - rule.x_offset).rem_euclid(x_modulo) == 0 && (y - rule.y_offset).rem_euclid(y_modulo) == 0
+}
+
+fn matches_pattern(
+    rule: &AutoRuleDef,
+    int_grid: &LdtkIntGrid,
+    cell: IVec2,
+    flip_x: bool,
+    flip_y: bool,
+) -> bool {
+    let radius = rule.size / 2;
+    let dir = IVec2::new(if flip_x { -1 } else { 1 }, if flip_y { -1 } else { 1 });
+
+    rule.pattern
+        .iter()
+        .enumerate()
+        .filter(|(_, expected)| **expected != 0)
+        .all(|(i, expected)| {
+            let i = i as i32;
+            let target = cell + IVec2::new(i % rule.size - radius, i / rule.size - radius) * dir;
+            let value = if target.cmpge(IVec2::ZERO).all() && target.cmplt(int_grid.size).all() {
+                int_grid.get_ldtk(target)
+            } else if let Some(value) = rule.out_of_bounds_value {
+                value
+            } else {
+                return false;
+            };
+
+            matches_value(*expected, value, int_grid)
+        })
+}
+
+fn matches_value(expected: i32, value: i32, int_grid: &LdtkIntGrid) -> bool {
+    let is_match = match expected.abs() {
+        PATTERN_ANYTHING => value != 0,
+        group if group > PATTERN_GROUP_MULTIPLIER => int_grid
+            .values
+            .get(&value)
+            .is_some_and(|v| v.group_uid == group / PATTERN_GROUP_MULTIPLIER - 1),
+        expected => value == expected,
+    };
+
+    is_match == (expected > 0)
+}
+
+/// A hash of the cell, so the same tiles are picked every time the rules are applied.
+fn rand_seed_coords(seed: i32, cell: IVec2, max: i32) -> i32 {
+    let h = seed
+        .wrapping_add(cell.x.wrapping_mul(374761393))
+        .wrapping_add(cell.y.wrapping_mul(668265263));
+    let h = (h ^ (h >> 13)).wrapping_mul(1274126177);
+    ((h ^ (h >> 16)).unsigned_abs() % max as u32) as i32
+}
+
+pub fn ldtk_auto_layer_updater(
+    mut commands: Commands,
+    mut int_grids_query: Query<(Entity, &mut LdtkIntGrid), Changed<LdtkIntGrid>>,
+    mut auto_layers_query: Query<(
+        Entity,
+        &LdtkAutoLayer,
+        &mut TilemapStorage,
+        Option<&mut TilemapLayers>,
+    )>,
+) {
+    for (entity, mut int_grid) in int_grids_query.iter_mut() {
+        if int_grid.changed.is_empty() {
+            continue;
+        }
+        let changed = std::mem::take(&mut int_grid.bypass_change_detection().changed);
+
+        auto_layers_query
+            .iter_mut()
+            .filter(|(_, auto_layer, ..)| auto_layer.source == entity)
+            .for_each(|(tilemap, auto_layer, mut storage, layers)| {
+                let depth = auto_layer.apply(&mut commands, &int_grid, &changed, &mut storage);
+
+                // make room for the stacked tiles, or the layers above the count are not rendered
+                match layers {
+                    Some(mut layers) if layers.count() < depth => layers.set_count(depth),
+                    None if depth > DEFAULT_LAYER_COUNT => {
+                        commands.entity(tilemap).insert(TilemapLayers::new(depth));
+                    }
+                    _ => {}
+                }
+            });
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        ldtk::json::definitions::IntGridValue, tilemap::chunking::storage::ChunkedStorage,
+        DEFAULT_CHUNK_SIZE,
+    };
+
+    use super::*;
+
+    fn create_rule(overrides: &str) -> AutoRuleDef {
+        let json = r#"{
+            "active": true,
+            "alpha": 1,
+            "breakOnMatch": true,
+            "chance": 1,
+            "checker": "None",
+            "flipX": false,
+            "flipY": false,
+            "outOfBoundsValue": null,
+            "pattern": [0,0,0,0,1,0,0,0,0],
+            "perlinActive": false,
+            "pivotX": 0,
+            "pivotY": 0,
+            "size": 3,
+            "tileMode": "Single",
+            "tileRectsIds": [[0]],
+            "uid": 21,
+            "xModulo": 1,
+            "xOffset": 0,
+            "yModulo": 1,
+            "yOffset": 0
+        }"#;
+        let mut rule = serde_json::from_str::<serde_json::Value>(json).unwrap();
+        let overrides = serde_json::from_str::<serde_json::Value>(overrides).unwrap();
+        for (key, value) in overrides.as_object().unwrap() {
+            rule[key] = value.clone();
+        }
+        serde_json::from_value(rule).unwrap()
+    }
+
+    /// A 3x3 grid with a wall of `1`s in the middle column, except for the top cell,
+    /// and a `2` in the group 1 at the top left.
+    fn create_int_grid() -> LdtkIntGrid {
+        let value = |value, group_uid| IntGridValue {
+            color: "#000000".to_string(),
+            group_uid,
+            identifier: None,
+            tile: None,
+            value,
+        };
+
+        let mut storage = ChunkedStorage::new(DEFAULT_CHUNK_SIZE);
+        storage.set_elem(ldtk_to_index(IVec2::new(1, 1)), 1);
+        storage.set_elem(ldtk_to_index(IVec2::new(1, 2)), 1);
+        storage.set_elem(ldtk_to_index(IVec2::new(0, 0)), 2);
+
+        LdtkIntGrid {
+            identifier: "Walls".to_string(),
+            grid_size: 16,
+            size: IVec2::splat(3),
+            values: HashMap::from([(1, value(1, 0)), (2, value(2, 1))]),
+            groups: HashMap::default(),
+            storage,
+            changed: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_rand_seed_coords() {
+        // `M.randSeedCoords` of LDtk
+        assert_eq!(rand_seed_coords(0, IVec2::new(0, 0), 100), 0);
+        assert_eq!(rand_seed_coords(1234, IVec2::new(3, 5), 100), 82);
+        assert_eq!(rand_seed_coords(8745326, IVec2::new(12, 7), 4), 0);
+        assert_eq!(rand_seed_coords(-5, IVec2::new(40, -2), 100), 43);
+        assert_eq!(rand_seed_coords(8745326 + 21, IVec2::new(0, 9), 3), 2);
+    }
+
+    #[test]
+    fn test_is_on_modulo() {
+        let rule = create_rule(r#"{ "xModulo": 3, "xOffset": 1 }"#);
+        assert!(is_on_modulo(&rule, IVec2::new(1, 0)));
+        assert!(is_on_modulo(&rule, IVec2::new(4, 7)));
+        assert!(!is_on_modulo(&rule, IVec2::new(0, 0)));
+        assert!(!is_on_modulo(&rule, IVec2::new(3, 2)));
+
+        // every other cell, shifted by one on every other row
+        let rule = create_rule(r#"{ "xModulo": 2, "checker": "Horizontal" }"#);
+        assert!(is_on_modulo(&rule, IVec2::new(0, 0)));
+        assert!(is_on_modulo(&rule, IVec2::new(2, 0)));
+        assert!(is_on_modulo(&rule, IVec2::new(1, 1)));
+        assert!(!is_on_modulo(&rule, IVec2::new(1, 0)));
+        assert!(!is_on_modulo(&rule, IVec2::new(0, 1)));
+
+        let rule = create_rule(r#"{ "yModulo": 2, "checker": "Vertical" }"#);
+        assert!(is_on_modulo(&rule, IVec2::new(0, 0)));
+        assert!(is_on_modulo(&rule, IVec2::new(1, 1)));
+        assert!(!is_on_modulo(&rule, IVec2::new(1, 0)));
+        assert!(!is_on_modulo(&rule, IVec2::new(0, 1)));
+    }
+
+    #[test]
+    fn test_matches_value() {
+        let int_grid = create_int_grid();
+
+        assert!(matches_value(1, 1, &int_grid));
+        assert!(!matches_value(1, 2, &int_grid));
+        assert!(matches_value(-1, 2, &int_grid));
+        assert!(matches_value(-1, 0, &int_grid));
+
+        assert!(matches_value(PATTERN_ANYTHING, 2, &int_grid));
+        assert!(!matches_value(PATTERN_ANYTHING, 0, &int_grid));
+        assert!(matches_value(-PATTERN_ANYTHING, 0, &int_grid));
+        assert!(!matches_value(-PATTERN_ANYTHING, 1, &int_grid));
+
+        // groups are `(uid + 1) * 1000`
+        assert!(matches_value(2000, 2, &int_grid));
+        assert!(!matches_value(2000, 1, &int_grid));
+        assert!(matches_value(-2000, 1, &int_grid));
+        assert!(!matches_value(-2000, 2, &int_grid));
+    }
+
+    #[test]
+    fn test_matches_pattern() {
+        let int_grid = create_int_grid();
+        let matches = |rule: &AutoRuleDef, cell: IVec2, flip_x: bool, flip_y: bool| {
+            matches_pattern(rule, &int_grid, cell, flip_x, flip_y)
+        };
+        // the top of the wall
+        let rule = create_rule(r#"{ "pattern": [0,-1,0,0,1,0,0,0,0] }"#);
+
+        assert!(matches(&rule, IVec2::new(1, 1), false, false));
+        assert!(!matches(&rule, IVec2::new(1, 2), false, false));
+        assert!(!matches(&rule, IVec2::new(0, 1), false, false));
+
+        // flipped, the cell below is out of bounds
+        assert!(!matches(&rule, IVec2::new(1, 2), false, true));
+        let rule = create_rule(r#"{ "pattern": [0,-1,0,0,1,0,0,0,0], "outOfBoundsValue": 0 }"#);
+        assert!(matches(&rule, IVec2::new(1, 2), false, true));
+
+        // the `2` is at the top left of the wall top, so it is only matched when flipped
+        let rule = create_rule(r#"{ "pattern": [0,0,2,0,1,0,0,0,0] }"#);
+        assert!(!matches(&rule, IVec2::new(1, 1), false, false));
+        assert!(matches(&rule, IVec2::new(1, 1), true, false));
+    }
+}
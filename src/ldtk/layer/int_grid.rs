@@ -0,0 +1,145 @@
+use bevy::{ecs::component::Component, math::IVec2, reflect::Reflect, utils::HashMap};
+
+use crate::{
+    ldtk::json::{
+        definitions::{IntGridValue, IntGroupValueGroup, LayerDef},
+        level::LayerInstance,
+    },
+    tilemap::chunking::storage::ChunkedStorage,
+    DEFAULT_CHUNK_SIZE,
+};
+
+/// The values of an IntGrid layer.
+///
+/// This is inserted on the tilemap of the layer, or on an entity of its own
+/// if the layer has no tiles or the tile layers are merged.
+/// The entities are listed in `LdtkLoadedLevel::int_grids`.
+///
+/// Cells are indexed the same way as the tiles of the tilemap.
+/// Changing the values using `set` will update the tiles generated by the auto-layer rules.
+#[derive(Component, Debug, Clone, Reflect)]
+pub struct LdtkIntGrid {
+    pub identifier: String,
+    pub grid_size: i32,
+    /// width and height in cells
+    pub size: IVec2,
+    /// value to the definition of the value
+    pub values: HashMap<i32, IntGridValue>,
+    /// group uid to the group
+    pub groups: HashMap<i32, IntGroupValueGroup>,
+    pub storage: ChunkedStorage<i32>,
+    /// cells changed since the auto-layer rules were applied
+    pub(crate) changed: Vec<IVec2>,
+}
+
+impl LdtkIntGrid {
+    pub fn new(layer: &LayerInstance, layer_def: &LayerDef) -> Self {
+        let mut storage = ChunkedStorage::new(DEFAULT_CHUNK_SIZE);
+        layer
+            .int_grid_csv
+            .iter()
+            .enumerate()
+            .filter(|(_, value)| **value != 0)
+            .for_each(|(index, value)| {
+                let index = index as i32;
+                storage.set_elem(
+                    ldtk_to_index(IVec2::new(index % layer.c_wid, index / layer.c_wid)),
+                    *value,
+                );
+            });
+
+        Self {
+            identifier: layer.identifier.clone(),
+            grid_size: layer.grid_size,
+            size: IVec2::new(layer.c_wid, layer.c_hei),
+            values: layer_def
+                .int_grid_values
+                .iter()
+                .map(|value| (value.value, value.clone()))
+                .collect(),
+            groups: layer_def
+                .int_grid_values_groups
+                .iter()
+                .map(|group| (group.uid, group.clone()))
+                .collect(),
+            storage,
+            changed: Vec::new(),
+        }
+    }
+
+    /// Set the value of the cell. `0` means empty.
+    pub fn set(&mut self, index: IVec2, value: i32) {
+        if value == 0 {
+            self.storage.remove_elem(index);
+        } else {
+            self.storage.set_elem(index, value);
+        }
+        self.changed.push(index);
+    }
+
+    /// Make the cell empty.
+    #[inline]
+    pub fn remove(&mut self, index: IVec2) {
+        self.set(index, 0);
+    }
+
+    /// Get the value of the cell using the grid coordinates of LDtk,
+    /// where y is down. Empty cells are `0`.
+    #[inline]
+    pub fn get_ldtk(&self, cell: IVec2) -> i32 {
+        self.get(ldtk_to_index(cell)).unwrap_or_default()
+    }
+
+    /// Get the value of the cell. Empty cells are `None`.
+    #[inline]
+    pub fn get(&self, index: IVec2) -> Option<i32> {
+        self.storage.get_elem(index).copied()
+    }
+
+    /// Get the definition of the value in the cell.
+    pub fn get_value_def(&self, index: IVec2) -> Option<&IntGridValue> {
+        self.get(index).and_then(|value| self.values.get(&value))
+    }
+
+    /// Get the identifier of the value in the cell.
+    pub fn get_identifier(&self, index: IVec2) -> Option<&str> {
+        self.get_value_def(index)
+            .and_then(|value| value.identifier.as_deref())
+    }
+
+    /// Get the group of the value in the cell.
+    pub fn get_group(&self, index: IVec2) -> Option<&IntGroupValueGroup> {
+        self.get_value_def(index)
+            .and_then(|value| self.groups.get(&value.group_uid))
+    }
+
+    /// Get the identifier of the group of the value in the cell.
+    pub fn get_group_identifier(&self, index: IVec2) -> Option<&str> {
+        self.get_group(index)
+            .and_then(|group| group.identifier.as_deref())
+    }
+
+    /// Find the value with the identifier.
+    pub fn find_value(&self, identifier: &str) -> Option<i32> {
+        self.values
+            .values()
+            .find(|value| value.identifier.as_deref() == Some(identifier))
+            .map(|value| value.value)
+    }
+
+    /// Check if the cell has the value with the identifier.
+    pub fn is(&self, index: IVec2, identifier: &str) -> bool {
+        self.get_identifier(index) == Some(identifier)
+    }
+
+    /// Check if the value in the cell is in the group with the identifier.
+    pub fn is_in_group(&self, index: IVec2, group: &str) -> bool {
+        self.get_group_identifier(index) == Some(group)
+    }
+}
+
+/// Convert the grid coordinates of LDtk to the index of the tile.
+#[inline]
+pub fn ldtk_to_index(cell: IVec2) -> IVec2 {
+    IVec2::new(cell.x, -cell.y - 1)
+}
@@ -1,6 +1,12 @@
+use std::sync::Arc;
+
 use bevy::{
     asset::AssetServer,
-    ecs::{entity::Entity, system::{Commands, EntityCommands}},
+    ecs::{
+        entity::Entity,
+        system::{Commands, EntityCommands},
+    },
+    log::warn,
     math::{IVec2, UVec2, Vec2, Vec4},
     prelude::SpatialBundle,
     sprite::SpriteBundle,
@@ -15,8 +21,8 @@ use crate::{
         buffers::TileBuffer,
         bundles::TilemapBundle,
         map::{
-            TileRenderSize, TilemapLayerOpacities, TilemapName, TilemapSlotSize, TilemapStorage,
-            TilemapTexture, TilemapTransform, TilemapType,
+            TileRenderSize, TilemapLayerOpacities, TilemapLayerStyle, TilemapLayers, TilemapName,
+            TilemapSlotSize, TilemapStorage, TilemapTexture, TilemapTransform, TilemapType,
         },
         tile::{TileBuilder, TileLayer, TileTexture},
     },
@@ -24,13 +30,24 @@ use crate::{
 };
 
 use super::{
-    components::{LayerIid, LdtkLoadedLevel, LevelIid, EntityIid, LdtkTempTransform},
-    traits::{LdtkEntityRegistry, LdtkEntityTagRegistry},
-    json::{level::{LayerInstance, Level, TileInstance, EntityInstance}, field::FieldInstance},
-    resources::{LdtkAssets, LdtkLevelManager, LdtkPatterns, LdtkLoadConfig},
+    components::{
+        EntityIid, LayerIid, LdtkEntityRefs, LdtkLoadedLevel, LdtkLoadingProgress,
+        LdtkPendingTiles, LdtkTempTransform, LevelIid, WorldIid,
+    },
+    error::LdtkError,
+    json::{
+        definitions::{LayerDef, LayerType},
+        field::FieldInstance,
+        level::{EntityInstance, LayerInstance, Level, TileInstance},
+    },
+    parallax::LdtkParallaxLayer,
+    resources::{LdtkAssets, LdtkLevelManager, LdtkLoadConfig, LdtkPatterns},
+    traits::{LdtkEntityRegistry, LdtkEntityTagRegistry, LdtkLayerRegistry, LdtkLevelRegistry},
     LdtkLoaderMode,
 };
 
+pub mod auto_layer;
+pub mod int_grid;
 #[cfg(feature = "algorithm")]
 pub mod path;
 #[cfg(feature = "physics")]
@@ -54,34 +71,34 @@ impl PackedLdtkEntity {
         config: &LdtkLoadConfig,
         ldtk_assets: &LdtkAssets,
         asset_server: &AssetServer,
-    ) {
+    ) -> Result<(), LdtkError> {
         let phantom_entity = {
             if let Some(e) = entity_registry.get(&self.instance.identifier) {
                 e
             } else if !config.ignore_unregistered_entities {
-                panic!(
-                    "Could not find entity type with entity identifier: {}! \
-                    You need to register it using App::register_ldtk_entity::<T>() first!",
-                    self.instance.identifier
-                );
+                return Err(LdtkError::UnregisteredEntity(
+                    self.instance.identifier.clone(),
+                ));
             } else {
-                return;
+                return Ok(());
             }
         };
-        
-        self.instance.tags.iter().for_each(|tag| {
+
+        let mut entity_tags = Vec::with_capacity(self.instance.tags.len());
+        for tag in self.instance.tags.iter() {
             if let Some(entity_tag) = entity_tag_registry.get(tag) {
-                entity_tag.add_tag(commands);
+                entity_tags.push(entity_tag);
             } else if !config.ignore_unregistered_entity_tags {
-                panic!(
-                    "Could not find entity tag with tag: {}! \
-                    You need to register it using App::register_ldtk_entity_tag::<T>() first! \
-                    Or call LdtkLevelManager::ignore_unregistered_entity_tags to ignore.",
-                    tag
-                );
+                return Err(LdtkError::UnregisteredEntityTag {
+                    entity: self.instance.identifier.clone(),
+                    tag: tag.clone(),
+                });
             }
-        });
-        
+        }
+        entity_tags
+            .into_iter()
+            .for_each(|entity_tag| entity_tag.add_tag(commands));
+
         phantom_entity.spawn(
             commands,
             &self.instance,
@@ -89,21 +106,60 @@ impl PackedLdtkEntity {
             asset_server,
             &manager,
             ldtk_assets,
-        )
+        );
+        Ok(())
     }
 }
 
 pub type LayerOpacity = f32;
 
-pub struct LdtkLayers<'a> {
+/// The total offset of the layer in bevy's coordinate system and the parallax factor.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct LayerTransform {
+    pub offset: Vec2,
+    pub parallax: Vec2,
+}
+
+impl LayerTransform {
+    pub fn new(layer: &LayerInstance, layer_def: Option<&LayerDef>) -> Self {
+        Self {
+            offset: get_layer_offset(layer),
+            parallax: layer_def
+                .map(|def| Vec2::new(def.parallax_factor_x, def.parallax_factor_y))
+                .unwrap_or_default(),
+        }
+    }
+}
+
+/// The total offset of the layer in bevy's coordinate system.
+pub fn get_layer_offset(layer: &LayerInstance) -> Vec2 {
+    Vec2::new(
+        layer.px_total_offset_x as f32,
+        -layer.px_total_offset_y as f32,
+    )
+}
+
+pub struct LdtkLayers {
     pub ty: LdtkLoaderMode,
     pub level_entity: Entity,
-    pub layers: Vec<Option<(TilemapPattern, TilemapTexture, LayerIid, LayerOpacity)>>,
+    pub layers: Vec<
+        Option<(
+            TilemapPattern,
+            TilemapTexture,
+            LayerIid,
+            LayerOpacity,
+            LayerTransform,
+        )>,
+    >,
     pub entities: Vec<PackedLdtkEntity>,
-    pub tilesets: &'a HashMap<i32, TilemapTexture>,
+    pub int_grids: HashMap<usize, (LayerIid, int_grid::LdtkIntGrid)>,
+    pub tilesets: Arc<HashMap<i32, TilemapTexture>>,
+    pub layer_defs: Arc<HashMap<i32, LayerDef>>,
     pub translation: Vec2,
     pub base_z_index: i32,
     pub background: SpriteBundle,
+    /// The errors of the layers and entities that failed to load.
+    pub errors: Vec<LdtkError>,
     #[cfg(feature = "algorithm")]
     pub path_layer: Option<(
         path::LdtkPathLayer,
@@ -113,11 +169,11 @@ pub struct LdtkLayers<'a> {
     pub physics_layer: Option<(physics::LdtkPhysicsLayer, Vec<i32>, UVec2)>,
 }
 
-impl<'a> LdtkLayers<'a> {
+impl LdtkLayers {
     pub fn new(
         level_entity: Entity,
         total_layers: usize,
-        ldtk_assets: &'a LdtkAssets,
+        ldtk_assets: &LdtkAssets,
         translation: Vec2,
         base_z_index: i32,
         ty: LdtkLoaderMode,
@@ -127,10 +183,13 @@ impl<'a> LdtkLayers<'a> {
             level_entity,
             layers: vec![None; total_layers],
             entities: vec![],
-            tilesets: &ldtk_assets.tilesets,
+            int_grids: HashMap::new(),
+            tilesets: ldtk_assets.tilesets.clone(),
+            layer_defs: ldtk_assets.layer_defs.clone(),
             translation,
             base_z_index,
             background,
+            errors: Vec::new(),
             ty,
             #[cfg(feature = "algorithm")]
             path_layer: None,
@@ -140,9 +199,12 @@ impl<'a> LdtkLayers<'a> {
     }
 
     pub fn set_tile(&mut self, layer_index: usize, layer: &LayerInstance, tile: &TileInstance) {
-        self.try_create_new_layer(layer_index, layer);
-
-        let (pattern, texture, _, _) = self.layers[layer_index].as_mut().unwrap();
+        if self.try_create_new_layer(layer_index, layer).is_err() {
+            return;
+        }
+        let Some((pattern, texture, _, _, _)) = self.layers[layer_index].as_mut() else {
+            return;
+        };
         let tile_size = texture.desc.tile_size;
         let tile_index = IVec2 {
             x: tile.px[0] / tile_size.x as i32,
@@ -171,17 +233,46 @@ impl<'a> LdtkLayers<'a> {
         self.entities.push(entity);
     }
 
-    fn try_create_new_layer(&mut self, layer_index: usize, layer: &LayerInstance) {
-        let tileset = self
-            .tilesets
-            .get(&layer.tileset_def_uid.unwrap())
-            .cloned()
-            .unwrap();
+    /// Check if the layer has auto-layer rules, so the tilemap is needed
+    /// even if there's no tile at first.
+    pub fn has_auto_rules(&self, layer: &LayerInstance) -> bool {
+        self.layer_defs
+            .get(&layer.layer_def_uid)
+            .is_some_and(|def| !def.auto_rule_groups.is_empty())
+    }
+
+    pub fn set_int_grid(
+        &mut self,
+        layer_index: usize,
+        layer: &LayerInstance,
+        int_grid: int_grid::LdtkIntGrid,
+    ) {
+        self.int_grids
+            .insert(layer_index, (LayerIid(layer.iid.clone()), int_grid));
+    }
 
+    /// Create the tilemap for the layer if it's not created yet.
+    ///
+    /// Layers without a tileset are skipped.
+    pub fn try_create_new_layer(
+        &mut self,
+        layer_index: usize,
+        layer: &LayerInstance,
+    ) -> Result<(), LdtkError> {
         if self.layers[layer_index].is_some() {
-            return;
+            return Ok(());
         }
 
+        let Some(tileset_uid) = layer.tileset_def_uid else {
+            return Ok(());
+        };
+        let Some(tileset) = self.tilesets.get(&tileset_uid).cloned() else {
+            return Err(LdtkError::MissingTileset {
+                layer: layer.identifier.clone(),
+                tileset_uid,
+            });
+        };
+
         let aabb = IAabb2d {
             min: IVec2::new(0, -layer.c_hei + 1),
             max: IVec2::new(layer.c_wid - 1, 0),
@@ -208,7 +299,9 @@ impl<'a> LdtkLayers<'a> {
             tileset,
             LayerIid(layer.iid.clone()),
             layer.opacity,
+            LayerTransform::new(layer, self.layer_defs.get(&layer.layer_def_uid)),
         ));
+        Ok(())
     }
 
     pub fn apply_all(
@@ -216,8 +309,11 @@ impl<'a> LdtkLayers<'a> {
         commands: &mut Commands,
         ldtk_patterns: &mut LdtkPatterns,
         level: &Level,
+        world_iid: &str,
         entity_registry: &LdtkEntityRegistry,
         entity_tag_registry: &LdtkEntityTagRegistry,
+        level_registry: &LdtkLevelRegistry,
+        layer_registry: &LdtkLayerRegistry,
         manager: &LdtkLevelManager,
         config: &LdtkLoadConfig,
         ldtk_assets: &LdtkAssets,
@@ -227,14 +323,15 @@ impl<'a> LdtkLayers<'a> {
             LdtkLoaderMode::Tilemap => {
                 let mut layers = HashMap::with_capacity(self.layers.len());
                 let mut entities = HashMap::with_capacity(self.entities.len());
+                let mut int_grids = HashMap::with_capacity(self.int_grids.len());
+                let mut pending_tiles = Vec::with_capacity(self.layers.len());
 
                 self.entities.drain(..).for_each(|entity| {
-                    let mut ldtk_entity = commands.spawn((
-                        entity.transform.clone(),
-                        entity.iid.clone()
-                    ));
-                    entities.insert(entity.iid.clone(), ldtk_entity.id());
-                    entity.instantiate(
+                    let mut ldtk_entity =
+                        commands.spawn((entity.transform.clone(), entity.iid.clone()));
+                    let iid = entity.iid.clone();
+                    let refs = LdtkEntityRefs::new(&entity.fields);
+                    if let Err(e) = entity.instantiate(
                         &mut ldtk_entity,
                         entity_registry,
                         entity_tag_registry,
@@ -242,72 +339,120 @@ impl<'a> LdtkLayers<'a> {
                         config,
                         ldtk_assets,
                         asset_server,
-                    );
-                });
+                    ) {
+                        ldtk_entity.despawn();
+                        self.errors.push(e);
+                        return;
+                    }
 
-                self.layers
-                .drain(..)
-                .enumerate()
-                .filter_map(|(i,e)| {
-                    if let Some(e) = e {
-                        Some((i,e))
-                    }else {
-                        None
+                    entities.insert(iid, ldtk_entity.id());
+                    if let Some(refs) = refs {
+                        ldtk_entity.insert(refs);
                     }
-                })
-                    .for_each(|(index, (pattern, texture, iid, opacity))| {
-                        let tilemap_entity = commands.spawn_empty().id();
-                        let mut tilemap = TilemapBundle {
-                            name: TilemapName(pattern.label.clone().unwrap()),
-                            ty: TilemapType::Square,
-                            tile_render_size: TileRenderSize(texture.desc.tile_size.as_vec2()),
-                            slot_size: TilemapSlotSize(texture.desc.tile_size.as_vec2()),
-                            texture: texture.clone(),
-                            storage: TilemapStorage::new(DEFAULT_CHUNK_SIZE, tilemap_entity),
-                            tilemap_transform: TilemapTransform {
-                                translation: self.translation,
-                                z_index: self.base_z_index - index as i32 - 1,
-                                ..Default::default()
-                            },
-                            layer_opacities: TilemapLayerOpacities([opacity; 4].into()),
-                            ..Default::default()
-                        };
-
-                        tilemap
-                            .storage
-                            .fill_with_buffer(commands, IVec2::ZERO, pattern.tiles);
-
-                        #[cfg(feature = "algorithm")]
-                        if let Some((path_layer, path_tilemap)) = &self.path_layer {
-                            if path_layer.parent == tilemap.name.0 {
-                                commands.entity(tilemap_entity).insert(
-                                    crate::tilemap::algorithm::path::PathTilemap {
-                                        storage:
-                                            crate::tilemap::chunking::storage::ChunkedStorage::from_mapper(
-                                                path_tilemap.clone(),
-                                                None,
-                                            ),
+                });
+
+                let level_center =
+                    self.translation + Vec2::new(level.px_wid as f32, -level.px_hei as f32) / 2.;
+
+                let is_merged = config.merge_tile_layers && self.is_mergeable();
+                if is_merged {
+                    self.spawn_merged_tilemap(
+                        commands,
+                        &mut layers,
+                        &mut pending_tiles,
+                        level_center,
+                    );
+                } else {
+                    std::mem::take(&mut self.layers)
+                        .into_iter()
+                        .enumerate()
+                        .filter_map(|(i, e)| e.map(|e| (i, e)))
+                        .for_each(
+                            |(index, (pattern, texture, iid, opacity, layer_transform))| {
+                                let tilemap_entity = commands.spawn_empty().id();
+                                let translation = self.translation + layer_transform.offset;
+                                let tilemap = TilemapBundle {
+                                    name: TilemapName(pattern.label.clone().unwrap()),
+                                    ty: TilemapType::Square,
+                                    tile_render_size: TileRenderSize(
+                                        texture.desc.tile_size.as_vec2(),
+                                    ),
+                                    slot_size: TilemapSlotSize(texture.desc.tile_size.as_vec2()),
+                                    texture: texture.clone(),
+                                    storage: TilemapStorage::new(
+                                        DEFAULT_CHUNK_SIZE,
+                                        tilemap_entity,
+                                    ),
+                                    tilemap_transform: TilemapTransform {
+                                        translation,
+                                        z_index: self.base_z_index - index as i32 - 1,
+                                        ..Default::default()
                                     },
+                                    layer_opacities: TilemapLayerOpacities([opacity; 4].into()),
+                                    ..Default::default()
+                                };
+
+                                pending_tiles.push((tilemap_entity, pattern.tiles.tiles));
+
+                                self.insert_additional_layers(
+                                    commands,
+                                    tilemap_entity,
+                                    &tilemap.name.0,
                                 );
-                            }
-                        }
+                                if let Some((iid, int_grid)) = self.int_grids.remove(&index) {
+                                    commands.entity(tilemap_entity).insert(int_grid);
+                                    int_grids.insert(iid, tilemap_entity);
+                                }
+                                if layer_transform.parallax != Vec2::ZERO {
+                                    commands.entity(tilemap_entity).insert(LdtkParallaxLayer {
+                                        factor: layer_transform.parallax,
+                                        base_translation: translation,
+                                        level_center,
+                                    });
+                                }
 
-                        #[cfg(feature = "physics")]
-                        if let Some((physics_layer,physics_data,size)) = &self.physics_layer {
-                            if pattern.label.clone().unwrap() == physics_layer.parent { 
-                                commands.entity(tilemap_entity).insert(crate::tilemap::physics::DataPhysicsTilemap::new(
-                                    IVec2::new(0, -(size.y as i32)),
-                                    physics_data.clone(),
-                                    *size,
-                                    physics_layer.air,
-                                    physics_layer.tiles.clone().unwrap_or_default()
-                                ));
-                            }
-                        }
+                                commands
+                                    .entity(tilemap_entity)
+                                    .insert((tilemap, iid.clone()));
+                                layers.insert(iid, tilemap_entity);
+                            },
+                        );
+                }
+
+                // the int grids without a tilemap to live on
+                self.int_grids.drain().for_each(|(_, (iid, int_grid))| {
+                    int_grids.insert(iid.clone(), commands.spawn((int_grid, iid)).id());
+                });
 
-                        commands.entity(tilemap_entity).insert((tilemap, iid.clone()));
-                        layers.insert(iid, tilemap_entity);
-                    });
+                if !is_merged {
+                    self.insert_auto_layers(commands, level, &layers, &int_grids);
+                } else if level.layer_instances.iter().any(|layer| {
+                    self.layer_defs
+                        .get(&layer.layer_def_uid)
+                        .is_some_and(|def| !def.auto_rule_groups.is_empty())
+                }) {
+                    warn!(
+                        "The auto-layer rules of level {:?} are not applied at runtime \
+                        because its tile layers are merged!",
+                        level.identifier
+                    );
+                }
+
+                level.layer_instances.iter().for_each(|layer| {
+                    let Some(phantom_layer) = layer_registry.get(&layer.identifier) else {
+                        return;
+                    };
+                    let iid = LayerIid(layer.iid.clone());
+                    if let Some(layer_entity) = layers.get(&iid).or(int_grids.get(&iid)) {
+                        phantom_layer.spawn(
+                            &mut commands.entity(*layer_entity),
+                            layer,
+                            asset_server,
+                            manager,
+                            ldtk_assets,
+                        );
+                    }
+                });
 
                 let bg = commands.spawn(self.background.clone()).id();
 
@@ -316,6 +461,7 @@ impl<'a> LdtkLayers<'a> {
                         identifier: level.identifier.clone(),
                         layers,
                         entities,
+                        int_grids,
                         background: bg,
                     },
                     SpatialBundle {
@@ -323,7 +469,33 @@ impl<'a> LdtkLayers<'a> {
                         ..Default::default()
                     },
                     LevelIid(level.iid.clone()),
+                    WorldIid(world_iid.to_string()),
+                    LdtkLoadingProgress {
+                        is_built: true,
+                        spawned_tiles: 0,
+                        total_tiles: pending_tiles.iter().map(|(_, tiles)| tiles.len()).sum(),
+                    },
+                    LdtkPendingTiles(pending_tiles),
                 ));
+
+                let fields = level
+                    .field_instances
+                    .iter()
+                    .map(|field| (field.identifier.clone(), field.clone()))
+                    .collect();
+                if let Some(refs) = LdtkEntityRefs::new(&fields) {
+                    commands.entity(self.level_entity).insert(refs);
+                }
+                level_registry.iter().for_each(|phantom_level| {
+                    phantom_level.spawn(
+                        &mut commands.entity(self.level_entity),
+                        level,
+                        &fields,
+                        asset_server,
+                        manager,
+                        ldtk_assets,
+                    );
+                });
             }
             LdtkLoaderMode::MapPattern => {
                 let level_pack = self
@@ -371,6 +543,217 @@ impl<'a> LdtkLayers<'a> {
         }
     }
 
+    /// All the tilesets used by the tile layers have the same tile size,
+    /// and all the layers have the same offset and parallax factor.
+    fn is_mergeable(&self) -> bool {
+        let layers = self
+            .layers
+            .iter()
+            .flatten()
+            .map(|(_, texture, _, _, transform)| (texture.desc.tile_size, *transform))
+            .collect::<Vec<_>>();
+        let Some((first_size, first_transform)) = layers.first() else {
+            return true;
+        };
+
+        if layers.iter().any(|(size, _)| size != first_size) {
+            warn!("Failed to merge the tile layers as the tilesets have different tile sizes!");
+            false
+        } else if layers
+            .iter()
+            .any(|(_, transform)| transform != first_transform)
+        {
+            warn!(
+                "Failed to merge the tile layers as the layers have \
+                different offsets or parallax factors!"
+            );
+            false
+        } else {
+            true
+        }
+    }
+
+    /// Spawn all the tile layers as one tilemap. Each ldtk layer occupies
+    /// as many tile layers as its deepest tile stack, and the bottom ldtk layer comes first.
+    fn spawn_merged_tilemap(
+        &mut self,
+        commands: &mut Commands,
+        layers: &mut HashMap<LayerIid, Entity>,
+        pending_tiles: &mut Vec<(Entity, HashMap<IVec2, TileBuilder>)>,
+        level_center: Vec2,
+    ) {
+        let ldtk_layers = self.layers.drain(..).rev().flatten().collect::<Vec<_>>();
+        let Some((_, first_texture, _, _, layer_transform)) = ldtk_layers.first() else {
+            return;
+        };
+        let layer_transform = *layer_transform;
+        let translation = self.translation + layer_transform.offset;
+
+        let mut texture = TilemapTexture::new(
+            first_texture.texture.clone(),
+            first_texture.desc.clone(),
+            first_texture.rotation,
+        );
+        let mut slots = HashMap::from([(first_texture.texture.id(), 0)]);
+        let mut tilemap_layers = TilemapLayers::new(0);
+        let mut merged_tiles = HashMap::<IVec2, TileBuilder>::new();
+        let tilemap_entity = commands.spawn_empty().id();
+
+        for (pattern, layer_texture, iid, opacity, _) in ldtk_layers {
+            let slot = *slots.entry(layer_texture.texture.id()).or_insert_with(|| {
+                texture.add_texture(layer_texture.texture.clone(), layer_texture.desc.clone())
+            }) as u32;
+
+            let base = tilemap_layers.count();
+            let mut depth = 1;
+            pattern
+                .tiles
+                .tiles
+                .into_iter()
+                .for_each(|(index, builder)| {
+                    let TileTexture::Static(tile_layers) = builder.texture else {
+                        unreachable!()
+                    };
+                    depth = depth.max(tile_layers.len());
+
+                    let TileTexture::Static(merged_layers) = &mut merged_tiles
+                        .entry(index)
+                        .or_insert_with(TileBuilder::new)
+                        .texture
+                    else {
+                        unreachable!()
+                    };
+                    merged_layers.resize(base, TileLayer::new());
+                    merged_layers.extend(
+                        tile_layers
+                            .into_iter()
+                            .map(|layer| layer.with_texture_slot(slot)),
+                    );
+                });
+
+            for i in 0..depth {
+                tilemap_layers.set_style(
+                    base + i,
+                    TilemapLayerStyle {
+                        opacity,
+                        ..Default::default()
+                    },
+                );
+            }
+
+            self.insert_additional_layers(
+                commands,
+                tilemap_entity,
+                pattern.label.as_ref().unwrap(),
+            );
+            layers.insert(iid, tilemap_entity);
+        }
+
+        let tilemap = TilemapBundle {
+            name: TilemapName("Merged".to_string()),
+            ty: TilemapType::Square,
+            tile_render_size: TileRenderSize(texture.desc.tile_size.as_vec2()),
+            slot_size: TilemapSlotSize(texture.desc.tile_size.as_vec2()),
+            texture,
+            storage: TilemapStorage::new(DEFAULT_CHUNK_SIZE, tilemap_entity),
+            tilemap_transform: TilemapTransform {
+                translation,
+                z_index: self.base_z_index - 1,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        pending_tiles.push((tilemap_entity, merged_tiles));
+
+        commands
+            .entity(tilemap_entity)
+            .insert((tilemap, tilemap_layers));
+        if layer_transform.parallax != Vec2::ZERO {
+            commands.entity(tilemap_entity).insert(LdtkParallaxLayer {
+                factor: layer_transform.parallax,
+                base_translation: translation,
+                level_center,
+            });
+        }
+    }
+
+    fn insert_auto_layers(
+        &self,
+        commands: &mut Commands,
+        level: &Level,
+        layers: &HashMap<LayerIid, Entity>,
+        int_grids: &HashMap<LayerIid, Entity>,
+    ) {
+        level.layer_instances.iter().for_each(|layer| {
+            let Some(layer_def) = self.layer_defs.get(&layer.layer_def_uid) else {
+                return;
+            };
+            if layer_def.auto_rule_groups.is_empty() {
+                return;
+            }
+
+            // the rules of IntGrid layers are applied to themselves
+            let source = if layer.ty == LayerType::IntGrid {
+                Some(layer)
+            } else {
+                level
+                    .layer_instances
+                    .iter()
+                    .find(|l| Some(l.layer_def_uid) == layer_def.auto_source_layer_def_uid)
+            };
+            let (Some(tilemap), Some(source), Some(tileset)) = (
+                layers.get(&LayerIid(layer.iid.clone())),
+                source.and_then(|source| int_grids.get(&LayerIid(source.iid.clone()))),
+                layer
+                    .tileset_def_uid
+                    .and_then(|uid| self.tilesets.get(&uid)),
+            ) else {
+                return;
+            };
+
+            let auto_layer = auto_layer::LdtkAutoLayer::new(layer, layer_def, *source, tileset);
+            commands.entity(*tilemap).insert(auto_layer);
+        });
+    }
+
+    #[allow(unused_variables)]
+    fn insert_additional_layers(
+        &self,
+        commands: &mut Commands,
+        tilemap_entity: Entity,
+        label: &str,
+    ) {
+        #[cfg(feature = "algorithm")]
+        if let Some((path_layer, path_tilemap)) = &self.path_layer {
+            if &path_layer.parent == label {
+                commands.entity(tilemap_entity).insert(
+                    crate::tilemap::algorithm::path::PathTilemap {
+                        storage: crate::tilemap::chunking::storage::ChunkedStorage::from_mapper(
+                            path_tilemap.clone(),
+                            None,
+                        ),
+                    },
+                );
+            }
+        }
+
+        #[cfg(feature = "physics")]
+        if let Some((physics_layer, physics_data, size)) = &self.physics_layer {
+            if &physics_layer.parent == label {
+                commands.entity(tilemap_entity).insert(
+                    crate::tilemap::physics::DataPhysicsTilemap::new(
+                        IVec2::new(0, -(size.y as i32)),
+                        physics_data.clone(),
+                        *size,
+                        physics_layer.air,
+                        physics_layer.tiles.clone().unwrap_or_default(),
+                    ),
+                );
+            }
+        }
+    }
+
     #[cfg(feature = "algorithm")]
     pub fn assign_path_layer(
         &mut self,
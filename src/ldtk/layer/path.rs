@@ -1,7 +1,10 @@
 use bevy::{ecs::system::Resource, math::IVec2, reflect::Reflect, utils::HashMap};
 
 use crate::{
-    ldtk::json::{definitions::LayerType, level::LayerInstance},
+    ldtk::{
+        error::LdtkError,
+        json::{definitions::LayerType, level::LayerInstance},
+    },
     tilemap::algorithm::path::PathTile,
 };
 
@@ -12,12 +15,12 @@ pub struct LdtkPathLayer {
     pub cost_mapper: Option<HashMap<i32, u32>>,
 }
 
-pub fn analyze_path_layer(layer: &LayerInstance, path: &LdtkPathLayer) -> HashMap<IVec2, PathTile> {
+pub fn analyze_path_layer(
+    layer: &LayerInstance,
+    path: &LdtkPathLayer,
+) -> Result<HashMap<IVec2, PathTile>, LdtkError> {
     if layer.ty != LayerType::IntGrid {
-        panic!(
-            "The path layer {:?} is not an IntGrid layer!",
-            layer.identifier
-        );
+        return Err(LdtkError::NotIntGridLayer(layer.identifier.clone()));
     }
 
     let size = IVec2::new(layer.c_wid, layer.c_hei);
@@ -38,5 +41,5 @@ pub fn analyze_path_layer(layer: &LayerInstance, path: &LdtkPathLayer) -> HashMa
         }
     }
 
-    tiles
+    Ok(tiles)
 }
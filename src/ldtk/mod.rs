@@ -1,18 +1,23 @@
-use std::path::Path;
+use std::{path::Path, sync::Arc};
 
 use bevy::{
     app::{Plugin, Startup, Update},
-    asset::{load_internal_asset, AssetServer, Assets, Handle},
+    asset::{load_internal_asset, AssetApp, AssetEvent, AssetServer, Assets, Handle, LoadState},
     ecs::{
         entity::Entity,
-        event::EventWriter,
-        query::{Added, With},
-        system::{Commands, NonSend, ParallelCommands, Query, Res, ResMut},
+        event::{EventReader, EventWriter},
+        query::{Added, With, Without},
+        removal_detection::RemovedComponents,
+        schedule::IntoSystemConfigs,
+        system::{Commands, ParallelCommands, Query, Res, ResMut},
     },
-    math::{UVec2, Vec2},
-    render::{mesh::Mesh, render_resource::Shader},
-    sprite::{Material2dPlugin, Sprite, SpriteBundle, TextureAtlas},
+    log::error,
+    math::{IVec2, UVec2, Vec2},
+    render::render_resource::Shader,
+    sprite::{Material2dPlugin, Sprite, SpriteBundle},
+    tasks::AsyncComputeTaskPool,
     transform::components::Transform,
+    utils::{HashMap, HashSet},
 };
 
 use crate::{
@@ -24,18 +29,22 @@ use crate::{
             EntityRef, GridPoint, LdtkColor, Toc, World,
         },
         resources::{
-            LdtkAdditionalLayers, LdtkAssets, LdtkGlobalEntityRegistry, LdtkPatterns, LdtkTocs,
+            LdtkAdditionalLayers, LdtkAssetParams, LdtkAssets, LdtkGlobalEntityRegistry,
+            LdtkPatterns, LdtkTocs, SharedLevel,
         },
         sprite::{AtlasRect, NineSliceBorders, SpriteMesh},
     },
-    tilemap::map::TilemapStorage,
+    math::aabb::IAabb2d,
+    tilemap::{buffers::TileBuffer, map::TilemapStorage},
 };
 
 use self::{
     components::{
-        EntityIid, GlobalEntity, LdtkLoadedLevel, LdtkTempTransform, LdtkUnloadLayer, LevelIid,
+        EntityIid, GlobalEntity, LdtkEntityRefs, LdtkLoadedLevel, LdtkLoadingProgress,
+        LdtkLoadingTask, LdtkPendingTiles, LdtkTempTransform, LdtkUnloadLayer, LevelIid,
     },
-    events::{LdtkEvent, LevelEvent},
+    error::LdtkError,
+    events::{LdtkEvent, LevelEvent, LoadFailedEvent},
     json::{
         definitions::LayerType,
         level::{LayerInstance, Level},
@@ -44,16 +53,24 @@ use self::{
     layer::{LdtkLayers, PackedLdtkEntity},
     resources::{LdtkLevelManager, LdtkLoadConfig},
     sprite::LdtkEntityMaterial,
-    traits::{LdtkEntityRegistry, LdtkEntityTagRegistry},
+    traits::{
+        LdtkEntityRegistry, LdtkEntityTagRegistry, LdtkLayerRegistry, LdtkLevelRegistry,
+        LdtkRegistries,
+    },
 };
 
 pub mod app_ext;
+pub mod asset;
 pub mod components;
+pub mod error;
 pub mod events;
 pub mod json;
 pub mod layer;
+pub mod parallax;
+pub mod prefab;
 pub mod resources;
 pub mod sprite;
+pub mod streaming;
 pub mod traits;
 
 pub const ENTITY_SPRITE_SHADER: Handle<Shader> = Handle::weak_from_u128(89874656485416351634163551);
@@ -71,19 +88,31 @@ impl Plugin for EntiTilesLdtkPlugin {
 
         app.add_plugins(Material2dPlugin::<LdtkEntityMaterial>::default());
 
+        app.init_asset::<LdtkJson>()
+            .init_asset::<Level>()
+            .init_asset_loader::<asset::LdtkJsonLoader>()
+            .init_asset_loader::<asset::LdtkLevelLoader>();
+
         app.add_systems(Startup, parse_ldtk_json);
         app.add_systems(
             Update,
             (
+                ldtk_json_reloader,
+                streaming::ldtk_level_streaming,
+                parallax::ldtk_parallax,
+                layer::auto_layer::ldtk_auto_layer_updater,
                 load_ldtk_json,
+                (ldtk_level_applier, ldtk_tile_spawner).chain(),
                 unload_ldtk_level,
                 unload_ldtk_layer,
                 global_entity_registerer,
                 ldtk_temp_tranform_applier,
+                ldtk_entity_refs_resolver,
+                prefab::ldtk_prefab_spawner.before(ldtk_temp_tranform_applier),
             ),
         );
 
-        app.insert_non_send_resource(LdtkEntityRegistry::default());
+        app.init_resource::<LdtkEntityRegistry>();
 
         app.init_resource::<LdtkLevelManager>()
             .init_resource::<LdtkLoadConfig>()
@@ -97,18 +126,26 @@ impl Plugin for EntiTilesLdtkPlugin {
 
         app.register_type::<LdtkLoadedLevel>()
             .register_type::<GlobalEntity>()
+            .register_type::<LdtkEntityRefs>()
             .register_type::<EntityIid>()
             .register_type::<LayerIid>()
             .register_type::<LevelIid>()
             .register_type::<WorldIid>()
             .register_type::<LevelEvent>()
+            .register_type::<LoadFailedEvent>()
             .register_type::<LdtkLoader>()
+            .register_type::<LdtkLoadingProgress>()
             .register_type::<LdtkUnloader>()
             .register_type::<LdtkLoaderMode>()
             .register_type::<AtlasRect>()
             .register_type::<LdtkEntityMaterial>()
             .register_type::<NineSliceBorders>()
-            .register_type::<SpriteMesh>();
+            .register_type::<SpriteMesh>()
+            .register_type::<streaming::LdtkLevelStreamer>()
+            .register_type::<parallax::LdtkParallaxCamera>()
+            .register_type::<parallax::LdtkParallaxLayer>()
+            .register_type::<layer::int_grid::LdtkIntGrid>()
+            .register_type::<layer::auto_layer::LdtkAutoLayer>();
 
         app.register_type::<FieldInstance>()
             .register_type::<Level>()
@@ -145,8 +182,122 @@ impl Plugin for EntiTilesLdtkPlugin {
     }
 }
 
-fn parse_ldtk_json(mut manager: ResMut<LdtkLevelManager>, config: Res<LdtkLoadConfig>) {
-    manager.reload_json(&config);
+fn parse_ldtk_json(
+    mut manager: ResMut<LdtkLevelManager>,
+    config: Res<LdtkLoadConfig>,
+    asset_server: Res<AssetServer>,
+) {
+    manager.initialize(&config, &asset_server);
+}
+
+/// Refresh the level cache and the assets when the ldtk file is loaded or modified,
+/// and reload the loaded levels that are changed.
+fn ldtk_json_reloader(
+    mut commands: Commands,
+    mut asset_events: EventReader<AssetEvent<LdtkJson>>,
+    mut level_asset_events: EventReader<AssetEvent<Level>>,
+    json_assets: Res<Assets<LdtkJson>>,
+    mut manager: ResMut<LdtkLevelManager>,
+    levels_query: Query<(&Transform, &WorldIid), With<LdtkLoadedLevel>>,
+    asset_params: LdtkAssetParams,
+) {
+    let LdtkAssetParams {
+        mut ldtk_assets,
+        config,
+        asset_server,
+        mut atlas_assets,
+        mut entity_material_assets,
+        mut mesh_assets,
+    } = asset_params;
+
+    let get_location = |entity: Entity| {
+        levels_query
+            .get(entity)
+            .ok()
+            .map(|(transform, world)| (transform.translation.truncate(), world.0.clone()))
+    };
+    let reload = |commands: &mut Commands,
+                  manager: &mut LdtkLevelManager,
+                  level: String,
+                  location: Option<(Vec2, String)>| {
+        manager.unload(commands, level.clone());
+        match location {
+            Some((translation, world)) => {
+                manager.load_in_world(commands, world, level, Some(translation))
+            }
+            None => manager.load(commands, level, None),
+        }
+    };
+
+    // external level files
+    for event in level_asset_events.read() {
+        let AssetEvent::Modified { id } = event else {
+            continue;
+        };
+        let Some(level) = manager
+            .external_levels
+            .iter()
+            .find(|(_, handle)| handle.id() == *id)
+            .map(|(level, _)| level.clone())
+        else {
+            continue;
+        };
+        let Some(entity) = manager.loaded_levels.get(&level).copied() else {
+            continue;
+        };
+
+        reload(&mut commands, &mut manager, level, get_location(entity));
+    }
+
+    for event in asset_events.read() {
+        let (AssetEvent::LoadedWithDependencies { id } | AssetEvent::Modified { id }) = event
+        else {
+            continue;
+        };
+        if manager.handle.as_ref().map(|h| h.id()) != Some(*id) {
+            continue;
+        }
+        let Some(ldtk_data) = json_assets.get(*id) else {
+            continue;
+        };
+
+        let old_data = manager.ldtk_json.replace(Arc::new(ldtk_data.clone()));
+        ldtk_assets.initialize(
+            &config,
+            &manager,
+            &asset_server,
+            &mut atlas_assets,
+            &mut entity_material_assets,
+            &mut mesh_assets,
+        );
+        if manager.patterns_requested {
+            manager.load_all_patterns(&mut commands);
+        }
+        let Some(old_data) = old_data else {
+            continue;
+        };
+
+        let defs_changed = old_data.defs != ldtk_data.defs;
+        let changed_levels = manager
+            .loaded_levels
+            .iter()
+            .map(|(level, entity)| (level.clone(), get_location(*entity)))
+            .filter(|(level, location)| {
+                let world = location.as_ref().map(|(_, world)| world.as_str());
+                defs_changed
+                    || old_data.get_level(world, level) != ldtk_data.get_level(world, level)
+            })
+            .collect::<Vec<_>>();
+
+        for (level, location) in changed_levels {
+            let world = location.as_ref().map(|(_, world)| world.as_str());
+            if ldtk_data.find_level(world, &level).is_some() {
+                reload(&mut commands, &mut manager, level, location);
+            } else {
+                manager.unload(&mut commands, level);
+            }
+        }
+    }
 }
 
 fn global_entity_registerer(
@@ -158,6 +309,27 @@ fn global_entity_registerer(
     });
 }
 
+fn ldtk_entity_refs_resolver(
+    mut refs_query: Query<&mut LdtkEntityRefs>,
+    new_refs_query: Query<(), Added<LdtkEntityRefs>>,
+    new_entities_query: Query<(), Added<EntityIid>>,
+    mut removed_entities: RemovedComponents<EntityIid>,
+    entities_query: Query<(Entity, &EntityIid)>,
+) {
+    let is_removed = removed_entities.read().count() > 0;
+    if new_refs_query.is_empty() && new_entities_query.is_empty() && !is_removed {
+        return;
+    }
+
+    let entities = entities_query
+        .iter()
+        .map(|(entity, iid)| (iid, entity))
+        .collect::<HashMap<_, _>>();
+    refs_query.for_each_mut(|mut refs| {
+        refs.resolve(&entities);
+    });
+}
+
 fn ldtk_temp_tranform_applier(
     commands: ParallelCommands,
     mut entities_query: Query<(Entity, &mut Transform, &LdtkTempTransform)>,
@@ -192,10 +364,14 @@ pub fn unload_ldtk_level(
 pub fn unload_ldtk_layer(
     mut commands: Commands,
     mut query: Query<&mut TilemapStorage, With<LdtkUnloadLayer>>,
+    int_grids_query: Query<Entity, (With<LdtkUnloadLayer>, Without<TilemapStorage>)>,
 ) {
     query.iter_mut().for_each(|mut storage| {
         storage.despawn(&mut commands);
     });
+    int_grids_query.for_each(|entity| {
+        commands.entity(entity).despawn();
+    });
 }
 
 #[cfg(feature = "physics")]
@@ -208,6 +384,7 @@ pub fn unload_ldtk_layer(
         ),
         With<LdtkUnloadLayer>,
     >,
+    int_grids_query: Query<Entity, (With<LdtkUnloadLayer>, Without<TilemapStorage>)>,
 ) {
     query.iter_mut().for_each(|(mut storage, physics)| {
         if let Some(mut physics) = physics {
@@ -215,156 +392,399 @@ pub fn unload_ldtk_layer(
         }
         storage.despawn(&mut commands);
     });
+    int_grids_query.for_each(|entity| {
+        commands.entity(entity).despawn();
+    });
 }
 
 pub fn load_ldtk_json(
     mut commands: Commands,
-    loader_query: Query<(Entity, &LdtkLoader)>,
-    asset_server: Res<AssetServer>,
-    entity_registry: Option<NonSend<LdtkEntityRegistry>>,
-    entity_tag_registry: Option<NonSend<LdtkEntityTagRegistry>>,
-    mut atlas_assets: ResMut<Assets<TextureAtlas>>,
+    loader_query: Query<(Entity, &LdtkLoader, Option<&LdtkUnloader>), Without<LdtkLoadingTask>>,
     mut ldtk_events: EventWriter<LdtkEvent>,
-    config: Res<LdtkLoadConfig>,
     mut manager: ResMut<LdtkLevelManager>,
     addi_layers: Res<LdtkAdditionalLayers>,
-    mut ldtk_assets: ResMut<LdtkAssets>,
-    mut entity_material_assets: ResMut<Assets<LdtkEntityMaterial>>,
-    mut mesh_assets: ResMut<Assets<Mesh>>,
-    mut patterns: ResMut<LdtkPatterns>,
+    asset_params: LdtkAssetParams,
     global_entities: Res<LdtkGlobalEntityRegistry>,
+    level_assets: Res<Assets<Level>>,
 ) {
-    for (entity, loader) in loader_query.iter() {
-        let entity_registry = entity_registry.as_ref().map(|r| &**r);
-        let entity_tag_registry = entity_tag_registry.as_ref().map(|r| &**r);
+    let LdtkAssetParams {
+        mut ldtk_assets,
+        config,
+        asset_server,
+        mut entity_material_assets,
+        mut mesh_assets,
+        ..
+    } = asset_params;
+
+    // wait for the ldtk file to be loaded
+    if !manager.is_initialized() {
+        let is_failed = manager
+            .handle
+            .as_ref()
+            .is_some_and(|handle| asset_server.get_load_state(handle) == Some(LoadState::Failed));
+        if is_failed {
+            for (entity, loader, _) in loader_query.iter() {
+                fail_loading(
+                    &mut commands,
+                    &mut manager,
+                    &mut ldtk_events,
+                    entity,
+                    loader,
+                    LdtkError::JsonNotLoaded(config.file_path.clone()),
+                );
+            }
+        }
+        return;
+    }
 
-        ldtk_assets.initialize(
-            &config,
-            &manager,
-            &asset_server,
-            &mut atlas_assets,
-            &mut entity_material_assets,
-            &mut mesh_assets,
-        );
+    for (entity, loader, unloader) in loader_query.iter() {
+        // unloaded before the external level file is loaded
+        if unloader.is_some() {
+            commands.entity(entity).despawn();
+            continue;
+        }
+
+        let world = loader.world.as_deref();
+        manager.request_level(world, &loader.level, &config, &asset_server);
+        let is_external = manager.external_levels.contains_key(&loader.level);
+        let Some(level) = manager.get_shared_level(world, &loader.level, &level_assets) else {
+            let is_failed = manager
+                .external_levels
+                .get(&loader.level)
+                .is_some_and(|handle| {
+                    asset_server.get_load_state(handle) == Some(LoadState::Failed)
+                });
+            let error = if !is_external {
+                LdtkError::LevelNotFound(loader.level.clone())
+            } else if is_failed {
+                LdtkError::LevelNotLoaded(loader.level.clone())
+            } else {
+                // wait for the external level file to be loaded
+                continue;
+            };
+
+            fail_loading(
+                &mut commands,
+                &mut manager,
+                &mut ldtk_events,
+                entity,
+                loader,
+                error,
+            );
+            continue;
+        };
+
+        // the assets of the levels in the ldtk file are created when it's loaded
+        if let Some(ldtk_data) = manager.get_cached_data().filter(|_| is_external) {
+            ldtk_assets.load_level_entities(
+                &config,
+                ldtk_data,
+                &level,
+                &mut entity_material_assets,
+                &mut mesh_assets,
+            );
+        }
+
+        let context = LevelBuildContext {
+            config: &config,
+            asset_server: &asset_server,
+            addi_layers: &addi_layers,
+            ldtk_assets: &ldtk_assets,
+            global_entities: &global_entities,
+        };
+        let Some(task) = build_level(&context, &manager, level, loader, entity) else {
+            let error = LdtkError::LevelNotFound(loader.level.clone());
+            fail_loading(
+                &mut commands,
+                &mut manager,
+                &mut ldtk_events,
+                entity,
+                loader,
+                error,
+            );
+            continue;
+        };
+
+        commands
+            .entity(entity)
+            .insert((task, LdtkLoadingProgress::default()));
+    }
+}
+
+/// Spawn the levels whose layers are built.
+fn ldtk_level_applier(
+    mut commands: Commands,
+    mut tasks_query: Query<(
+        Entity,
+        &LdtkLoader,
+        &mut LdtkLoadingTask,
+        Option<&LdtkUnloader>,
+    )>,
+    registries: LdtkRegistries,
+    mut ldtk_events: EventWriter<LdtkEvent>,
+    config: Res<LdtkLoadConfig>,
+    manager: Res<LdtkLevelManager>,
+    ldtk_assets: Res<LdtkAssets>,
+    mut patterns: ResMut<LdtkPatterns>,
+    asset_server: Res<AssetServer>,
+) {
+    for (entity, loader, mut task, unloader) in tasks_query.iter_mut() {
+        // unloaded before the layers are built, the task is cancelled when it's dropped
+        if unloader.is_some() {
+            commands.entity(entity).despawn();
+            continue;
+        }
 
-        load_levels(
+        let Some((level, mut ldtk_layers)) =
+            bevy::tasks::block_on(futures_lite::future::poll_once(&mut task.task))
+        else {
+            continue;
+        };
+        commands
+            .entity(entity)
+            .remove::<(LdtkLoader, LdtkLoadingTask)>();
+
+        ldtk_layers.apply_all(
             &mut commands,
+            &mut patterns,
+            &level,
+            &task.world_iid,
+            registries
+                .entities
+                .as_deref()
+                .unwrap_or(&LdtkEntityRegistry::default()),
+            registries
+                .entity_tags
+                .as_deref()
+                .unwrap_or(&LdtkEntityTagRegistry::default()),
+            registries
+                .levels
+                .as_deref()
+                .unwrap_or(&LdtkLevelRegistry::default()),
+            registries
+                .layers
+                .as_deref()
+                .unwrap_or(&LdtkLayerRegistry::default()),
+            &manager,
             &config,
-            &mut manager,
-            &addi_layers,
-            loader,
+            &ldtk_assets,
             &asset_server,
-            &entity_registry.unwrap_or(&LdtkEntityRegistry::default()),
-            &entity_tag_registry.unwrap_or(&LdtkEntityTagRegistry::default()),
-            entity,
-            &mut ldtk_events,
-            &mut ldtk_assets,
-            &mut patterns,
-            &global_entities,
         );
 
-        commands.entity(entity).remove::<LdtkLoader>();
+        ldtk_layers
+            .errors
+            .drain(..)
+            .for_each(|error| send_load_failed(&mut ldtk_events, &loader.level, error));
+        // the tiles of the tilemaps are spawned in `ldtk_tile_spawner`
+        if loader.mode == LdtkLoaderMode::MapPattern {
+            ldtk_events.send(LdtkEvent::LevelLoaded(LevelEvent {
+                identifier: level.identifier.clone(),
+                iid: level.iid.clone(),
+            }));
+        }
+    }
+}
+
+/// Spawn the tiles of the loading levels, no more than `LdtkLoadConfig::max_tiles_per_frame`.
+fn ldtk_tile_spawner(
+    mut commands: Commands,
+    mut levels_query: Query<(
+        Entity,
+        &LdtkLoadedLevel,
+        &LevelIid,
+        &mut LdtkPendingTiles,
+        &mut LdtkLoadingProgress,
+    )>,
+    mut storages_query: Query<&mut TilemapStorage>,
+    config: Res<LdtkLoadConfig>,
+    mut ldtk_events: EventWriter<LdtkEvent>,
+) {
+    let mut budget = config
+        .max_tiles_per_frame
+        .map(|max| max.max(1))
+        .unwrap_or(usize::MAX);
+
+    for (entity, level, iid, mut pending, mut progress) in levels_query.iter_mut() {
+        while budget > 0 {
+            let Some((tilemap, tiles)) = pending.0.last_mut() else {
+                break;
+            };
+            let tilemap = *tilemap;
+            let tiles = if tiles.len() <= budget {
+                pending.0.pop().unwrap().1
+            } else {
+                let indices = tiles.keys().take(budget).copied().collect::<Vec<_>>();
+                indices
+                    .into_iter()
+                    .filter_map(|index| tiles.remove_entry(&index))
+                    .collect()
+            };
+            budget -= tiles.len();
+            progress.spawned_tiles += tiles.len();
+
+            // the tilemap is despawned if it's not found
+            if let Ok(mut storage) = storages_query.get_mut(tilemap) {
+                storage.fill_with_buffer(
+                    &mut commands,
+                    IVec2::ZERO,
+                    TileBuffer {
+                        tiles,
+                        aabb: IAabb2d::default(),
+                    },
+                );
+            }
+        }
+
+        if pending.0.is_empty() {
+            commands
+                .entity(entity)
+                .remove::<(LdtkPendingTiles, LdtkLoadingProgress)>();
+            ldtk_events.send(LdtkEvent::LevelLoaded(LevelEvent {
+                identifier: level.identifier.clone(),
+                iid: iid.0.clone(),
+            }));
+        }
     }
 }
 
-fn load_levels(
+fn fail_loading(
     commands: &mut Commands,
-    config: &LdtkLoadConfig,
     manager: &mut LdtkLevelManager,
-    addi_layers: &LdtkAdditionalLayers,
-    loader: &LdtkLoader,
-    asset_server: &AssetServer,
-    entity_registry: &LdtkEntityRegistry,
-    entity_tag_registry: &LdtkEntityTagRegistry,
-    level_entity: Entity,
     ldtk_events: &mut EventWriter<LdtkEvent>,
-    ldtk_assets: &mut LdtkAssets,
-    patterns: &mut LdtkPatterns,
-    global_entities: &LdtkGlobalEntityRegistry,
+    entity: Entity,
+    loader: &LdtkLoader,
+    error: LdtkError,
 ) {
-    let ldtk_data = manager.get_cached_data();
+    manager.loaded_levels.remove(&loader.level);
+    manager.external_levels.remove(&loader.level);
+    commands.entity(entity).despawn();
+    send_load_failed(ldtk_events, &loader.level, error);
+}
 
-    let Some((level_index, level)) = ldtk_data
-        .levels
-        .iter()
-        .enumerate()
-        .find(|(_, level)| level.identifier == loader.level)
-    else {
-        return;
-    };
+fn send_load_failed(ldtk_events: &mut EventWriter<LdtkEvent>, level: &str, error: LdtkError) {
+    error!("Failed to load level {:?}! {}", level, error);
+    ldtk_events.send(LdtkEvent::LoadFailed(LoadFailedEvent {
+        level: level.to_string(),
+        error,
+    }));
+}
+
+/// The resources used to build the levels.
+struct LevelBuildContext<'a> {
+    config: &'a LdtkLoadConfig,
+    asset_server: &'a AssetServer,
+    addi_layers: &'a LdtkAdditionalLayers,
+    ldtk_assets: &'a LdtkAssets,
+    global_entities: &'a LdtkGlobalEntityRegistry,
+}
+
+/// Start building the layers of the level on the `AsyncComputeTaskPool`.
+///
+/// Returns `None` if the level is not found.
+fn build_level(
+    context: &LevelBuildContext,
+    manager: &LdtkLevelManager,
+    level: SharedLevel,
+    loader: &LdtkLoader,
+    level_entity: Entity,
+) -> Option<LdtkLoadingTask> {
+    let LevelBuildContext {
+        config,
+        asset_server,
+        addi_layers,
+        ldtk_assets,
+        global_entities,
+    } = context;
+    let ldtk_data = manager.get_cached_data()?;
+
+    let (world, level_index) = ldtk_data.find_level(loader.world.as_deref(), &level.identifier)?;
 
     let translation = loader
         .trans_ovrd
-        .unwrap_or_else(|| get_level_translation(&ldtk_data, level_index));
+        .unwrap_or_else(|| get_level_translation(ldtk_data, world, level_index));
 
     let level_px = UVec2 {
         x: level.px_wid as u32,
         y: level.px_hei as u32,
     };
 
-    let background = load_background(level, translation, level_px, asset_server, config);
+    let background = load_background(&level, translation, level_px, asset_server, config);
 
     let mut ldtk_layers = LdtkLayers::new(
         level_entity,
         level.layer_instances.len(),
-        &ldtk_assets,
+        ldtk_assets,
         translation,
         config.z_index,
         loader.mode,
         background,
     );
 
-    for (layer_index, layer) in level.layer_instances.iter().enumerate() {
-        #[cfg(feature = "algorithm")]
-        if let Some(path) = addi_layers.path_layer.as_ref() {
-            if layer.identifier == path.identifier {
-                ldtk_layers
-                    .assign_path_layer(path.clone(), layer::path::analyze_path_layer(layer, path));
-                continue;
+    #[allow(unused_variables)]
+    let addi_layers = (*addi_layers).clone();
+    // the global entities that are already spawned are skipped
+    let global_entities = level
+        .layer_instances
+        .iter()
+        .flat_map(|layer| layer.entity_instances.iter())
+        .map(|entity| EntityIid(entity.iid.clone()))
+        .filter(|iid| global_entities.contains(iid))
+        .collect::<HashSet<_>>();
+
+    let task = AsyncComputeTaskPool::get().spawn(async move {
+        for (layer_index, layer) in level.layer_instances.iter().enumerate() {
+            #[cfg(feature = "algorithm")]
+            if let Some(path) = addi_layers.path_layer.as_ref() {
+                if layer.identifier == path.identifier {
+                    match layer::path::analyze_path_layer(layer, path) {
+                        Ok(tiles) => ldtk_layers.assign_path_layer(path.clone(), tiles),
+                        Err(e) => ldtk_layers.errors.push(e),
+                    }
+                    continue;
+                }
             }
-        }
 
-        #[cfg(feature = "physics")]
-        if let Some(phy) = addi_layers.physics_layer.as_ref() {
-            if layer.identifier == phy.identifier {
-                ldtk_layers.assign_physics_layer(
-                    phy.clone(),
-                    layer.int_grid_csv.clone(),
-                    UVec2 {
-                        x: layer.c_wid as u32,
-                        y: layer.c_hei as u32,
-                    },
-                );
-                continue;
+            #[cfg(feature = "physics")]
+            if let Some(phy) = addi_layers.physics_layer.as_ref() {
+                if layer.identifier == phy.identifier {
+                    ldtk_layers.assign_physics_layer(
+                        phy.clone(),
+                        layer.int_grid_csv.clone(),
+                        UVec2 {
+                            x: layer.c_wid as u32,
+                            y: layer.c_hei as u32,
+                        },
+                    );
+                    continue;
+                }
             }
-        }
 
-        load_layer(
-            layer_index,
-            layer,
-            &mut ldtk_layers,
-            translation,
-            config,
-            &global_entities,
-        );
-    }
+            if layer.ty == LayerType::IntGrid {
+                let int_grid = ldtk_layers
+                    .layer_defs
+                    .get(&layer.layer_def_uid)
+                    .map(|layer_def| layer::int_grid::LdtkIntGrid::new(layer, layer_def));
+                if let Some(int_grid) = int_grid {
+                    ldtk_layers.set_int_grid(layer_index, layer, int_grid);
+                }
+            }
 
-    ldtk_layers.apply_all(
-        commands,
-        patterns,
-        level,
-        entity_registry,
-        entity_tag_registry,
-        manager,
-        config,
-        ldtk_assets,
-        asset_server,
-    );
+            load_layer(
+                layer_index,
+                layer,
+                &mut ldtk_layers,
+                translation,
+                &global_entities,
+            );
+        }
 
-    ldtk_events.send(LdtkEvent::LevelLoaded(LevelEvent {
-        identifier: level.identifier.clone(),
-        iid: level.iid.clone(),
-    }));
+        (level, ldtk_layers)
+    });
+
+    Some(LdtkLoadingTask {
+        task,
+        world_iid: ldtk_data.get_world_iid(world).to_string(),
+    })
 }
 
 fn load_background(
@@ -400,11 +820,16 @@ fn load_layer(
     layer: &LayerInstance,
     ldtk_layers: &mut LdtkLayers,
     translation: Vec2,
-    config: &LdtkLoadConfig,
-    global_entities: &LdtkGlobalEntityRegistry,
+    global_entities: &HashSet<EntityIid>,
 ) {
     match layer.ty {
         LayerType::IntGrid | LayerType::AutoLayer => {
+            if !layer.auto_layer_tiles.is_empty() || ldtk_layers.has_auto_rules(layer) {
+                if let Err(e) = ldtk_layers.try_create_new_layer(layer_index, layer) {
+                    ldtk_layers.errors.push(e);
+                    return;
+                }
+            }
             layer.auto_layer_tiles.iter().for_each(|tile| {
                 ldtk_layers.set_tile(layer_index, layer, tile);
             });
@@ -416,6 +841,15 @@ fn load_layer(
                     continue;
                 }
 
+                if let Some(tile) = entity_instance.tile.as_ref() {
+                    if !ldtk_layers.tilesets.contains_key(&tile.tileset_uid) {
+                        ldtk_layers.errors.push(LdtkError::MissingEntityTileset {
+                            entity: entity_instance.identifier.clone(),
+                            tileset_uid: tile.tileset_uid,
+                        });
+                    }
+                }
+
                 let fields = entity_instance
                     .field_instances
                     .iter()
@@ -426,8 +860,8 @@ fn load_layer(
                     fields,
                     iid,
                     transform: LdtkTempTransform {
-                        level_translation: translation,
-                        z_index: config.z_index as f32
+                        level_translation: translation + layer::get_layer_offset(layer),
+                        z_index: ldtk_layers.base_z_index as f32
                             - layer_index as f32
                             - (1. - (order as f32 / layer.entity_instances.len() as f32)),
                     },
@@ -436,6 +870,12 @@ fn load_layer(
             }
         }
         LayerType::Tiles => {
+            if !layer.grid_tiles.is_empty() {
+                if let Err(e) = ldtk_layers.try_create_new_layer(layer_index, layer) {
+                    ldtk_layers.errors.push(e);
+                    return;
+                }
+            }
             layer.grid_tiles.iter().for_each(|tile| {
                 ldtk_layers.set_tile(layer_index, layer, tile);
             });
@@ -443,13 +883,23 @@ fn load_layer(
     }
 }
 
-fn get_level_translation(ldtk_data: &LdtkJson, index: usize) -> Vec2 {
-    let level = &ldtk_data.levels[index];
-    match ldtk_data.world_layout.unwrap() {
+fn get_level_translation(ldtk_data: &LdtkJson, world: Option<&World>, index: usize) -> Vec2 {
+    let (levels, world_layout) = ldtk_data.get_world(world);
+    let level = &levels[index];
+    match world_layout.unwrap_or(WorldLayout::Free) {
         WorldLayout::GridVania | WorldLayout::Free => Vec2 {
             x: level.world_x as f32,
             y: -level.world_y as f32,
         },
-        WorldLayout::LinearHorizontal | WorldLayout::LinearVertical => Vec2::ZERO,
+        // LDtk exports -1 as the world position of linear layouts,
+        // so the levels are placed next to each other in order.
+        WorldLayout::LinearHorizontal => Vec2 {
+            x: levels[..index].iter().map(|l| l.px_wid as f32).sum(),
+            y: 0.,
+        },
+        WorldLayout::LinearVertical => Vec2 {
+            x: 0.,
+            y: -levels[..index].iter().map(|l| l.px_hei as f32).sum::<f32>(),
+        },
     }
 }
@@ -0,0 +1,44 @@
+use bevy::{
+    ecs::{component::Component, query::With, system::Query},
+    math::{Vec2, Vec3Swizzles},
+    reflect::Reflect,
+    transform::components::GlobalTransform,
+};
+
+use crate::tilemap::map::TilemapTransform;
+
+/// Add this to the camera that drives the parallax layers.
+#[derive(Component, Debug, Default, Clone, Copy, Reflect)]
+pub struct LdtkParallaxCamera;
+
+/// A tile layer with parallax factors.
+///
+/// The layer scrolls with the `LdtkParallaxCamera` relative to the center of the level,
+/// the same way as it does in the editor.
+/// Parallax scaling is not supported as tilemaps can't be scaled.
+#[derive(Component, Debug, Clone, Copy, Reflect)]
+pub struct LdtkParallaxLayer {
+    pub factor: Vec2,
+    /// The translation of the tilemap when the camera is at the center of the level.
+    pub base_translation: Vec2,
+    pub level_center: Vec2,
+}
+
+pub fn ldtk_parallax(
+    cameras_query: Query<&GlobalTransform, With<LdtkParallaxCamera>>,
+    mut layers_query: Query<(&mut TilemapTransform, &LdtkParallaxLayer)>,
+) {
+    let Some(camera) = cameras_query.iter().next() else {
+        return;
+    };
+    let camera = camera.translation().xy();
+
+    layers_query
+        .par_iter_mut()
+        .for_each(|(mut transform, layer)| {
+            let translation = layer.base_translation + (camera - layer.level_center) * layer.factor;
+            if transform.translation != translation {
+                transform.translation = translation;
+            }
+        });
+}
@@ -0,0 +1,280 @@
+use std::any::TypeId;
+
+use bevy::{
+    asset::{Assets, Handle},
+    ecs::{
+        component::Component,
+        entity::Entity,
+        reflect::{AppTypeRegistry, ReflectComponent},
+        system::{Command, Commands, EntityCommands, Query, Res},
+        world::World,
+    },
+    hierarchy::{Children, Parent},
+    log::warn,
+    math::IVec2,
+    reflect::{DynamicEnum, FromReflect, Reflect, ReflectMut, TypeInfo, VariantInfo},
+    render::color::Color,
+    scene::DynamicScene,
+    transform::components::{GlobalTransform, Transform},
+    utils::HashMap,
+};
+
+use super::{
+    components::GlobalEntity,
+    json::{
+        field::{FieldInstance, FieldValue},
+        level::EntityInstance,
+    },
+    resources::{LdtkAssets, LdtkLevelManager},
+    traits::PhantomLdtkEntityTrait,
+};
+
+/// An ldtk entity spawned from the first entity of a `DynamicScene`.
+///
+/// Register it using `App::register_ldtk_prefab`. The components of the scene
+/// are inserted once the scene is loaded, and the fields of the ldtk entity are injected
+/// into the fields of the components with the same names.
+///
+/// Supported field types are `i32`, `f32`, `bool`, `String`, `Color`, `IVec2` for points
+/// in the grid coordinates of LDtk, `Option`s and `Vec`s of them and enums with unit variants.
+///
+/// The entity keeps its position in the level, so only the rotation and the scale of the
+/// `Transform` in the scene are used. Components holding `Entity`s are not supported,
+/// as the entities of the scene are not mapped. `Parent` and `Children` are skipped.
+#[derive(Debug, Clone)]
+pub struct LdtkPrefab {
+    pub scene: Handle<DynamicScene>,
+    pub spawn_sprite: bool,
+    pub global_entity: bool,
+}
+
+impl LdtkPrefab {
+    pub fn new(scene: Handle<DynamicScene>) -> Self {
+        Self {
+            scene,
+            spawn_sprite: false,
+            global_entity: false,
+        }
+    }
+
+    /// Spawn the sprite of the entity like `#[spawn_sprite]`.
+    pub fn with_sprite(mut self) -> Self {
+        self.spawn_sprite = true;
+        self
+    }
+
+    /// Make the entity a `GlobalEntity` like `#[global_entity]`.
+    pub fn as_global(mut self) -> Self {
+        self.global_entity = true;
+        self
+    }
+}
+
+impl PhantomLdtkEntityTrait for LdtkPrefab {
+    fn spawn(
+        &self,
+        commands: &mut EntityCommands,
+        entity_instance: &EntityInstance,
+        fields: &HashMap<String, FieldInstance>,
+        _asset_server: &bevy::asset::AssetServer,
+        _ldtk_manager: &LdtkLevelManager,
+        ldtk_assets: &LdtkAssets,
+    ) {
+        if self.spawn_sprite {
+            entity_instance.generate_sprite(commands, ldtk_assets);
+        }
+        if self.global_entity {
+            commands.insert(GlobalEntity);
+        }
+
+        commands.insert(LdtkPendingPrefab {
+            scene: self.scene.clone(),
+            fields: fields.clone(),
+        });
+    }
+}
+
+/// The prefab waiting for its scene to be loaded.
+#[derive(Component)]
+pub struct LdtkPendingPrefab {
+    pub(crate) scene: Handle<DynamicScene>,
+    pub(crate) fields: HashMap<String, FieldInstance>,
+}
+
+pub fn ldtk_prefab_spawner(
+    mut commands: Commands,
+    prefabs_query: Query<(Entity, &LdtkPendingPrefab)>,
+    scenes: Option<Res<Assets<DynamicScene>>>,
+) {
+    let Some(scenes) = scenes else {
+        return;
+    };
+
+    prefabs_query.for_each(|(entity, prefab)| {
+        let Some(scene) = scenes.get(&prefab.scene) else {
+            return;
+        };
+
+        let components = scene
+            .entities
+            .first()
+            .map(|scene_entity| {
+                scene_entity
+                    .components
+                    .iter()
+                    .map(|component| {
+                        let mut component = component.clone_value();
+                        inject_fields(&mut *component, &prefab.fields);
+                        component
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        commands.entity(entity).remove::<LdtkPendingPrefab>();
+        commands.add(InsertPrefabComponents { entity, components });
+    });
+}
+
+struct InsertPrefabComponents {
+    entity: Entity,
+    components: Vec<Box<dyn Reflect>>,
+}
+
+impl Command for InsertPrefabComponents {
+    fn apply(self, world: &mut World) {
+        let type_registry = world.resource::<AppTypeRegistry>().clone();
+        let type_registry = type_registry.read();
+        let Some(mut entity) = world.get_entity_mut(self.entity) else {
+            return;
+        };
+
+        for component in self.components {
+            let type_id = component
+                .get_represented_type_info()
+                .map(|info| info.type_id());
+            if type_id == Some(TypeId::of::<Transform>()) {
+                // keep the position of the ldtk entity
+                let Some(scene_transform) = Transform::from_reflect(&*component) else {
+                    continue;
+                };
+                match entity.get_mut::<Transform>() {
+                    Some(mut transform) => {
+                        transform.rotation = scene_transform.rotation;
+                        transform.scale = scene_transform.scale;
+                    }
+                    None => {
+                        entity.insert(scene_transform);
+                    }
+                }
+                continue;
+            }
+            // propagated from the `Transform`, and the hierarchy of the scene isn't spawned
+            if type_id == Some(TypeId::of::<GlobalTransform>())
+                || type_id == Some(TypeId::of::<Parent>())
+                || type_id == Some(TypeId::of::<Children>())
+            {
+                continue;
+            }
+
+            let Some(reflect_component) = component
+                .get_represented_type_info()
+                .and_then(|info| type_registry.get(info.type_id()))
+                .and_then(|registration| registration.data::<ReflectComponent>())
+            else {
+                warn!(
+                    "Could not insert {} as it's not a registered component!",
+                    component.reflect_type_path()
+                );
+                continue;
+            };
+
+            reflect_component.apply_or_insert(&mut entity, &*component);
+        }
+    }
+}
+
+fn inject_fields(component: &mut dyn Reflect, fields: &HashMap<String, FieldInstance>) {
+    let ReflectMut::Struct(component) = component.reflect_mut() else {
+        return;
+    };
+
+    for (identifier, field) in fields.iter() {
+        let (Some(target), Some(value)) = (component.field_mut(identifier), field.value.as_ref())
+        else {
+            continue;
+        };
+
+        let is_injected = match value {
+            FieldValue::LocalEnum((_, variant)) | FieldValue::ExternEnum((_, variant)) => {
+                inject_enum(target, variant)
+            }
+            _ => {
+                let expected = target
+                    .get_represented_type_info()
+                    .map(|info| info.type_id());
+                match get_candidates(value).into_iter().find(|candidate| {
+                    candidate
+                        .get_represented_type_info()
+                        .map(|info| info.type_id())
+                        == expected
+                }) {
+                    Some(candidate) => {
+                        target.apply(&*candidate);
+                        true
+                    }
+                    None => false,
+                }
+            }
+        };
+
+        if !is_injected {
+            warn!(
+                "Could not inject the field {:?} into {} as the types don't match!",
+                identifier,
+                component.reflect_type_path()
+            );
+        }
+    }
+}
+
+fn inject_enum(target: &mut dyn Reflect, variant: &str) -> bool {
+    let Some(TypeInfo::Enum(info)) = target.get_represented_type_info() else {
+        return false;
+    };
+    if !matches!(info.variant(variant), Some(VariantInfo::Unit(_))) {
+        return false;
+    }
+
+    target.apply(&DynamicEnum::new(variant, ()));
+    true
+}
+
+/// The values the field can be injected as.
+fn get_candidates(value: &FieldValue) -> Vec<Box<dyn Reflect>> {
+    match value {
+        FieldValue::Integer(x) => vec![Box::new(*x), Box::new(Some(*x))],
+        FieldValue::Float(x) => vec![Box::new(*x), Box::new(Some(*x))],
+        FieldValue::Bool(x) => vec![Box::new(*x), Box::new(Some(*x))],
+        FieldValue::String(x) => vec![Box::new(x.clone()), Box::new(Some(x.clone()))],
+        FieldValue::Color(x) => {
+            let color: Color = (*x).into();
+            vec![Box::new(color), Box::new(Some(color))]
+        }
+        FieldValue::Point(x) => {
+            let point = IVec2::new(x.cx, x.cy);
+            vec![Box::new(point), Box::new(Some(point))]
+        }
+        FieldValue::IntegerArray(x) => vec![Box::new(x.clone())],
+        FieldValue::FloatArray(x) => vec![Box::new(x.clone())],
+        FieldValue::BoolArray(x) => vec![Box::new(x.clone())],
+        FieldValue::StringArray(x) => vec![Box::new(x.clone())],
+        FieldValue::ColorArray(x) => vec![Box::new(
+            x.iter().map(|c| (*c).into()).collect::<Vec<Color>>(),
+        )],
+        FieldValue::PointArray(x) => vec![Box::new(
+            x.iter().map(|p| IVec2::new(p.cx, p.cy)).collect::<Vec<_>>(),
+        )],
+        _ => Vec::new(),
+    }
+}
@@ -1,10 +1,10 @@
-use std::{fs::read_to_string, path::Path};
+use std::{ops::Deref, path::Path, sync::Arc};
 
 use bevy::{
-    asset::{AssetServer, Assets, Handle},
+    asset::{AssetPath, AssetServer, Assets, Handle},
     ecs::{
         entity::Entity,
-        system::{Commands, Resource},
+        system::{Commands, Res, ResMut, Resource, SystemParam},
     },
     log::error,
     math::{IVec2, UVec2, Vec2},
@@ -19,12 +19,16 @@ use bevy::{
 
 use crate::{
     serializing::pattern::TilemapPattern,
-    tilemap::map::{TilemapRotation, TilemapTexture, TilemapTextureDescriptor},
+    tilemap::map::{TilemapRotation, TilemapTexture, TilemapTextureDescriptor, TilemapTextureMode},
 };
 
 use super::{
     components::EntityIid,
-    json::{definitions::EntityDef, EntityRef, LdtkJson, TocInstance},
+    json::{
+        definitions::{EntityDef, LayerDef},
+        level::Level,
+        EntityRef, LdtkJson, TocInstance,
+    },
     sprite::{AtlasRect, LdtkEntityMaterial},
     LdtkLoader, LdtkLoaderMode, LdtkUnloader,
 };
@@ -53,16 +57,18 @@ impl LdtkPatterns {
     pub fn get_with_ident(
         &self,
         identifier: String,
-    ) -> &(Vec<(TilemapPattern, TilemapTexture)>, SpriteBundle) {
-        self.patterns.get(&identifier).unwrap()
+    ) -> Option<&(Vec<(TilemapPattern, TilemapTexture)>, SpriteBundle)> {
+        self.patterns.get(&identifier)
     }
 
     #[inline]
     pub fn get_with_index(
         &self,
         index: u8,
-    ) -> &(Vec<(TilemapPattern, TilemapTexture)>, SpriteBundle) {
-        self.patterns.get(&self.idents[&index]).unwrap()
+    ) -> Option<&(Vec<(TilemapPattern, TilemapTexture)>, SpriteBundle)> {
+        self.idents
+            .get(&index)
+            .and_then(|ident| self.patterns.get(ident))
     }
 
     #[inline]
@@ -81,7 +87,9 @@ impl LdtkPatterns {
         &self,
         index: u8,
     ) -> Option<&crate::tilemap::physics::DataPhysicsTilemap> {
-        self.physics_patterns.get(&self.idents[&index])
+        self.idents
+            .get(&index)
+            .and_then(|ident| self.physics_patterns.get(ident))
     }
 
     #[inline]
@@ -90,15 +98,30 @@ impl LdtkPatterns {
     }
 }
 
+/// The `LdtkAssets` and the resources used to create them.
+#[derive(SystemParam)]
+pub struct LdtkAssetParams<'w> {
+    pub ldtk_assets: ResMut<'w, LdtkAssets>,
+    pub config: Res<'w, LdtkLoadConfig>,
+    pub asset_server: Res<'w, AssetServer>,
+    pub atlas_assets: ResMut<'w, Assets<TextureAtlas>>,
+    pub entity_material_assets: ResMut<'w, Assets<LdtkEntityMaterial>>,
+    pub mesh_assets: ResMut<'w, Assets<Mesh>>,
+}
+
 #[derive(Resource, Default, Reflect)]
 pub struct LdtkAssets {
     pub(crate) associated_file: String,
-    /// tileset iid to texture
-    pub(crate) tilesets: HashMap<i32, TilemapTexture>,
+    /// tileset iid to texture, shared with the tasks building the levels
+    #[reflect(ignore)]
+    pub(crate) tilesets: Arc<HashMap<i32, TilemapTexture>>,
     /// tileset iid to texture atlas handle
     pub(crate) atlas_handles: HashMap<i32, Handle<TextureAtlas>>,
     /// entity identifier to entity definition
     pub(crate) entity_defs: HashMap<String, EntityDef>,
+    /// layer uid to layer definition, shared with the tasks building the levels
+    #[reflect(ignore)]
+    pub(crate) layer_defs: Arc<HashMap<i32, LayerDef>>,
     /// entity iid to mesh handle
     pub(crate) meshes: HashMap<String, Mesh2dHandle>,
     /// entity iid to material handle
@@ -106,28 +129,33 @@ pub struct LdtkAssets {
 }
 
 impl LdtkAssets {
-    pub fn get_tileset(&self, tileset_uid: i32) -> &TilemapTexture {
-        self.tilesets.get(&tileset_uid).unwrap()
+    pub fn get_tileset(&self, tileset_uid: i32) -> Option<&TilemapTexture> {
+        self.tilesets.get(&tileset_uid)
+    }
+
+    pub fn clone_atlas_handle(&self, tileset_uid: i32) -> Option<Handle<TextureAtlas>> {
+        self.atlas_handles.get(&tileset_uid).cloned()
     }
 
-    pub fn clone_atlas_handle(&self, tileset_uid: i32) -> Handle<TextureAtlas> {
-        self.atlas_handles.get(&tileset_uid).unwrap().clone()
+    pub fn get_entity_def(&self, identifier: &String) -> Option<&EntityDef> {
+        self.entity_defs.get(identifier)
     }
 
-    pub fn get_entity_def(&self, identifier: &String) -> &EntityDef {
-        self.entity_defs.get(identifier).unwrap()
+    pub fn get_layer_def(&self, layer_uid: i32) -> Option<&LayerDef> {
+        self.layer_defs.get(&layer_uid)
     }
 
-    pub fn clone_mesh_handle(&self, iid: &String) -> Mesh2dHandle {
-        self.meshes.get(iid).unwrap().clone()
+    pub fn clone_mesh_handle(&self, iid: &String) -> Option<Mesh2dHandle> {
+        self.meshes.get(iid).cloned()
     }
 
-    pub fn clone_material_handle(&self, iid: &String) -> Handle<LdtkEntityMaterial> {
-        self.materials.get(iid).unwrap().clone()
+    pub fn clone_material_handle(&self, iid: &String) -> Option<Handle<LdtkEntityMaterial>> {
+        self.materials.get(iid).cloned()
     }
 
     /// Initialize the assets.
     ///
+    /// This is called when the ldtk file is loaded or modified.
     /// You need to call this after you changed something like the size of an entity,
     /// or maybe the identifier of an entity.
     pub fn initialize(
@@ -139,19 +167,32 @@ impl LdtkAssets {
         material_assets: &mut Assets<LdtkEntityMaterial>,
         mesh_assets: &mut Assets<Mesh>,
     ) {
+        let Some(ldtk_data) = manager.get_cached_data() else {
+            return;
+        };
+
         self.associated_file = config.file_path.clone();
-        self.load_texture(config, manager, asset_server, atlas_assets);
-        self.load_entities(config, manager, material_assets, mesh_assets);
+        self.load_texture(config, ldtk_data, asset_server, atlas_assets);
+        self.load_entities(config, ldtk_data, material_assets, mesh_assets);
+        self.layer_defs = Arc::new(
+            ldtk_data
+                .defs
+                .layers
+                .iter()
+                .map(|layer| (layer.uid, layer.clone()))
+                .collect(),
+        );
     }
 
     fn load_texture(
         &mut self,
         config: &LdtkLoadConfig,
-        manager: &LdtkLevelManager,
+        ldtk_data: &LdtkJson,
         asset_server: &AssetServer,
         atlas_assets: &mut Assets<TextureAtlas>,
     ) {
-        let ldtk_data = manager.get_cached_data();
+        let mut tilesets = HashMap::new();
+        self.atlas_handles.clear();
         ldtk_data.defs.tilesets.iter().for_each(|tileset| {
             let Some(path) = tileset.rel_path.as_ref() else {
                 return;
@@ -168,49 +209,65 @@ impl LdtkAssets {
                     y: tileset.tile_grid_size as u32,
                 },
                 filter_mode: config.filter_mode,
+                mode: config.texture_mode,
             };
-            let texture = TilemapTexture {
-                texture,
-                desc,
-                rotation: TilemapRotation::None,
-            };
+            let texture = TilemapTexture::new(texture, desc, TilemapRotation::None);
 
-            self.tilesets.insert(tileset.uid, texture.clone());
             self.atlas_handles
                 .insert(tileset.uid, atlas_assets.add(texture.as_texture_atlas()));
+            tilesets.insert(tileset.uid, texture);
         });
+        self.tilesets = Arc::new(tilesets);
     }
 
     fn load_entities(
         &mut self,
         config: &LdtkLoadConfig,
-        manager: &LdtkLevelManager,
+        ldtk_data: &LdtkJson,
         material_assets: &mut Assets<LdtkEntityMaterial>,
         mesh_assets: &mut Assets<Mesh>,
     ) {
-        let ldtk_data = manager.get_cached_data();
         ldtk_data.defs.entities.iter().for_each(|entity| {
             self.entity_defs
                 .insert(entity.identifier.clone(), entity.clone());
         });
 
-        ldtk_data
-            .levels
+        ldtk_data.iter_levels().for_each(|level| {
+            self.load_level_entities(config, ldtk_data, level, material_assets, mesh_assets);
+        });
+    }
+
+    /// Create the materials and meshes of the entities in the level.
+    ///
+    /// External levels are not included in `initialize`,
+    /// so this is called when they are loaded.
+    pub(crate) fn load_level_entities(
+        &mut self,
+        config: &LdtkLoadConfig,
+        ldtk_data: &LdtkJson,
+        level: &Level,
+        material_assets: &mut Assets<LdtkEntityMaterial>,
+        mesh_assets: &mut Assets<Mesh>,
+    ) {
+        level
+            .layer_instances
             .iter()
-            .map(|level| level.layer_instances.iter())
-            .flatten()
             .map(|layer| layer.entity_instances.iter())
             .flatten()
             .for_each(|entity_instance| {
                 let Some(tile_rect) = entity_instance.tile.as_ref() else {
                     return;
                 };
+                // reported when the entity is loaded
+                let Some(tileset) = self.tilesets.get(&tile_rect.tileset_uid) else {
+                    return;
+                };
 
-                let texture_size = self.get_tileset(tile_rect.tileset_uid).desc.size.as_vec2();
+                let texture_size = tileset.desc.size.as_vec2();
                 self.materials.insert(
                     entity_instance.iid.clone(),
                     material_assets.add(LdtkEntityMaterial {
-                        texture: self.get_tileset(tile_rect.tileset_uid).texture.clone(),
+                        texture: tileset.texture.clone(),
                         atlas_rect: AtlasRect {
                             min: IVec2::new(tile_rect.x_pos, tile_rect.y_pos).as_vec2()
                                 / texture_size,
@@ -270,7 +327,7 @@ pub struct LdtkWfcManager {
 impl LdtkWfcManager {
     pub fn get_ident(&self, level_index: UVec2) -> Option<String> {
         let idx = self.wfc_data.as_ref()?.get(level_index)?;
-        Some(self.idents[&idx].clone())
+        self.idents.get(&idx).cloned()
     }
 
     pub fn get_translation(&self, level_index: IVec2) -> Vec2 {
@@ -291,7 +348,7 @@ impl LdtkTocs {
     }
 }
 
-#[derive(Resource, Default, Reflect)]
+#[derive(Resource, Default, Reflect, Clone)]
 pub struct LdtkAdditionalLayers {
     #[cfg(feature = "algorithm")]
     pub path_layer: Option<super::layer::path::LdtkPathLayer>,
@@ -301,84 +358,209 @@ pub struct LdtkAdditionalLayers {
 
 #[derive(Resource, Default, Reflect)]
 pub struct LdtkLoadConfig {
+    /// The asset path of the ldtk file.
     pub file_path: String,
     pub asset_path_prefix: String,
     #[reflect(ignore)]
     pub filter_mode: FilterMode,
+    /// The mode of the textures of all the tilesets.
+    pub texture_mode: TilemapTextureMode,
     pub z_index: i32,
     pub ignore_unregistered_entities: bool,
     pub ignore_unregistered_entity_tags: bool,
+    /// Spawn all the tile layers of a level as one tilemap, with a texture slot for each tileset.
+    /// The tilesets must have the same tile size, or the layers are spawned separately.
+    ///
+    /// Only works in `LdtkLoaderMode::Tilemap`. The auto-layer rules of merged layers
+    /// are not applied when the `LdtkIntGrid`s change.
+    pub merge_tile_layers: bool,
+    /// The maximum number of tiles spawned in a frame, shared by all the loading levels.
+    /// All the tiles of a level are spawned in one frame if this is `None`.
+    pub max_tiles_per_frame: Option<usize>,
 }
 
 #[derive(Resource, Default, Reflect)]
 pub struct LdtkLevelManager {
-    pub(crate) ldtk_json: Option<LdtkJson>,
+    /// Shared with the tasks building the levels.
+    #[reflect(ignore)]
+    pub(crate) ldtk_json: Option<Arc<LdtkJson>>,
+    pub(crate) handle: Option<Handle<LdtkJson>>,
     pub(crate) loaded_levels: HashMap<String, Entity>,
+    /// Level identifier to the handle of the external level file.
+    pub(crate) external_levels: HashMap<String, Handle<Level>>,
+    /// `load_all_patterns` is called before the ldtk file is loaded.
+    pub(crate) patterns_requested: bool,
 }
 
 impl LdtkLevelManager {
-    /// Reloads the ldtk file and refresh the level cache.
-    pub fn reload_json(&mut self, config: &LdtkLoadConfig) {
+    /// Start loading the ldtk file using the `AssetServer`.
+    ///
+    /// The level cache is refreshed when the file is loaded,
+    /// and the loaded levels that are changed will be reloaded.
+    pub fn initialize(&mut self, config: &LdtkLoadConfig, asset_server: &AssetServer) {
         if config.file_path.is_empty() {
             error!("No specified ldtk level file path!");
             return;
         }
 
-        let path = std::env::current_dir().unwrap().join(&config.file_path);
-        let str_raw = match read_to_string(&path) {
-            Ok(data) => data,
-            Err(e) => panic!("Could not read file at path: {:?}!\n{}", path, e),
+        self.handle = Some(asset_server.load(config.file_path.clone()));
+    }
+
+    /// Reloads the ldtk file and refresh the level cache.
+    ///
+    /// This is not needed if the `file_watcher` feature of bevy is enabled.
+    pub fn reload_json(&mut self, config: &LdtkLoadConfig, asset_server: &AssetServer) {
+        if self.handle.is_none() {
+            self.initialize(config, asset_server);
+        } else {
+            asset_server.reload(config.file_path.clone());
+        }
+    }
+
+    /// Returns `None` if the ldtk file is not loaded yet.
+    pub fn get_cached_data(&self) -> Option<&LdtkJson> {
+        self.ldtk_json.as_deref()
+    }
+
+    /// Start loading the level file if the project option "Save levels separately" is enabled.
+    pub(crate) fn request_level(
+        &mut self,
+        world: Option<&str>,
+        level: &String,
+        config: &LdtkLoadConfig,
+        asset_server: &AssetServer,
+    ) {
+        if self.external_levels.contains_key(level) {
+            return;
+        }
+
+        let Some(rel_path) = self
+            .find_level(world, level)
+            .and_then(|level| level.external_rel_path.as_ref())
+        else {
+            return;
         };
 
-        self.ldtk_json = match serde_json::from_str::<LdtkJson>(&str_raw) {
-            Ok(data) => Some(data),
-            Err(e) => panic!("Could not parse file at path: {}!\n{}", config.file_path, e),
+        match AssetPath::parse(&config.file_path).resolve_embed(rel_path) {
+            Ok(path) => {
+                self.external_levels
+                    .insert(level.clone(), asset_server.load(path));
+            }
+            Err(e) => error!("Invalid external level path {:?}! {}", rel_path, e),
+        }
+    }
+
+    /// Get the level with the layer instances. The level and the world can be
+    /// either the identifier or the iid.
+    ///
+    /// Returns `None` if the level is in an external file that is not loaded yet.
+    pub fn get_level<'a>(
+        &'a self,
+        world: Option<&str>,
+        level: &String,
+        level_assets: &'a Assets<Level>,
+    ) -> Option<&'a Level> {
+        let cached = self.find_level(world, level)?;
+
+        if cached.external_rel_path.is_none() {
+            Some(cached)
+        } else {
+            level_assets.get(self.external_levels.get(level)?)
+        }
+    }
+
+    /// Get the level like `get_level`, without cloning the levels in the ldtk file.
+    pub(crate) fn get_shared_level(
+        &self,
+        world: Option<&str>,
+        level: &String,
+        level_assets: &Assets<Level>,
+    ) -> Option<SharedLevel> {
+        let ldtk_data = self.ldtk_json.as_ref()?;
+        let (world_index, index) = ldtk_data.find_level_index(world, level)?;
+        let shared = SharedLevel::Cached {
+            ldtk_data: ldtk_data.clone(),
+            world: world_index,
+            index,
         };
+
+        if shared.external_rel_path.is_none() {
+            Some(shared)
+        } else {
+            // the assets can't be shared, so the external levels are cloned
+            let external = level_assets.get(self.external_levels.get(level)?)?;
+            Some(SharedLevel::External(Arc::new(external.clone())))
+        }
     }
 
-    pub fn get_cached_data(&self) -> &LdtkJson {
-        self.check_initialized();
-        self.ldtk_json.as_ref().unwrap()
+    fn find_level(&self, world: Option<&str>, level: &str) -> Option<&Level> {
+        self.get_cached_data()?.get_level(world, level)
     }
 
+    /// Load the level using its identifier or iid.
+    ///
+    /// If Multi-worlds is enabled, all the worlds are searched.
+    /// Use `load_in_world` or the iid if the identifier is used in more than one world.
     pub fn load(&mut self, commands: &mut Commands, level: String, trans_ovrd: Option<Vec2>) {
-        let level = level.to_string();
+        self.spawn_loader(commands, None, level, trans_ovrd);
+    }
 
-        if self.loaded_levels.contains_key(&level.to_string()) {
+    /// Load the level in the world. Both of them can be either the identifier or the iid.
+    pub fn load_in_world(
+        &mut self,
+        commands: &mut Commands,
+        world: String,
+        level: String,
+        trans_ovrd: Option<Vec2>,
+    ) {
+        self.spawn_loader(commands, Some(world), level, trans_ovrd);
+    }
+
+    fn spawn_loader(
+        &mut self,
+        commands: &mut Commands,
+        world: Option<String>,
+        level: String,
+        trans_ovrd: Option<Vec2>,
+    ) {
+        if self.loaded_levels.contains_key(&level) {
             error!("Trying to load {:?} that is already loaded!", level);
         } else {
             let entity = commands.spawn(LdtkLoader {
                 level: level.clone(),
+                world,
                 mode: LdtkLoaderMode::Tilemap,
                 trans_ovrd,
             });
-            self.loaded_levels.insert(level.clone(), entity.id());
+            self.loaded_levels.insert(level, entity.id());
         }
     }
 
+    /// Load all the levels as patterns.
+    ///
+    /// If the ldtk file is not loaded yet, the patterns are loaded when it is.
     pub fn load_all_patterns(&mut self, commands: &mut Commands) {
-        self.check_initialized();
-
-        self.ldtk_json
-            .as_ref()
-            .unwrap()
-            .levels
-            .iter()
-            .for_each(|level| {
-                if self.loaded_levels.contains_key(&level.identifier) {
-                    error!("Trying to load {:?} that is already loaded!", level);
-                } else {
-                    commands.spawn(LdtkLoader {
-                        level: level.identifier.clone(),
-                        mode: LdtkLoaderMode::MapPattern,
-                        trans_ovrd: None,
-                    });
-                }
-            });
+        let Some(ldtk_data) = self.ldtk_json.as_ref() else {
+            self.patterns_requested = true;
+            return;
+        };
+        self.patterns_requested = false;
+
+        ldtk_data.iter_levels().for_each(|level| {
+            if self.loaded_levels.contains_key(&level.identifier) {
+                error!("Trying to load {:?} that is already loaded!", level);
+            } else {
+                commands.spawn(LdtkLoader {
+                    level: level.identifier.clone(),
+                    world: None,
+                    mode: LdtkLoaderMode::MapPattern,
+                    trans_ovrd: None,
+                });
+            }
+        });
     }
 
     pub fn switch_to(&mut self, commands: &mut Commands, level: String, trans_ovrd: Option<Vec2>) {
-        self.check_initialized();
         if self.loaded_levels.contains_key(&level.to_string()) {
             error!("Trying to load {:?} that is already loaded!", level);
         } else {
@@ -387,11 +569,27 @@ impl LdtkLevelManager {
         }
     }
 
+    pub fn switch_to_world(
+        &mut self,
+        commands: &mut Commands,
+        world: String,
+        level: String,
+        trans_ovrd: Option<Vec2>,
+    ) {
+        if self.loaded_levels.contains_key(&level) {
+            error!("Trying to load {:?} that is already loaded!", level);
+        } else {
+            self.unload_all(commands);
+            self.load_in_world(commands, world, level, trans_ovrd);
+        }
+    }
+
     pub fn unload(&mut self, commands: &mut Commands, level: String) {
         let level = level.to_string();
         if let Some(l) = self.loaded_levels.get(&level) {
             commands.entity(*l).insert(LdtkUnloader);
             self.loaded_levels.remove(&level);
+            self.external_levels.remove(&level);
         } else {
             error!("Trying to unload {:?} that is not loaded!", level);
         }
@@ -402,6 +600,7 @@ impl LdtkLevelManager {
             commands.entity(*l).insert(LdtkUnloader);
         }
         self.loaded_levels.clear();
+        self.external_levels.clear();
     }
 
     pub fn is_loaded(&self, level: String) -> bool {
@@ -411,16 +610,40 @@ impl LdtkLevelManager {
     pub fn is_initialized(&self) -> bool {
         self.ldtk_json.is_some()
     }
+}
 
-    fn check_initialized(&self) {
-        assert!(
-            self.is_initialized(),
-            "LdtkLevelManager is not initialized!"
-        );
+/// A level shared with the task building its layers.
+#[derive(Clone)]
+pub(crate) enum SharedLevel {
+    /// A level in the ldtk file, in the world at the index if Multi-worlds is enabled.
+    Cached {
+        ldtk_data: Arc<LdtkJson>,
+        world: Option<usize>,
+        index: usize,
+    },
+    /// A level in an external level file.
+    External(Arc<Level>),
+}
+
+impl Deref for SharedLevel {
+    type Target = Level;
+
+    fn deref(&self) -> &Self::Target {
+        match self {
+            SharedLevel::Cached {
+                ldtk_data,
+                world: Some(world),
+                index,
+            } => &ldtk_data.worlds[*world].levels[*index],
+            SharedLevel::Cached {
+                ldtk_data, index, ..
+            } => &ldtk_data.levels[*index],
+            SharedLevel::External(level) => level,
+        }
     }
 }
 
-#[derive(Resource, Default, Reflect)]
+#[derive(Resource, Default, Reflect, Clone)]
 pub struct LdtkGlobalEntityRegistry(pub(crate) HashMap<EntityIid, Entity>);
 
 impl LdtkGlobalEntityRegistry {
@@ -51,7 +51,7 @@ impl Material2d for LdtkEntityMaterial {
     }
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone, Reflect)]
+#[derive(Serialize, Deserialize, Debug, Clone, Reflect, PartialEq)]
 pub enum TileRenderMode {
     Cover,
     FitInside,
@@ -198,7 +198,7 @@ impl TileRenderMode {
     }
 }
 
-#[derive(Serialize, Debug, Clone, Copy, Reflect)]
+#[derive(Serialize, Debug, Clone, Copy, Reflect, PartialEq)]
 pub struct NineSliceBorders {
     pub is_valid: bool,
     pub up: i32,
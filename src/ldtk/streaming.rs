@@ -0,0 +1,184 @@
+use bevy::{
+    ecs::{
+        component::Component,
+        system::{Commands, Query, ResMut},
+    },
+    log::warn,
+    math::Vec2,
+    reflect::Reflect,
+    utils::{HashMap, HashSet},
+};
+
+use crate::math::{aabb::Aabb2d, CameraAabb2d};
+
+use super::{
+    json::{level::Level, LdtkJson, World, WorldLayout},
+    resources::LdtkLevelManager,
+};
+
+/// Add this to a camera to load the levels around it automatically.
+///
+/// Only works for `GridVania` and `Free` world layouts. The streamed levels are
+/// addressed by their iids in `LdtkLevelManager`.
+#[derive(Component, Debug, Clone, Reflect)]
+pub struct LdtkLevelStreamer {
+    pub(crate) world: Option<String>,
+    pub(crate) load_scale: f32,
+    pub(crate) unload_scale: f32,
+    pub(crate) loaded: HashSet<String>,
+}
+
+impl LdtkLevelStreamer {
+    /// These scales are used to scale the camera aabb for:
+    /// - If a level is intersected with the load aabb, then it will be loaded.
+    /// - If a level is not intersected with the unload aabb, then it will be unloaded.
+    ///
+    /// Levels are found through the neighbours of the loaded levels.
+    /// Others are only found when none of the loaded levels are in the load aabb.
+    pub fn new(load_scale: f32, unload_scale: f32) -> Self {
+        assert!(
+            unload_scale >= load_scale,
+            "unload_scale must be >= load_scale!"
+        );
+
+        Self {
+            world: None,
+            load_scale,
+            unload_scale,
+            loaded: HashSet::new(),
+        }
+    }
+
+    /// Stream the levels in this world, using its identifier or iid.
+    ///
+    /// By default, the levels of the first world are streamed.
+    pub fn with_world(mut self, world: String) -> Self {
+        self.world = Some(world);
+        self
+    }
+}
+
+pub fn ldtk_level_streaming(
+    mut commands: Commands,
+    mut streamers_query: Query<(&CameraAabb2d, &mut LdtkLevelStreamer)>,
+    mut manager: ResMut<LdtkLevelManager>,
+) {
+    if !manager.is_initialized() {
+        return;
+    }
+
+    streamers_query.for_each_mut(|(cam_aabb, mut streamer)| {
+        // the levels may be unloaded by others
+        streamer.loaded.retain(|iid| manager.is_loaded(iid.clone()));
+
+        let Some(ldtk_data) = manager.get_cached_data() else {
+            return;
+        };
+        let Some(world) = find_world(ldtk_data, streamer.world.as_deref()) else {
+            warn!("Failed to find the world {:?} to stream!", streamer.world);
+            return;
+        };
+        let (levels, world_layout) = ldtk_data.get_world(world);
+        if let Some(WorldLayout::LinearHorizontal | WorldLayout::LinearVertical) = world_layout {
+            warn!("Level streaming only works for GridVania and Free world layouts!");
+            return;
+        }
+        let world_iid = ldtk_data.get_world_iid(world).to_string();
+
+        let load_aabb = cam_aabb
+            .0
+            .with_scale(Vec2::splat(streamer.load_scale), Vec2::splat(0.5));
+        let unload_aabb = cam_aabb
+            .0
+            .with_scale(Vec2::splat(streamer.unload_scale), Vec2::splat(0.5));
+        let indices = levels
+            .iter()
+            .enumerate()
+            .map(|(index, level)| (level.iid.as_str(), index))
+            .collect::<HashMap<_, _>>();
+
+        let mut queue = streamer
+            .loaded
+            .iter()
+            .filter_map(|iid| indices.get(iid.as_str()).copied())
+            .filter(|index| load_aabb.is_intersected(get_level_aabb(&levels[*index])))
+            .collect::<Vec<_>>();
+        if queue.is_empty() {
+            queue = (0..levels.len())
+                .filter(|index| load_aabb.is_intersected(get_level_aabb(&levels[*index])))
+                .collect();
+        }
+
+        let mut visible = HashSet::new();
+        while let Some(index) = queue.pop() {
+            if !visible.insert(index) {
+                continue;
+            }
+
+            levels[index]
+                .neighbours
+                .iter()
+                .filter_map(|neighbour| indices.get(neighbour.level_iid.as_str()).copied())
+                .filter(|index| {
+                    !visible.contains(index)
+                        && load_aabb.is_intersected(get_level_aabb(&levels[*index]))
+                })
+                .for_each(|index| queue.push(index));
+        }
+
+        let to_load = visible
+            .into_iter()
+            .map(|index| &levels[index])
+            .filter(|level| {
+                !streamer.loaded.contains(&level.iid)
+                    && !manager.is_loaded(level.iid.clone())
+                    && !manager.is_loaded(level.identifier.clone())
+            })
+            .map(|level| level.iid.clone())
+            .collect::<Vec<_>>();
+        let to_unload = streamer
+            .loaded
+            .iter()
+            .filter(|iid| {
+                indices.get(iid.as_str()).map_or(true, |index| {
+                    !unload_aabb.is_intersected(get_level_aabb(&levels[*index]))
+                })
+            })
+            .cloned()
+            .collect::<Vec<_>>();
+        drop(indices);
+
+        for iid in to_unload {
+            manager.unload(&mut commands, iid.clone());
+            streamer.loaded.remove(&iid);
+        }
+
+        for iid in to_load {
+            manager.load_in_world(&mut commands, world_iid.clone(), iid.clone(), None);
+            streamer.loaded.insert(iid);
+        }
+    });
+}
+
+fn find_world<'a>(ldtk_data: &'a LdtkJson, world: Option<&str>) -> Option<Option<&'a World>> {
+    match world {
+        Some(world) if world != ldtk_data.dummy_world_iid => ldtk_data
+            .worlds
+            .iter()
+            .find(|w| w.identifier == world || w.iid == world)
+            .map(Some),
+        Some(_) => Some(None),
+        // `worlds` is empty if Multi-worlds is disabled
+        None => Some(ldtk_data.worlds.first()),
+    }
+}
+
+/// The aabb of the level in bevy's coordinate system.
+fn get_level_aabb(level: &Level) -> Aabb2d {
+    Aabb2d::new(
+        level.world_x as f32,
+        -(level.world_y + level.px_hei) as f32,
+        (level.world_x + level.px_wid) as f32,
+        -level.world_y as f32,
+    )
+}
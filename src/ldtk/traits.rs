@@ -2,16 +2,35 @@ use std::marker::PhantomData;
 
 use bevy::{
     asset::AssetServer,
-    ecs::{bundle::Bundle, component::Component, system::EntityCommands},
+    ecs::{
+        bundle::Bundle,
+        component::Component,
+        system::{EntityCommands, Res, Resource, SystemParam},
+    },
+    prelude::{Deref, DerefMut},
     utils::HashMap,
 };
 
 use super::{
-    json::{field::FieldInstance, level::EntityInstance},
+    json::{
+        field::FieldInstance,
+        level::{EntityInstance, LayerInstance, Level},
+    },
     resources::{LdtkAssets, LdtkLevelManager},
 };
 
-pub type LdtkEntityRegistry = HashMap<String, Box<dyn PhantomLdtkEntityTrait>>;
+/// All the registries used when loading levels.
+#[derive(SystemParam)]
+pub struct LdtkRegistries<'w> {
+    pub entities: Option<Res<'w, LdtkEntityRegistry>>,
+    pub entity_tags: Option<Res<'w, LdtkEntityTagRegistry>>,
+    pub levels: Option<Res<'w, LdtkLevelRegistry>>,
+    pub layers: Option<Res<'w, LdtkLayerRegistry>>,
+}
+
+/// Entity identifier to the entity type or the prefab.
+#[derive(Resource, Default, Deref, DerefMut)]
+pub struct LdtkEntityRegistry(pub HashMap<String, Box<dyn PhantomLdtkEntityTrait>>);
 
 pub trait LdtkEntity {
     fn initialize(
@@ -36,7 +55,7 @@ impl<T: LdtkEntity + Bundle> PhantomLdtkEntity<T> {
     }
 }
 
-pub trait PhantomLdtkEntityTrait {
+pub trait PhantomLdtkEntityTrait: Send + Sync {
     fn spawn(
         &self,
         commands: &mut EntityCommands,
@@ -69,11 +88,126 @@ impl<T: LdtkEntity + Bundle> PhantomLdtkEntityTrait for PhantomLdtkEntity<T> {
     }
 }
 
+#[derive(Resource, Default, Deref, DerefMut)]
+pub struct LdtkLevelRegistry(pub Vec<Box<dyn PhantomLdtkLevelTrait>>);
+
+/// Components made from the custom fields of levels.
+/// They are inserted on the level entities, next to the `LdtkLoadedLevel`.
+pub trait LdtkLevel {
+    fn initialize(
+        commands: &mut EntityCommands,
+        level: &Level,
+        fields: &HashMap<String, FieldInstance>,
+        asset_server: &AssetServer,
+        ldtk_manager: &LdtkLevelManager,
+        ldtk_assets: &LdtkAssets,
+    );
+}
+
+pub struct PhantomLdtkLevel<T: LdtkLevel + Bundle> {
+    pub marker: PhantomData<T>,
+}
+
+impl<T: LdtkLevel + Bundle> PhantomLdtkLevel<T> {
+    pub fn new() -> Self {
+        Self {
+            marker: PhantomData::<T>,
+        }
+    }
+}
+
+pub trait PhantomLdtkLevelTrait: Send + Sync {
+    fn spawn(
+        &self,
+        commands: &mut EntityCommands,
+        level: &Level,
+        fields: &HashMap<String, FieldInstance>,
+        asset_server: &AssetServer,
+        ldtk_manager: &LdtkLevelManager,
+        ldtk_assets: &LdtkAssets,
+    );
+}
+
+impl<T: LdtkLevel + Bundle> PhantomLdtkLevelTrait for PhantomLdtkLevel<T> {
+    fn spawn(
+        &self,
+        commands: &mut EntityCommands,
+        level: &Level,
+        fields: &HashMap<String, FieldInstance>,
+        asset_server: &AssetServer,
+        ldtk_manager: &LdtkLevelManager,
+        ldtk_assets: &LdtkAssets,
+    ) {
+        T::initialize(
+            commands,
+            level,
+            fields,
+            asset_server,
+            ldtk_manager,
+            ldtk_assets,
+        );
+    }
+}
+
+/// Layer identifier to the layer components.
+#[derive(Resource, Default, Deref, DerefMut)]
+pub struct LdtkLayerRegistry(pub HashMap<String, Box<dyn PhantomLdtkLayerTrait>>);
+
+/// Components made from the metadata of layers.
+/// They are inserted on the layer entities, which are listed in `LdtkLoadedLevel`.
+pub trait LdtkLayer {
+    fn initialize(
+        commands: &mut EntityCommands,
+        layer: &LayerInstance,
+        asset_server: &AssetServer,
+        ldtk_manager: &LdtkLevelManager,
+        ldtk_assets: &LdtkAssets,
+    );
+}
+
+pub struct PhantomLdtkLayer<T: LdtkLayer + Bundle> {
+    pub marker: PhantomData<T>,
+}
+
+impl<T: LdtkLayer + Bundle> PhantomLdtkLayer<T> {
+    pub fn new() -> Self {
+        Self {
+            marker: PhantomData::<T>,
+        }
+    }
+}
+
+pub trait PhantomLdtkLayerTrait: Send + Sync {
+    fn spawn(
+        &self,
+        commands: &mut EntityCommands,
+        layer: &LayerInstance,
+        asset_server: &AssetServer,
+        ldtk_manager: &LdtkLevelManager,
+        ldtk_assets: &LdtkAssets,
+    );
+}
+
+impl<T: LdtkLayer + Bundle> PhantomLdtkLayerTrait for PhantomLdtkLayer<T> {
+    fn spawn(
+        &self,
+        commands: &mut EntityCommands,
+        layer: &LayerInstance,
+        asset_server: &AssetServer,
+        ldtk_manager: &LdtkLevelManager,
+        ldtk_assets: &LdtkAssets,
+    ) {
+        T::initialize(commands, layer, asset_server, ldtk_manager, ldtk_assets);
+    }
+}
+
 pub trait LdtkEnum {
     fn get_identifier(ident: &str) -> Self;
 }
 
-pub type LdtkEntityTagRegistry = HashMap<String, Box<dyn PhantomLdtkEntityTagTrait>>;
+/// Tag to the tag component.
+#[derive(Resource, Default, Deref, DerefMut)]
+pub struct LdtkEntityTagRegistry(pub HashMap<String, Box<dyn PhantomLdtkEntityTagTrait>>);
 
 pub trait LdtkEntityTag {
     fn add_tag(commands: &mut EntityCommands);
@@ -91,7 +225,7 @@ impl<T: LdtkEntityTag + Component> PhantomLdtkEntityTag<T> {
     }
 }
 
-pub trait PhantomLdtkEntityTagTrait {
+pub trait PhantomLdtkEntityTagTrait: Send + Sync {
     fn add_tag(&self, commands: &mut EntityCommands);
 }
 
@@ -13,9 +13,13 @@ pub mod math;
 pub mod render;
 #[cfg(feature = "serializing")]
 pub mod serializing;
+#[cfg(feature = "tiled")]
+pub mod tiled;
 pub mod tilemap;
 
-pub const MAX_LAYER_COUNT: usize = 4;
+/// Tilemaps with at most this many layers keep the layer data in the vertex attributes.
+/// Tilemaps with more layers read them from a storage buffer.
+pub const DEFAULT_LAYER_COUNT: usize = 4;
 pub const DEFAULT_CHUNK_SIZE: u32 = 16;
 
 pub mod prelude {
@@ -27,6 +31,7 @@ pub mod prelude {
     #[cfg(feature = "ldtk")]
     pub use crate::ldtk::resources::{LdtkAssets, LdtkLevelManager};
     pub use crate::math::{aabb::Aabb2d, TileArea};
+    pub use crate::render::material::{TilemapMaterial, TilemapMaterialPlugin};
     #[cfg(feature = "serializing")]
     pub use crate::serializing::{
         chunk::{
@@ -35,17 +40,26 @@ pub mod prelude {
         },
         map::{load::TilemapLoader, save::TilemapSaver},
     };
+    #[cfg(feature = "tiled")]
+    pub use crate::tiled::{
+        app_ext::TiledAppExt,
+        resources::{TiledLoadConfig, TiledMapManager},
+        traits::TiledObject,
+    };
     #[cfg(feature = "physics")]
     pub use crate::tilemap::physics::TileCollision;
     pub use crate::tilemap::{
         bundles::{PureColorTilemapBundle, TilemapBundle},
         chunking::camera::{CameraChunkUpdater, CameraChunkUpdation},
+        fog::{FogState, FogTilemap},
+        light::{LightTilemap, TileLight},
         map::{
-            TilePivot, TileRenderSize, TilemapAnimations, TilemapLayerOpacities, TilemapName,
-            TilemapSlotSize, TilemapStorage, TilemapTexture, TilemapTextureDescriptor,
+            LayerBlendMode, TilePivot, TileRenderSize, TilemapAnimations, TilemapDepthMode,
+            TilemapLayerOpacities, TilemapLayerStyle, TilemapLayers, TilemapName, TilemapSlotSize,
+            TilemapStorage, TilemapTexture, TilemapTextureDescriptor, TilemapTextureMode,
             TilemapTransform, TilemapType,
         },
-        tile::{TileAnimation, TileBuilder, TileLayer, TileUpdater},
+        tile::{AnimationMode, TileAnimation, TileBuilder, TileLayer, TileUpdater},
     };
 }
 
@@ -67,6 +81,8 @@ impl Plugin for EntiTilesPlugin {
             serializing::EntiTilesSerializingPlugin,
             #[cfg(feature = "ldtk")]
             ldtk::EntiTilesLdtkPlugin,
+            #[cfg(feature = "tiled")]
+            tiled::EntiTilesTiledPlugin,
         ));
     }
 }
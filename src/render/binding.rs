@@ -16,14 +16,16 @@ use bevy::{
 
 use super::{
     buffer::{
-        PerTilemapBuffersStorage, TilemapStorageBuffers, TilemapUniform, TilemapUniformBuffer,
-        UniformBuffer,
+        GpuTileLayer, PerTilemapBuffersStorage, TilemapLayerStyleBuffers, TilemapLayerStyleUniform,
+        TilemapStorageBuffers, TilemapUniform, TilemapUniformBuffer, UniformBuffer,
     },
     extract::ExtractedTilemap,
     pipeline::EntiTilesPipeline,
     texture::TilemapTexturesStorage,
 };
 
+use crate::tilemap::map::TilemapTextureMode;
+
 #[derive(Component)]
 pub struct TilemapViewBindGroup {
     pub value: BindGroup,
@@ -61,27 +63,42 @@ impl TilemapBindGroups {
         &mut self,
         render_device: &RenderDevice,
         storage_buffers: &mut TilemapStorageBuffers,
+        layer_style_buffers: &mut TilemapLayerStyleBuffers,
         entitiles_pipeline: &EntiTilesPipeline,
     ) {
+        let mut style_bindings = layer_style_buffers.bindings();
         storage_buffers
             .bindings()
             .into_iter()
             .for_each(|(tilemap, resource)| {
+                let Some(styles) = style_bindings.remove(&tilemap) else {
+                    return;
+                };
+
                 self.tilemap_storage_buffers.insert(
                     tilemap,
                     render_device.create_bind_group(
                         Some("tilemap_storage_bind_group"),
                         &entitiles_pipeline.storage_buffers_layout,
-                        &[BindGroupEntry {
-                            binding: 0,
-                            resource,
-                        }],
+                        &[
+                            BindGroupEntry {
+                                binding: 0,
+                                resource,
+                            },
+                            BindGroupEntry {
+                                binding: 1,
+                                resource: styles,
+                            },
+                        ],
                     ),
                 );
             });
     }
 
     /// Returns is_pure_color
+    ///
+    /// All the texture slots of the tilemap are combined into one texture,
+    /// so a single bind group is enough no matter how many slots there are.
     pub fn queue_textures(
         &mut self,
         tilemap: &ExtractedTilemap,
@@ -93,16 +110,20 @@ impl TilemapBindGroups {
             return true;
         };
 
-        let Some(texture) = textures_storage.get_texture(tilemap_texture.handle()) else {
-            return !textures_storage.contains(tilemap_texture.handle());
+        let render_handle = tilemap_texture.get_render_handle();
+        let Some(texture) = textures_storage.get_texture(&render_handle) else {
+            return !textures_storage.contains(&render_handle);
         };
 
-        if !self.colored_textures.contains_key(tilemap_texture.handle()) {
+        if !self.colored_textures.contains_key(&render_handle) {
             self.colored_textures.insert(
-                tilemap_texture.clone_weak(),
+                render_handle,
                 render_device.create_bind_group(
                     Some("color_texture_bind_group"),
-                    &entitile_pipeline.color_texture_layout,
+                    match tilemap_texture.mode() {
+                        TilemapTextureMode::Array => &entitile_pipeline.color_texture_array_layout,
+                        TilemapTextureMode::Atlas => &entitile_pipeline.color_texture_atlas_layout,
+                    },
                     &[
                         BindGroupEntry {
                             binding: 0,
@@ -126,7 +147,9 @@ pub struct TilemapBindGroupLayouts {
     pub view_layout: BindGroupLayout,
     pub tilemap_uniforms_layout: BindGroupLayout,
     pub tilemap_storage_layout: BindGroupLayout,
-    pub color_texture_layout: BindGroupLayout,
+    pub tilemap_layered_storage_layout: BindGroupLayout,
+    pub color_texture_array_layout: BindGroupLayout,
+    pub color_texture_atlas_layout: BindGroupLayout,
 }
 
 impl FromWorld for TilemapBindGroupLayouts {
@@ -161,25 +184,57 @@ impl FromWorld for TilemapBindGroupLayouts {
                 }],
             });
 
+        let storage_entries = [
+            // anim_seqs
+            BindGroupLayoutEntry {
+                binding: 0,
+                visibility: ShaderStages::VERTEX_FRAGMENT,
+                ty: BindingType::Buffer {
+                    ty: BufferBindingType::Storage { read_only: true },
+                    has_dynamic_offset: false,
+                    min_binding_size: Some(i32::min_size()),
+                },
+                count: None,
+            },
+            // layer_styles
+            BindGroupLayoutEntry {
+                binding: 1,
+                visibility: ShaderStages::FRAGMENT,
+                ty: BindingType::Buffer {
+                    ty: BufferBindingType::Storage { read_only: true },
+                    has_dynamic_offset: false,
+                    min_binding_size: Some(TilemapLayerStyleUniform::min_size()),
+                },
+                count: None,
+            },
+            // tile_layers
+            BindGroupLayoutEntry {
+                binding: 2,
+                visibility: ShaderStages::FRAGMENT,
+                ty: BindingType::Buffer {
+                    ty: BufferBindingType::Storage { read_only: true },
+                    has_dynamic_offset: false,
+                    min_binding_size: Some(GpuTileLayer::min_size()),
+                },
+                count: None,
+            },
+        ];
+
         let tilemap_storage_layout =
             render_device.create_bind_group_layout(&BindGroupLayoutDescriptor {
                 label: Some("tilemap_storage_layout"),
-                entries: &[BindGroupLayoutEntry {
-                    binding: 0,
-                    visibility: ShaderStages::VERTEX,
-                    ty: BindingType::Buffer {
-                        ty: BufferBindingType::Storage { read_only: true },
-                        has_dynamic_offset: false,
-                        min_binding_size: Some(i32::min_size()),
-                    },
-                    count: None,
-                }],
+                entries: &storage_entries[..2],
+            });
+
+        let tilemap_layered_storage_layout =
+            render_device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+                label: Some("tilemap_layered_storage_layout"),
+                entries: &storage_entries,
             });
 
-        #[cfg(not(feature = "atlas"))]
-        let color_texture_layout =
+        let color_texture_array_layout =
             render_device.create_bind_group_layout(&BindGroupLayoutDescriptor {
-                label: Some("color_texture_layout"),
+                label: Some("color_texture_array_layout"),
                 entries: &[
                     BindGroupLayoutEntry {
                         binding: 0,
@@ -199,11 +254,10 @@ impl FromWorld for TilemapBindGroupLayouts {
                     },
                 ],
             });
-        
-        #[cfg(feature = "atlas")]
-        let color_texture_layout =
+
+        let color_texture_atlas_layout =
             render_device.create_bind_group_layout(&BindGroupLayoutDescriptor {
-                label: Some("color_texture_layout"),
+                label: Some("color_texture_atlas_layout"),
                 entries: &[
                     BindGroupLayoutEntry {
                         binding: 0,
@@ -228,7 +282,9 @@ impl FromWorld for TilemapBindGroupLayouts {
             view_layout,
             tilemap_uniforms_layout,
             tilemap_storage_layout,
-            color_texture_layout,
+            tilemap_layered_storage_layout,
+            color_texture_array_layout,
+            color_texture_atlas_layout,
         }
     }
 }
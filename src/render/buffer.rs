@@ -2,7 +2,7 @@ use std::marker::PhantomData;
 
 use bevy::{
     ecs::entity::Entity,
-    math::{Mat2, Vec4},
+    math::{IVec2, Mat2, Vec4},
     prelude::{Component, Resource, Vec2},
     render::{
         render_resource::{
@@ -14,7 +14,7 @@ use bevy::{
     utils::EntityHashMap,
 };
 
-use crate::tilemap::map::TilemapType;
+use crate::tilemap::map::{TilemapLayerStyle, TilemapType};
 
 use super::extract::ExtractedTilemap;
 
@@ -91,6 +91,35 @@ pub trait PerTilemapBuffersStorage<U: ShaderType + WriteInto + ShaderSize + 'sta
     fn get_mapper(&mut self) -> &mut EntityHashMap<Entity, (StorageBuffer<Vec<U>>, Vec<U>)>;
 }
 
+#[derive(ShaderType, Clone, Copy)]
+pub struct TilemapLayerStyleUniform {
+    pub tint: Vec4,
+    pub opacity: f32,
+    pub blend_mode: u32,
+}
+
+impl From<&TilemapLayerStyle> for TilemapLayerStyleUniform {
+    fn from(style: &TilemapLayerStyle) -> Self {
+        Self {
+            tint: style.tint,
+            opacity: style.opacity,
+            blend_mode: style.blend_mode as u32,
+        }
+    }
+}
+
+/// A tile layer stored in the storage buffer of a chunk,
+/// used by tilemaps with more than `DEFAULT_LAYER_COUNT` layers.
+#[derive(ShaderType, Clone, Copy, Default)]
+pub struct GpuTileLayer {
+    /// Values less than -1 are animated layers, the negated value is the start of the sequence.
+    pub texture_index: i32,
+    /// The third bit means the animation of the layer is paused,
+    /// the fourth bit is the diagonal flip.
+    pub flip: u32,
+    pub anim_phase: f32,
+}
+
 #[derive(ShaderType, Clone, Copy)]
 pub struct TilemapUniform {
     pub translation: Vec2,
@@ -102,9 +131,11 @@ pub struct TilemapUniform {
     pub layer_opacities: Vec4,
     pub hex_legs: f32,
     pub time: f32,
-    #[cfg(feature = "atlas")]
-    pub texture_tiled_size: bevy::math::IVec2,
-    #[cfg(feature = "atlas")]
+    pub unexplored_brightness: f32,
+    pub ambient_light: Vec4,
+    /// Only used by atlas textures.
+    pub texture_tiled_size: IVec2,
+    /// Only used by atlas textures.
     pub tile_uv_size: Vec2,
 }
 
@@ -124,15 +155,12 @@ impl UniformBuffer<ExtractedTilemap, TilemapUniform> for TilemapUniformBuffer {
             }
         };
 
-        #[cfg(feature = "atlas")]
         let (texture_tiled_size, tile_uv_size) = {
             if let Some(tex) = extracted.texture.as_ref() {
-                (
-                    (tex.desc.size / tex.desc.tile_size).as_ivec2(),
-                    tex.desc.tile_size.as_vec2() / tex.desc.size.as_vec2(),
-                )
+                let tiled_size = tex.get_combined_tiled_size();
+                (tiled_size.as_ivec2(), Vec2::ONE / tiled_size.as_vec2())
             } else {
-                (bevy::math::IVec2::ZERO, Vec2::ZERO)
+                (IVec2::ZERO, Vec2::ZERO)
             }
         };
 
@@ -149,9 +177,9 @@ impl UniformBuffer<ExtractedTilemap, TilemapUniform> for TilemapUniformBuffer {
                 _ => 0.,
             },
             time: extracted.time,
-            #[cfg(feature = "atlas")]
+            unexplored_brightness: extracted.unexplored_brightness,
+            ambient_light: extracted.ambient_light.extend(1.),
             texture_tiled_size,
-            #[cfg(feature = "atlas")]
             tile_uv_size,
         }))
     }
@@ -170,3 +198,28 @@ impl PerTilemapBuffersStorage<i32> for TilemapStorageBuffers {
         &mut self.0
     }
 }
+
+#[derive(Resource, Default)]
+pub struct TilemapLayerStyleBuffers(
+    EntityHashMap<
+        Entity,
+        (
+            StorageBuffer<Vec<TilemapLayerStyleUniform>>,
+            Vec<TilemapLayerStyleUniform>,
+        ),
+    >,
+);
+
+impl PerTilemapBuffersStorage<TilemapLayerStyleUniform> for TilemapLayerStyleBuffers {
+    fn get_mapper(
+        &mut self,
+    ) -> &mut EntityHashMap<
+        Entity,
+        (
+            StorageBuffer<Vec<TilemapLayerStyleUniform>>,
+            Vec<TilemapLayerStyleUniform>,
+        ),
+    > {
+        &mut self.0
+    }
+}
@@ -1,31 +1,41 @@
+use std::ops::Range;
+
 use bevy::{
     ecs::{component::Component, event::Event},
     math::{IVec2, IVec4, UVec4},
-    prelude::{Entity, Mesh, Resource, Vec3, Vec4},
+    prelude::{Entity, Mesh, Resource, Vec2, Vec3, Vec4},
     reflect::Reflect,
     render::{
         mesh::{GpuBufferInfo, GpuMesh, Indices},
-        render_resource::{BufferInitDescriptor, BufferUsages, IndexFormat, PrimitiveTopology},
+        render_resource::{
+            encase, BindGroup, Buffer, BufferInitDescriptor, BufferUsages, IndexFormat,
+            PrimitiveTopology,
+        },
         renderer::RenderDevice,
     },
-    utils::{EntityHashMap, HashMap},
+    utils::{EntityHashMap, FloatOrd, HashMap},
 };
 
 use crate::{
     math::{aabb::Aabb2d, extension::DivToFloor},
     tilemap::{
-        map::{TilemapTexture, TilemapType},
-        tile::TileTexture,
+        coordinates,
+        map::{TilemapDepthMode, TilemapTexture, TilemapType},
+        tile::{TileAnimation, TileTexture},
     },
-    MAX_LAYER_COUNT,
+    DEFAULT_LAYER_COUNT,
 };
 
 use super::{
+    buffer::GpuTileLayer,
     extract::{ExtractedTile, ExtractedTilemap},
-    TILEMAP_MESH_ATTR_COLOR, TILEMAP_MESH_ATTR_FLIP, TILEMAP_MESH_ATTR_INDEX,
+    TILEMAP_MESH_ATTR_ANIM, TILEMAP_MESH_ATTR_COLOR, TILEMAP_MESH_ATTR_FLIP, TILEMAP_MESH_ATTR_FOG,
+    TILEMAP_MESH_ATTR_INDEX, TILEMAP_MESH_ATTR_LIGHT, TILEMAP_MESH_ATTR_RECT,
     TILEMAP_MESH_ATTR_TEX_INDICES,
 };
 
+const ANIM_PAUSED_FLAG: u32 = 0b100;
+
 #[derive(Component, Default, Debug, Clone, Reflect)]
 pub struct UnloadRenderChunk(pub Vec<IVec2>);
 
@@ -35,16 +45,40 @@ pub struct ChunkUnload {
     pub index: IVec2,
 }
 
+/// The corners of chunks sent from `ChunkCornersUpdate`.
+#[derive(Component, Default, Debug, Clone)]
+pub struct CornerRenderChunks<T: RenderChunkCorners>(pub Vec<(IVec2, Vec<[T; 4]>)>);
+
+pub type FogRenderChunks = CornerRenderChunks<f32>;
+pub type LightRenderChunks = CornerRenderChunks<Vec3>;
+
+/// The values at the corners of tiles that can be applied to render chunks.
+pub trait RenderChunkCorners: Default + Clone + Send + Sync + 'static {
+    fn apply(chunk: &mut TilemapRenderChunk, corners: &[[Self; 4]]);
+}
+
+impl RenderChunkCorners for f32 {
+    #[inline]
+    fn apply(chunk: &mut TilemapRenderChunk, corners: &[[Self; 4]]) {
+        chunk.set_fog(corners);
+    }
+}
+
+impl RenderChunkCorners for Vec3 {
+    #[inline]
+    fn apply(chunk: &mut TilemapRenderChunk, corners: &[[Self; 4]]) {
+        chunk.set_light(corners);
+    }
+}
+
 #[derive(Clone)]
 pub struct MeshTileData {
-    // When the third and forth component of index are not -1,
-    // it means this tile is a animated tile
-    // So the zw components are the start index and the length of the animation sequence
     pub index: IVec4,
-    // 4 layers
-    pub texture_indices: IVec4,
     pub color: Vec4,
-    pub flip: UVec4,
+    pub depth: f32,
+    pub render_size: Option<Vec2>,
+    pub anchor: Option<Vec2>,
+    pub layers: Vec<GpuTileLayer>,
 }
 
 #[derive(Clone)]
@@ -54,42 +88,71 @@ pub struct TilemapRenderChunk {
     pub dirty_mesh: bool,
     pub ty: TilemapType,
     pub size: u32,
+    pub layer_count: usize,
     pub texture: Option<TilemapTexture>,
     pub tiles: Vec<Option<MeshTileData>>,
+    /// The brightness of the corners of each tile. Empty if the chunk has no fog.
+    pub fog: Vec<[f32; 4]>,
+    /// The light of the corners of each tile. Empty if the chunk has no light.
+    pub light: Vec<[Vec3; 4]>,
     pub mesh: Mesh,
     pub gpu_mesh: Option<GpuMesh>,
+    /// The layers of the tiles. Only used when the layer count is
+    /// more than `DEFAULT_LAYER_COUNT`.
+    pub layers_buffer: Option<Buffer>,
+    pub layers_bind_group: Option<BindGroup>,
+    pub depth_mode: Option<TilemapDepthMode>,
+    /// The tiles with the same depth and the range of their indices in the mesh.
+    /// Empty if the tilemap has no `TilemapDepthMode`.
+    pub depth_slices: Vec<(f32, Range<u32>)>,
+    /// Expanded to contain the tiles that are larger than the slots.
     pub aabb: Aabb2d,
+    /// The aabb of the slots in the chunk.
+    pub slots_aabb: Aabb2d,
 }
 
 impl TilemapRenderChunk {
     pub fn from_index(index: IVec2, tilemap: &ExtractedTilemap) -> Self {
+        let aabb = Aabb2d::from_tilemap(
+            index,
+            tilemap.chunk_size,
+            tilemap.ty,
+            tilemap.tile_pivot,
+            tilemap.slot_size,
+            tilemap.transform,
+        );
+
         TilemapRenderChunk {
             visible: true,
             index: index.div_to_floor(IVec2::splat(tilemap.chunk_size as i32)),
             size: tilemap.chunk_size,
+            layer_count: tilemap.layer_styles.len(),
             ty: tilemap.ty,
             texture: tilemap.texture.clone(),
             tiles: vec![None; (tilemap.chunk_size * tilemap.chunk_size) as usize],
+            fog: Vec::new(),
+            light: Vec::new(),
             mesh: Mesh::new(PrimitiveTopology::TriangleList),
             gpu_mesh: None,
+            layers_buffer: None,
+            layers_bind_group: None,
+            depth_mode: tilemap.depth_mode,
+            depth_slices: Vec::new(),
             dirty_mesh: true,
-            aabb: Aabb2d::from_tilemap(
-                index,
-                tilemap.chunk_size,
-                tilemap.ty,
-                tilemap.tile_pivot,
-                tilemap.slot_size,
-                tilemap.transform,
-            ),
+            aabb,
+            slots_aabb: aabb,
         }
     }
 
     /// Update the raw mesh for GPU processing.
-    pub fn update_mesh(&mut self, render_device: &RenderDevice) {
+    pub fn update_mesh(&mut self, tilemap: &ExtractedTilemap, render_device: &RenderDevice) {
         if !self.dirty_mesh {
             return;
         }
         let is_pure_color = self.texture.is_none();
+        let is_layered = self.layer_count > DEFAULT_LAYER_COUNT;
+        // Expanded again by the tiles that are still in the chunk.
+        self.aabb = self.slots_aabb;
 
         let mut v_index = 0;
         let len = self.tiles.len();
@@ -100,36 +163,110 @@ impl TilemapRenderChunk {
         let mut vertex_indices = Vec::with_capacity(len * 6);
         let mut color = Vec::with_capacity(len * 4);
         let mut flip = Vec::with_capacity(len * 4);
+        let mut anim = Vec::with_capacity(len * 4);
+        let mut fog = Vec::with_capacity(len * 4);
+        let mut light = Vec::with_capacity(len * 4);
+        let mut rects = Vec::with_capacity(len * 4);
+        let mut layers = Vec::new();
+
+        let mut tiles = self
+            .tiles
+            .iter()
+            .enumerate()
+            .filter_map(|(index, tile)| tile.as_ref().map(|t| (index, t, 0.)))
+            .collect::<Vec<_>>();
+
+        self.depth_slices.clear();
+        if let Some(mode) = self.depth_mode {
+            tiles.iter_mut().for_each(|(_, tile, depth)| {
+                let rel_y = coordinates::index_to_rel(
+                    IVec2::new(tile.index.x, tile.index.y),
+                    &tilemap.ty,
+                    &tilemap.transform,
+                    tilemap.tile_pivot,
+                    tilemap.slot_size,
+                )
+                .y;
+                *depth = mode.get_tile_depth(tile.depth, rel_y);
+            });
+            // Stable sort, so tiles with the same depth keep their order.
+            tiles.sort_by(|a, b| a.2.total_cmp(&b.2));
+        }
 
-        for tile_data in self.tiles.iter() {
-            if let Some(tile) = tile_data {
-                if !is_pure_color {
-                    texture_indices.extend_from_slice(&[
-                        tile.texture_indices,
-                        tile.texture_indices,
-                        tile.texture_indices,
-                        tile.texture_indices,
-                    ]);
+        for (index, tile, depth) in tiles {
+            if self.depth_mode.is_some() {
+                let start = vertex_indices.len() as u32;
+                match self.depth_slices.last_mut() {
+                    Some((d, range)) if *d == depth => range.end = start + 6,
+                    _ => self.depth_slices.push((depth, start..start + 6)),
                 }
+            }
 
-                let pos = Vec3::ZERO;
-                positions.extend_from_slice(&[pos, pos, pos, pos]);
+            if !is_pure_color {
+                let mut tile_texture_indices = IVec4::NEG_ONE;
+                let mut tile_flip = UVec4::ZERO;
+                let mut tile_anim = Vec4::ZERO;
+                let count = tile.layers.len().min(self.layer_count);
 
-                vertex_indices.extend_from_slice(&[
-                    v_index,
-                    v_index + 1,
-                    v_index + 3,
-                    v_index + 1,
-                    v_index + 2,
-                    v_index + 3,
-                ]);
+                if is_layered {
+                    // Store the offset and the count of the layers instead.
+                    tile_texture_indices.x = layers.len() as i32;
+                    tile_texture_indices.y = count as i32;
+                    layers.extend_from_slice(&tile.layers[..count]);
+                } else {
+                    tile.layers[..count].iter().enumerate().for_each(|(i, l)| {
+                        tile_texture_indices[i] = l.texture_index;
+                        tile_flip[i] = l.flip;
+                        tile_anim[i] = l.anim_phase;
+                    });
+                }
 
-                v_index += 4;
+                texture_indices.extend_from_slice(&[tile_texture_indices; 4]);
+                flip.extend_from_slice(&[tile_flip; 4]);
+                anim.extend_from_slice(&[tile_anim; 4]);
+            }
 
-                grid_indices.extend_from_slice(&[tile.index, tile.index, tile.index, tile.index]);
-                color.extend_from_slice(&[tile.color, tile.color, tile.color, tile.color]);
-                flip.extend_from_slice(&[tile.flip, tile.flip, tile.flip, tile.flip]);
+            let pos = Vec3::ZERO;
+            positions.extend_from_slice(&[pos, pos, pos, pos]);
+
+            vertex_indices.extend_from_slice(&[
+                v_index,
+                v_index + 1,
+                v_index + 3,
+                v_index + 1,
+                v_index + 2,
+                v_index + 3,
+            ]);
+
+            v_index += 4;
+
+            grid_indices.extend_from_slice(&[tile.index, tile.index, tile.index, tile.index]);
+            color.extend_from_slice(&[tile.color, tile.color, tile.color, tile.color]);
+            // Negative values mean using the default brightness in the uniform.
+            fog.extend_from_slice(self.fog.get(index).unwrap_or(&[-1.; 4]));
+            // Same as fog, negative alpha means using the ambient light in the uniform.
+            if let Some(corners) = self.light.get(index) {
+                light.extend(corners.iter().map(|c| c.extend(1.)));
+            } else {
+                light.extend_from_slice(&[Vec4::NEG_ONE; 4]);
             }
+            // Negative values mean using the render size and the pivot in the uniform.
+            let rect = if tile.render_size.is_some() || tile.anchor.is_some() {
+                let render_size = tile.render_size.unwrap_or(tilemap.tile_render_size);
+                let anchor = tile.anchor.unwrap_or(tilemap.tile_pivot);
+                self.aabb.expand(coordinates::get_tile_render_aabb(
+                    IVec2::new(tile.index.x, tile.index.y),
+                    &tilemap.ty,
+                    &tilemap.transform,
+                    render_size,
+                    anchor,
+                    tilemap.slot_size,
+                ));
+                Vec4::new(render_size.x, render_size.y, anchor.x, anchor.y)
+            } else {
+                Vec4::NEG_ONE
+            };
+            rects.extend_from_slice(&[rect; 4]);
         }
 
         self.mesh
@@ -137,10 +274,14 @@ impl TilemapRenderChunk {
         self.mesh
             .insert_attribute(TILEMAP_MESH_ATTR_INDEX, grid_indices);
         self.mesh.insert_attribute(TILEMAP_MESH_ATTR_COLOR, color);
+        self.mesh.insert_attribute(TILEMAP_MESH_ATTR_FOG, fog);
+        self.mesh.insert_attribute(TILEMAP_MESH_ATTR_LIGHT, light);
+        self.mesh.insert_attribute(TILEMAP_MESH_ATTR_RECT, rects);
         if !is_pure_color {
             self.mesh
                 .insert_attribute(TILEMAP_MESH_ATTR_TEX_INDICES, texture_indices);
-            self.mesh.insert_attribute(TILEMAP_MESH_ATTR_FLIP, flip)
+            self.mesh.insert_attribute(TILEMAP_MESH_ATTR_FLIP, flip);
+            self.mesh.insert_attribute(TILEMAP_MESH_ATTR_ANIM, anim);
         }
         self.mesh.set_indices(Some(Indices::U32(vertex_indices)));
 
@@ -175,6 +316,21 @@ impl TilemapRenderChunk {
             layout: self.mesh.get_mesh_vertex_buffer_layout(),
         });
 
+        self.layers_buffer = is_layered.then(|| {
+            if layers.is_empty() {
+                layers.push(GpuTileLayer::default());
+            }
+            let mut buffer = encase::StorageBuffer::new(Vec::new());
+            buffer.write(&layers).unwrap();
+            render_device.create_buffer_with_data(&BufferInitDescriptor {
+                label: Some("tilemap_layers_buffer"),
+                contents: buffer.as_ref(),
+                usage: BufferUsages::STORAGE,
+            })
+        });
+        // Rebound to the new buffer in `prepare_chunk_layers`.
+        self.layers_bind_group = None;
+
         self.dirty_mesh = false;
     }
 
@@ -189,53 +345,129 @@ impl TilemapRenderChunk {
             return;
         };
 
-        let mut texture_indices = IVec4::NEG_ONE;
-        let mut flip = UVec4::ZERO;
-        let tile_index = {
-            match &tile.texture {
-                TileTexture::Static(tex) => {
-                    tex.iter()
-                        .enumerate()
-                        .rev()
-                        .take(MAX_LAYER_COUNT)
-                        .for_each(|(i, t)| {
-                            texture_indices[i] = t.texture_index;
-                            flip[i] = t.flip;
-                        });
-                    IVec4::new(tile.index.x, tile.index.y, -1, -1)
-                }
-                TileTexture::Animated(anim) => IVec4::new(
-                    tile.index.x,
-                    tile.index.y,
-                    anim.start as i32,
-                    anim.length as i32,
-                ),
-            }
+        let layers = match &tile.texture {
+            TileTexture::Static(tex) => tex
+                .iter()
+                .map(|l| match &l.animation {
+                    Some(anim) => Self::get_animated_layer(anim, l.flip),
+                    None => GpuTileLayer {
+                        texture_index: match &self.texture {
+                            Some(texture) if l.texture_index >= 0 && l.texture_slot > 0 => texture
+                                .get_global_index(l.texture_slot as usize, l.texture_index as u32)
+                                as i32,
+                            _ => l.texture_index,
+                        },
+                        flip: l.flip,
+                        anim_phase: 0.,
+                    },
+                })
+                .collect(),
+            TileTexture::Animated(anim) => vec![Self::get_animated_layer(anim, 0)],
         };
 
         self.tiles[index] = Some(MeshTileData {
-            index: tile_index,
-            texture_indices,
+            index: IVec4::new(tile.index.x, tile.index.y, -1, -1),
             color: tile.color,
-            flip,
+            depth: tile.depth,
+            render_size: tile.render_size,
+            anchor: tile.anchor,
+            layers,
         });
         self.dirty_mesh = true;
     }
+
+    fn get_animated_layer(anim: &TileAnimation, flip: u32) -> GpuTileLayer {
+        if anim.paused {
+            GpuTileLayer {
+                texture_index: -(anim.start as i32),
+                flip: flip | ANIM_PAUSED_FLAG,
+                anim_phase: anim.offset,
+            }
+        } else {
+            GpuTileLayer {
+                texture_index: -(anim.start as i32),
+                flip,
+                anim_phase: anim.offset - anim.start_time.unwrap_or_default(),
+            }
+        }
+    }
+
+    /// Set the fog corners of the chunk. The corners are in the order of `FogTilemap` storage.
+    pub fn set_fog(&mut self, corners: &[[f32; 4]]) {
+        self.fog = corners.iter().rev().cloned().collect();
+        self.dirty_mesh = true;
+    }
+
+    /// Set the light corners of the chunk. The corners are in the order of `LightTilemap` storage.
+    pub fn set_light(&mut self, corners: &[[Vec3; 4]]) {
+        self.light = corners.iter().rev().cloned().collect();
+        self.dirty_mesh = true;
+    }
 }
 
+/// The chunks and the ranges of indices to draw for each sort key of a tilemap.
+pub type DepthSlices = HashMap<FloatOrd, Vec<(IVec2, Range<u32>)>>;
+
 #[derive(Resource, Default)]
 pub struct RenderChunkStorage {
     pub(crate) value: EntityHashMap<Entity, HashMap<IVec2, TilemapRenderChunk>>,
+    /// The depth slices of the tilemaps with a `TilemapDepthMode`.
+    pub(crate) depth_slices: EntityHashMap<Entity, DepthSlices>,
 }
 
 impl RenderChunkStorage {
     /// Update the mesh for all chunks of a tilemap.
     pub fn prepare_chunks(&mut self, tilemap: &ExtractedTilemap, render_device: &RenderDevice) {
         if let Some(chunks) = self.value.get_mut(&tilemap.id) {
+            chunks.values_mut().for_each(|c| {
+                if c.layer_count != tilemap.layer_styles.len() {
+                    c.layer_count = tilemap.layer_styles.len();
+                    c.dirty_mesh = true;
+                }
+                if c.depth_mode != tilemap.depth_mode {
+                    c.depth_mode = tilemap.depth_mode;
+                    c.dirty_mesh = true;
+                }
+                c.update_mesh(tilemap, render_device)
+            });
+        }
+    }
+
+    /// Group the depth slices of the visible chunks of a tilemap by their sort keys.
+    pub fn prepare_depth_slices(&mut self, tilemap: &ExtractedTilemap) {
+        if tilemap.depth_mode.is_none() {
+            self.depth_slices.remove(&tilemap.id);
+            return;
+        }
+
+        let base_depth = tilemap.get_base_depth();
+        let mut slices = DepthSlices::default();
+        if let Some(chunks) = self.value.get(&tilemap.id) {
             chunks
-                .values_mut()
-                .for_each(|c| c.update_mesh(render_device));
+                .iter()
+                .filter(|(_, c)| c.visible)
+                .for_each(|(index, c)| {
+                    c.depth_slices.iter().for_each(|(depth, range)| {
+                        slices
+                            .entry(FloatOrd(base_depth + depth))
+                            .or_default()
+                            .push((*index, range.clone()));
+                    });
+                });
         }
+        self.depth_slices.insert(tilemap.id, slices);
+    }
+
+    /// Get the chunks and the ranges of indices to draw for the sort key of a tilemap.
+    #[inline]
+    pub fn get_depth_slices(
+        &self,
+        tilemap: Entity,
+        sort_key: FloatOrd,
+    ) -> Option<&Vec<(IVec2, Range<u32>)>> {
+        self.depth_slices
+            .get(&tilemap)
+            .and_then(|slices| slices.get(&sort_key))
     }
 
     #[inline]
@@ -256,6 +488,7 @@ impl RenderChunkStorage {
         &mut self,
         tilemap: Entity,
     ) -> Option<HashMap<IVec2, TilemapRenderChunk>> {
+        self.depth_slices.remove(&tilemap);
         self.value.remove(&tilemap)
     }
 
@@ -1,3 +1,5 @@
+use std::ops::Range;
+
 use bevy::{
     core_pipeline::core_2d::Transparent2d,
     ecs::{
@@ -9,7 +11,7 @@ use bevy::{
     },
     log::error,
     render::{
-        mesh::GpuBufferInfo,
+        mesh::{GpuBufferInfo, GpuMesh},
         render_phase::{RenderCommand, RenderCommandResult, TrackedRenderPass},
         render_resource::PipelineCache,
         view::ViewUniformOffset,
@@ -19,7 +21,7 @@ use bevy::{
 use super::{
     binding::{TilemapBindGroups, TilemapViewBindGroup},
     buffer::{DynamicOffsetComponent, TilemapUniform},
-    chunk::RenderChunkStorage,
+    chunk::{RenderChunkStorage, TilemapRenderChunk},
     extract::ExtractedTilemap,
 };
 
@@ -32,6 +34,14 @@ pub type DrawTilemap = (
     DrawTileMesh,
 );
 
+pub type DrawTilemapLayered = (
+    SetPipeline,
+    SetTilemapViewBindGroup<0>,
+    SetTilemapUniformBufferBindGroup<1>,
+    SetTilemapColorTextureBindGroup<2>,
+    DrawLayeredTileMesh<3>,
+);
+
 pub type DrawTilemapPureColor = (
     SetPipeline,
     SetTilemapViewBindGroup<0>,
@@ -167,7 +177,7 @@ impl<const I: usize> RenderCommand<Transparent2d> for SetTilemapColorTextureBind
         if let Some(bind_group) = &bind_groups
             .into_inner()
             .colored_textures
-            .get(tilemap.texture.as_ref().unwrap().handle())
+            .get(&tilemap.texture.as_ref().unwrap().get_render_handle())
         {
             pass.set_bind_group(I, bind_group, &[]);
             RenderCommandResult::Success
@@ -188,37 +198,99 @@ impl RenderCommand<Transparent2d> for DrawTileMesh {
 
     #[inline]
     fn render<'w>(
-        _item: &Transparent2d,
+        item: &Transparent2d,
         _view: ROQueryItem<'w, Self::ViewWorldQuery>,
         tilemap: ROQueryItem<'w, Self::ItemWorldQuery>,
         render_chunks: SystemParamItem<'w, '_, Self::Param>,
         pass: &mut TrackedRenderPass<'w>,
     ) -> RenderCommandResult {
-        if let Some(chunks) = render_chunks.into_inner().get_chunks(tilemap.id) {
-            for chunk in chunks.values() {
-                if !chunk.visible {
-                    continue;
-                }
-
-                if let Some(gpu_mesh) = &chunk.gpu_mesh {
-                    pass.set_vertex_buffer(0, gpu_mesh.vertex_buffer.slice(..));
-                    match &gpu_mesh.buffer_info {
-                        GpuBufferInfo::Indexed {
-                            buffer,
-                            count,
-                            index_format,
-                        } => {
-                            pass.set_index_buffer(buffer.slice(..), 0, *index_format);
-                            pass.draw_indexed(0..*count, 0, 0..1);
-                        }
-                        GpuBufferInfo::NonIndexed => {
-                            pass.draw(0..gpu_mesh.vertex_count, 0..1);
-                        }
-                    }
-                }
+        let render_chunks = render_chunks.into_inner();
+        for_each_chunk(item, tilemap, render_chunks, |chunk, indices| {
+            if let Some(gpu_mesh) = &chunk.gpu_mesh {
+                draw_gpu_mesh(gpu_mesh, indices, pass);
             }
-        }
+        });
 
         RenderCommandResult::Success
     }
 }
+
+/// Draws the chunks of tilemaps with more than `DEFAULT_LAYER_COUNT` layers,
+/// binding the layers of each chunk to the group `I`.
+pub struct DrawLayeredTileMesh<const I: usize>;
+impl<const I: usize> RenderCommand<Transparent2d> for DrawLayeredTileMesh<I> {
+    type Param = SRes<RenderChunkStorage>;
+
+    type ViewWorldQuery = ();
+
+    type ItemWorldQuery = Read<ExtractedTilemap>;
+
+    #[inline]
+    fn render<'w>(
+        item: &Transparent2d,
+        _view: ROQueryItem<'w, Self::ViewWorldQuery>,
+        tilemap: ROQueryItem<'w, Self::ItemWorldQuery>,
+        render_chunks: SystemParamItem<'w, '_, Self::Param>,
+        pass: &mut TrackedRenderPass<'w>,
+    ) -> RenderCommandResult {
+        let render_chunks = render_chunks.into_inner();
+        for_each_chunk(item, tilemap, render_chunks, |chunk, indices| {
+            if let (Some(gpu_mesh), Some(bind_group)) = (&chunk.gpu_mesh, &chunk.layers_bind_group)
+            {
+                pass.set_bind_group(I, bind_group, &[]);
+                draw_gpu_mesh(gpu_mesh, indices, pass);
+            }
+        });
+
+        RenderCommandResult::Success
+    }
+}
+
+/// Calls `draw` for each visible chunk of the tilemap with the whole mesh,
+/// or if the tilemap has a `TilemapDepthMode`, for the chunks with tiles
+/// at the depth of the item with the range of indices of these tiles.
+#[inline]
+fn for_each_chunk<'w>(
+    item: &Transparent2d,
+    tilemap: &ExtractedTilemap,
+    render_chunks: &'w RenderChunkStorage,
+    mut draw: impl FnMut(&'w TilemapRenderChunk, Option<Range<u32>>),
+) {
+    let Some(chunks) = render_chunks.get_chunks(tilemap.id) else {
+        return;
+    };
+
+    if tilemap.depth_mode.is_none() {
+        chunks
+            .values()
+            .filter(|c| c.visible)
+            .for_each(|c| draw(c, None));
+    } else if let Some(slices) = render_chunks.get_depth_slices(tilemap.id, item.sort_key) {
+        slices
+            .iter()
+            .filter_map(|(index, range)| chunks.get(index).map(|c| (c, range.clone())))
+            .for_each(|(c, range)| draw(c, Some(range)));
+    }
+}
+
+#[inline]
+fn draw_gpu_mesh<'w>(
+    gpu_mesh: &'w GpuMesh,
+    indices: Option<Range<u32>>,
+    pass: &mut TrackedRenderPass<'w>,
+) {
+    pass.set_vertex_buffer(0, gpu_mesh.vertex_buffer.slice(..));
+    match &gpu_mesh.buffer_info {
+        GpuBufferInfo::Indexed {
+            buffer,
+            count,
+            index_format,
+        } => {
+            pass.set_index_buffer(buffer.slice(..), 0, *index_format);
+            pass.draw_indexed(indices.unwrap_or(0..*count), 0, 0..1);
+        }
+        GpuBufferInfo::NonIndexed => {
+            pass.draw(0..gpu_mesh.vertex_count, 0..1);
+        }
+    }
+}
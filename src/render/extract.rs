@@ -1,6 +1,6 @@
 use bevy::{
     ecs::{event::EventReader, query::Without, system::Res},
-    prelude::{Changed, Commands, Component, Entity, Query, Vec2, Vec4},
+    prelude::{Changed, Commands, Component, Entity, Query, Vec2, Vec3, Vec4},
     render::Extract,
     time::Time,
     utils::EntityHashMap,
@@ -9,17 +9,21 @@ use bevy::{
 use crate::{
     math::CameraAabb2d,
     tilemap::{
+        chunking::corners::ChunkCornersUpdate,
         despawn::{DespawnedTile, DespawnedTilemap},
+        fog::FogTilemap,
+        light::LightTilemap,
         map::{
-            TilePivot, TileRenderSize, TilemapAnimations, TilemapLayerOpacities, TilemapName,
-            TilemapSlotSize, TilemapStorage, TilemapTexture, TilemapTransform, TilemapType,
+            TilePivot, TileRenderSize, TilemapAnimations, TilemapDepthMode, TilemapLayerOpacities,
+            TilemapLayerStyle, TilemapLayers, TilemapName, TilemapSlotSize, TilemapStorage,
+            TilemapTexture, TilemapTextureMode, TilemapTransform, TilemapType,
         },
         tile::Tile,
     },
 };
 
 use super::{
-    chunk::{ChunkUnload, UnloadRenderChunk},
+    chunk::{ChunkUnload, CornerRenderChunks, RenderChunkCorners, UnloadRenderChunk},
     culling::{FrustumCulling, InvisibleTilemap},
 };
 
@@ -32,11 +36,37 @@ pub struct ExtractedTilemap {
     pub ty: TilemapType,
     pub tile_pivot: Vec2,
     pub layer_opacities: Vec4,
+    /// The styles of the layers. The length is the layer count of the tilemap.
+    pub layer_styles: Vec<TilemapLayerStyle>,
     pub transform: TilemapTransform,
     pub texture: Option<TilemapTexture>,
     pub animations: Option<TilemapAnimations>,
     pub chunk_size: u32,
     pub time: f32,
+    /// The brightness of tiles without fog data. `1` if the tilemap has no `FogTilemap`.
+    pub unexplored_brightness: f32,
+    /// The light of tiles without light data. `1` if the tilemap has no `LightTilemap`.
+    pub ambient_light: Vec3,
+    /// `None` if the tilemap is drawn as a whole at the z index.
+    pub depth_mode: Option<TilemapDepthMode>,
+}
+
+impl ExtractedTilemap {
+    #[inline]
+    pub fn is_atlas(&self) -> bool {
+        self.texture
+            .as_ref()
+            .is_some_and(|t| t.mode() == TilemapTextureMode::Atlas)
+    }
+
+    /// The depth shared by all tiles. Add the depth of a tile slice to get its sort key.
+    #[inline]
+    pub fn get_base_depth(&self) -> f32 {
+        self.depth_mode
+            .map_or(self.transform.z_index as f32, |mode| {
+                mode.get_base_depth(&self.transform)
+            })
+    }
 }
 
 pub type ExtractedTile = Tile;
@@ -59,6 +89,10 @@ pub fn extract_tilemaps(
                 &TilemapStorage,
                 Option<&TilemapTexture>,
                 Option<&TilemapAnimations>,
+                Option<&FogTilemap>,
+                Option<&LightTilemap>,
+                Option<&TilemapLayers>,
+                Option<&TilemapDepthMode>,
             ),
             Without<InvisibleTilemap>,
         >,
@@ -81,6 +115,10 @@ pub fn extract_tilemaps(
                     storage,
                     texture,
                     animations,
+                    fog,
+                    light,
+                    layers,
+                    depth_mode,
                 )| {
                     (
                         entity,
@@ -92,11 +130,17 @@ pub fn extract_tilemaps(
                             ty: *ty,
                             tile_pivot: tile_pivot.0,
                             layer_opacities: layer_opacities.0,
+                            layer_styles: layers.cloned().unwrap_or_default().styles,
                             transform: *transform,
                             texture: texture.cloned(),
                             animations: animations.cloned(),
                             chunk_size: storage.storage.chunk_size,
-                            time: time.elapsed_seconds(),
+                            time: animations.map(|a| a.time).unwrap_or(time.elapsed_seconds()),
+                            unexplored_brightness: fog
+                                .map(|f| f.unexplored_brightness)
+                                .unwrap_or(1.),
+                            ambient_light: light.map(|l| l.ambient).unwrap_or(Vec3::ONE),
+                            depth_mode: depth_mode.cloned(),
                         },
                     )
                 },
@@ -122,6 +166,9 @@ pub fn extract_tiles(
                         index: tile.index,
                         texture: tile.texture.clone(),
                         color: tile.color,
+                        depth: tile.depth,
+                        render_size: tile.render_size,
+                        anchor: tile.anchor,
                     },
                 )
             })
@@ -154,6 +201,23 @@ pub fn extract_unloaded_chunks(
     ));
 }
 
+/// Extract the corners of the chunks sent from `FogChunkUpdate` or `LightChunkUpdate`.
+pub fn extract_chunk_corners<T: RenderChunkCorners>(
+    mut commands: Commands,
+    mut corners_update: Extract<EventReader<ChunkCornersUpdate<T>>>,
+) {
+    commands.insert_or_spawn_batch(corners_update.read().fold(
+        EntityHashMap::<Entity, CornerRenderChunks<T>>::default(),
+        |mut acc, elem| {
+            acc.entry(elem.tilemap)
+                .or_default()
+                .0
+                .push((elem.index, elem.corners.clone()));
+            acc
+        },
+    ));
+}
+
 pub fn extract_resources(mut commands: Commands, frustum_culling: Extract<Res<FrustumCulling>>) {
     commands.insert_resource(FrustumCulling(frustum_culling.0));
 }
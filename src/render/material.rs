@@ -0,0 +1,492 @@
+use std::{hash::Hash, marker::PhantomData};
+
+use bevy::{
+    app::{App, Plugin},
+    asset::{Asset, AssetApp, AssetEvent, AssetId, AssetServer, Assets, Handle},
+    core_pipeline::core_2d::Transparent2d,
+    ecs::{
+        component::Component,
+        entity::Entity,
+        event::EventReader,
+        query::{ROQueryItem, With},
+        system::{
+            lifetimeless::{Read, SRes},
+            Commands, Local, Query, Res, ResMut, Resource, SystemParamItem,
+        },
+        world::FromWorld,
+    },
+    log::error,
+    prelude::{IntoSystemConfigs, Shader},
+    render::{
+        render_asset::RenderAssets,
+        render_phase::{
+            AddRenderCommand, RenderCommand, RenderCommandResult, RenderPhase, TrackedRenderPass,
+        },
+        render_resource::{
+            AsBindGroup, AsBindGroupError, BindGroup, BindGroupLayout, OwnedBindingResource,
+            RenderPipelineDescriptor, ShaderDefVal, ShaderRef, SpecializedRenderPipeline,
+            SpecializedRenderPipelines,
+        },
+        renderer::RenderDevice,
+        texture::{FallbackImage, Image},
+        Extract, ExtractSchedule, Render, RenderApp, RenderSet,
+    },
+    utils::{HashMap, HashSet},
+};
+
+use crate::{tilemap::map::TilemapStorage, DEFAULT_LAYER_COUNT};
+
+use super::{
+    draw::{
+        DrawLayeredTileMesh, DrawTileMesh, SetPipeline, SetTilemapColorTextureBindGroup,
+        SetTilemapStorageBufferBindGroup, SetTilemapUniformBufferBindGroup,
+        SetTilemapViewBindGroup,
+    },
+    extract::ExtractedTilemap,
+    pipeline::{EntiTilesPipeline, EntiTilesPipelineKey},
+    queue::{self, TilemapQueueParams},
+};
+
+/// Custom shaders and bindings for tilemaps, similar to bevy's `Material2d`.
+///
+/// Add `TilemapMaterialPlugin<M>` to your app and insert a `Handle<M>` to the tilemap entity.
+///
+/// The entry points of the shaders must be `tilemap_vertex` and `tilemap_fragment`.
+/// Use the functions in `bevy_entitiles::functions` to get the default behaviours.
+/// The material is bound to `@group(#{MATERIAL_BIND_GROUP})`.
+pub trait TilemapMaterial: Asset + AsBindGroup + Clone + Sized {
+    /// Returns this material's vertex shader. If `ShaderRef::Default` is returned,
+    /// the default tilemap vertex shader will be used.
+    fn vertex_shader() -> ShaderRef {
+        ShaderRef::Default
+    }
+
+    /// Returns this material's fragment shader. If `ShaderRef::Default` is returned,
+    /// the default tilemap fragment shader will be used.
+    fn fragment_shader() -> ShaderRef {
+        ShaderRef::Default
+    }
+
+    /// Customizes the default `RenderPipelineDescriptor`.
+    #[allow(unused_variables)]
+    #[inline]
+    fn specialize(descriptor: &mut RenderPipelineDescriptor, key: TilemapMaterialKey<Self>) {}
+}
+
+pub struct TilemapMaterialPlugin<M: TilemapMaterial>(PhantomData<M>);
+
+impl<M: TilemapMaterial> Default for TilemapMaterialPlugin<M> {
+    fn default() -> Self {
+        Self(Default::default())
+    }
+}
+
+impl<M: TilemapMaterial> Plugin for TilemapMaterialPlugin<M>
+where
+    M::Data: PartialEq + Eq + Hash + Clone,
+{
+    fn build(&self, app: &mut App) {
+        app.init_asset::<M>();
+
+        let render_app = app.get_sub_app_mut(RenderApp).unwrap();
+
+        render_app
+            .add_systems(
+                ExtractSchedule,
+                (
+                    extract_tilemap_materials::<M>,
+                    extract_material_handles::<M>,
+                ),
+            )
+            .add_systems(
+                Render,
+                (
+                    prepare_tilemap_materials::<M>.in_set(RenderSet::PrepareAssets),
+                    queue_tilemap_materials::<M>
+                        .in_set(RenderSet::Queue)
+                        .after(prepare_tilemap_materials::<M>)
+                        .after(queue::queue),
+                ),
+            );
+
+        render_app
+            .init_resource::<ExtractedTilemapMaterials<M>>()
+            .init_resource::<RenderTilemapMaterials<M>>()
+            .init_resource::<SpecializedRenderPipelines<TilemapMaterialPipeline<M>>>();
+
+        render_app
+            .add_render_command::<Transparent2d, DrawTilemapMaterial<M>>()
+            .add_render_command::<Transparent2d, DrawTilemapLayeredMaterial<M>>()
+            .add_render_command::<Transparent2d, DrawTilemapPureColorMaterial<M>>();
+    }
+
+    fn finish(&self, app: &mut App) {
+        app.sub_app_mut(RenderApp)
+            .init_resource::<TilemapMaterialPipeline<M>>();
+    }
+}
+
+pub struct TilemapMaterialKey<M: TilemapMaterial> {
+    pub tilemap_key: EntiTilesPipelineKey,
+    pub bind_group_data: M::Data,
+}
+
+impl<M: TilemapMaterial> Eq for TilemapMaterialKey<M> where M::Data: PartialEq {}
+
+impl<M: TilemapMaterial> PartialEq for TilemapMaterialKey<M>
+where
+    M::Data: PartialEq,
+{
+    fn eq(&self, other: &Self) -> bool {
+        self.tilemap_key == other.tilemap_key && self.bind_group_data == other.bind_group_data
+    }
+}
+
+impl<M: TilemapMaterial> Clone for TilemapMaterialKey<M>
+where
+    M::Data: Clone,
+{
+    fn clone(&self) -> Self {
+        Self {
+            tilemap_key: self.tilemap_key.clone(),
+            bind_group_data: self.bind_group_data.clone(),
+        }
+    }
+}
+
+impl<M: TilemapMaterial> Hash for TilemapMaterialKey<M>
+where
+    M::Data: Hash,
+{
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.tilemap_key.hash(state);
+        self.bind_group_data.hash(state);
+    }
+}
+
+/// Marks the extracted tilemaps that are rendered with a custom material,
+/// so the default queue system skips them.
+#[derive(Component)]
+pub struct ExtractedTilemapMaterial;
+
+#[derive(Resource)]
+pub struct TilemapMaterialPipeline<M: TilemapMaterial> {
+    pub tilemap_pipeline: EntiTilesPipeline,
+    pub material_layout: BindGroupLayout,
+    pub vertex_shader: Option<Handle<Shader>>,
+    pub fragment_shader: Option<Handle<Shader>>,
+    marker: PhantomData<M>,
+}
+
+impl<M: TilemapMaterial> FromWorld for TilemapMaterialPipeline<M> {
+    fn from_world(world: &mut bevy::prelude::World) -> Self {
+        let asset_server = world.resource::<AssetServer>();
+        let render_device = world.resource::<RenderDevice>();
+
+        Self {
+            tilemap_pipeline: world.resource::<EntiTilesPipeline>().clone(),
+            material_layout: M::bind_group_layout(render_device),
+            vertex_shader: match M::vertex_shader() {
+                ShaderRef::Default => None,
+                ShaderRef::Handle(handle) => Some(handle),
+                ShaderRef::Path(path) => Some(asset_server.load(path)),
+            },
+            fragment_shader: match M::fragment_shader() {
+                ShaderRef::Default => None,
+                ShaderRef::Handle(handle) => Some(handle),
+                ShaderRef::Path(path) => Some(asset_server.load(path)),
+            },
+            marker: PhantomData,
+        }
+    }
+}
+
+impl<M: TilemapMaterial> SpecializedRenderPipeline for TilemapMaterialPipeline<M>
+where
+    M::Data: PartialEq + Eq + Hash + Clone,
+{
+    type Key = TilemapMaterialKey<M>;
+
+    fn specialize(&self, key: Self::Key) -> RenderPipelineDescriptor {
+        let mut descriptor = self.tilemap_pipeline.specialize(key.tilemap_key.clone());
+
+        // The material comes after the bind groups of the default pipeline.
+        let material_bind_group =
+            ShaderDefVal::UInt("MATERIAL_BIND_GROUP".into(), descriptor.layout.len() as u32);
+        descriptor.layout.push(self.material_layout.clone());
+        descriptor.label = Some("tilemap_material_pipeline".into());
+
+        descriptor
+            .vertex
+            .shader_defs
+            .push(material_bind_group.clone());
+        if let Some(vertex_shader) = &self.vertex_shader {
+            descriptor.vertex.shader = vertex_shader.clone();
+        }
+
+        if let Some(fragment) = descriptor.fragment.as_mut() {
+            fragment.shader_defs.push(material_bind_group);
+            if let Some(fragment_shader) = &self.fragment_shader {
+                fragment.shader = fragment_shader.clone();
+            }
+        }
+
+        M::specialize(&mut descriptor, key);
+        descriptor
+    }
+}
+
+pub struct PreparedTilemapMaterial<M: TilemapMaterial> {
+    pub bindings: Vec<(u32, OwnedBindingResource)>,
+    pub bind_group: BindGroup,
+    pub key: M::Data,
+}
+
+#[derive(Resource)]
+pub struct ExtractedTilemapMaterials<M: TilemapMaterial> {
+    extracted: Vec<(AssetId<M>, M)>,
+    removed: Vec<AssetId<M>>,
+}
+
+impl<M: TilemapMaterial> Default for ExtractedTilemapMaterials<M> {
+    fn default() -> Self {
+        Self {
+            extracted: Default::default(),
+            removed: Default::default(),
+        }
+    }
+}
+
+#[derive(Resource)]
+pub struct RenderTilemapMaterials<M: TilemapMaterial>(
+    HashMap<AssetId<M>, PreparedTilemapMaterial<M>>,
+);
+
+impl<M: TilemapMaterial> Default for RenderTilemapMaterials<M> {
+    fn default() -> Self {
+        Self(Default::default())
+    }
+}
+
+impl<M: TilemapMaterial> RenderTilemapMaterials<M> {
+    pub fn get(&self, id: AssetId<M>) -> Option<&PreparedTilemapMaterial<M>> {
+        self.0.get(&id)
+    }
+}
+
+pub fn extract_tilemap_materials<M: TilemapMaterial>(
+    mut commands: Commands,
+    mut events: Extract<EventReader<AssetEvent<M>>>,
+    assets: Extract<Res<Assets<M>>>,
+) {
+    let mut changed_assets = HashSet::default();
+    let mut removed = Vec::new();
+    for event in events.read() {
+        match event {
+            AssetEvent::Added { id } | AssetEvent::Modified { id } => {
+                changed_assets.insert(*id);
+            }
+            AssetEvent::Removed { id } => {
+                changed_assets.remove(id);
+                removed.push(*id);
+            }
+            AssetEvent::LoadedWithDependencies { .. } => {}
+        }
+    }
+
+    commands.insert_resource(ExtractedTilemapMaterials {
+        extracted: changed_assets
+            .into_iter()
+            .filter_map(|id| assets.get(id).map(|asset| (id, asset.clone())))
+            .collect(),
+        removed,
+    });
+}
+
+pub type TilemapMaterialHandlesQuery<M> =
+    Query<'static, 'static, (Entity, &'static Handle<M>), With<TilemapStorage>>;
+
+pub fn extract_material_handles<M: TilemapMaterial>(
+    mut commands: Commands,
+    tilemaps_query: Extract<TilemapMaterialHandlesQuery<M>>,
+) {
+    commands.insert_or_spawn_batch(
+        tilemaps_query
+            .iter()
+            .map(|(entity, handle)| (entity, (handle.clone_weak(), ExtractedTilemapMaterial)))
+            .collect::<Vec<_>>(),
+    );
+}
+
+pub fn prepare_tilemap_materials<M: TilemapMaterial>(
+    mut prepare_next_frame: Local<Vec<(AssetId<M>, M)>>,
+    mut extracted_assets: ResMut<ExtractedTilemapMaterials<M>>,
+    mut render_materials: ResMut<RenderTilemapMaterials<M>>,
+    render_device: Res<RenderDevice>,
+    images: Res<RenderAssets<Image>>,
+    fallback_image: Res<FallbackImage>,
+    pipeline: Res<TilemapMaterialPipeline<M>>,
+) {
+    let queued_assets = std::mem::take(&mut *prepare_next_frame);
+
+    for removed in std::mem::take(&mut extracted_assets.removed) {
+        render_materials.0.remove(&removed);
+    }
+
+    for (id, material) in queued_assets
+        .into_iter()
+        .chain(std::mem::take(&mut extracted_assets.extracted))
+    {
+        match material.as_bind_group(
+            &pipeline.material_layout,
+            &render_device,
+            &images,
+            &fallback_image,
+        ) {
+            Ok(prepared) => {
+                render_materials.0.insert(
+                    id,
+                    PreparedTilemapMaterial {
+                        bindings: prepared.bindings,
+                        bind_group: prepared.bind_group,
+                        key: prepared.data,
+                    },
+                );
+            }
+            Err(AsBindGroupError::RetryNextUpdate) => {
+                prepare_next_frame.push((id, material));
+            }
+        }
+    }
+}
+
+pub fn queue_tilemap_materials<M: TilemapMaterial>(
+    mut views_query: Query<&mut RenderPhase<Transparent2d>>,
+    tilemaps_query: Query<(&ExtractedTilemap, &Handle<M>)>,
+    mut sp_material_pipeline: ResMut<SpecializedRenderPipelines<TilemapMaterialPipeline<M>>>,
+    material_pipeline: Res<TilemapMaterialPipeline<M>>,
+    render_materials: Res<RenderTilemapMaterials<M>>,
+    mut params: TilemapQueueParams,
+) where
+    M::Data: PartialEq + Eq + Hash + Clone,
+{
+    for mut transparent_phase in views_query.iter_mut() {
+        for (tilemap, handle) in tilemaps_query.iter() {
+            let Some(material) = render_materials.get(handle.id()) else {
+                continue;
+            };
+
+            let is_pure_color = params.bind_groups.queue_textures(
+                tilemap,
+                &params.render_device,
+                &params.textures_storage,
+                &material_pipeline.tilemap_pipeline,
+            );
+
+            let is_layered = !is_pure_color && tilemap.layer_styles.len() > DEFAULT_LAYER_COUNT;
+
+            let pipeline = sp_material_pipeline.specialize(
+                &params.pipeline_cache,
+                &material_pipeline,
+                TilemapMaterialKey {
+                    tilemap_key: EntiTilesPipelineKey {
+                        msaa: params.msaa.samples(),
+                        map_type: tilemap.ty,
+                        is_pure_color,
+                        is_layered,
+                        is_atlas: tilemap.is_atlas(),
+                    },
+                    bind_group_data: material.key.clone(),
+                },
+            );
+
+            let draw_function = {
+                if is_pure_color {
+                    params
+                        .draw_functions
+                        .read()
+                        .get_id::<DrawTilemapPureColorMaterial<M>>()
+                        .unwrap()
+                } else if is_layered {
+                    params
+                        .draw_functions
+                        .read()
+                        .get_id::<DrawTilemapLayeredMaterial<M>>()
+                        .unwrap()
+                } else {
+                    params
+                        .draw_functions
+                        .read()
+                        .get_id::<DrawTilemapMaterial<M>>()
+                        .unwrap()
+                }
+            };
+
+            for sort_key in queue::get_sort_keys(tilemap, &params.render_chunks) {
+                transparent_phase.add(Transparent2d {
+                    sort_key,
+                    entity: tilemap.id,
+                    pipeline,
+                    draw_function,
+                    batch_range: 0..1,
+                    dynamic_offset: None,
+                });
+            }
+        }
+    }
+}
+
+pub type DrawTilemapMaterial<M> = (
+    SetPipeline,
+    SetTilemapViewBindGroup<0>,
+    SetTilemapUniformBufferBindGroup<1>,
+    SetTilemapColorTextureBindGroup<2>,
+    SetTilemapStorageBufferBindGroup<3>,
+    SetTilemapMaterialBindGroup<M, 4>,
+    DrawTileMesh,
+);
+
+pub type DrawTilemapLayeredMaterial<M> = (
+    SetPipeline,
+    SetTilemapViewBindGroup<0>,
+    SetTilemapUniformBufferBindGroup<1>,
+    SetTilemapColorTextureBindGroup<2>,
+    SetTilemapMaterialBindGroup<M, 4>,
+    DrawLayeredTileMesh<3>,
+);
+
+pub type DrawTilemapPureColorMaterial<M> = (
+    SetPipeline,
+    SetTilemapViewBindGroup<0>,
+    SetTilemapUniformBufferBindGroup<1>,
+    SetTilemapMaterialBindGroup<M, 2>,
+    DrawTileMesh,
+);
+
+pub struct SetTilemapMaterialBindGroup<M: TilemapMaterial, const I: usize>(PhantomData<M>);
+impl<M: TilemapMaterial, const I: usize> RenderCommand<Transparent2d>
+    for SetTilemapMaterialBindGroup<M, I>
+{
+    type Param = SRes<RenderTilemapMaterials<M>>;
+
+    type ViewWorldQuery = ();
+
+    type ItemWorldQuery = Read<Handle<M>>;
+
+    #[inline]
+    fn render<'w>(
+        _item: &Transparent2d,
+        _view: ROQueryItem<'w, Self::ViewWorldQuery>,
+        handle: ROQueryItem<'w, Self::ItemWorldQuery>,
+        materials: SystemParamItem<'w, '_, Self::Param>,
+        pass: &mut TrackedRenderPass<'w>,
+    ) -> RenderCommandResult {
+        if let Some(material) = materials.into_inner().get(handle.id()) {
+            pass.set_bind_group(I, &material.bind_group, &[]);
+            RenderCommandResult::Success
+        } else {
+            error!("Failed to get tilemap material bind group!");
+            RenderCommandResult::Failure
+        }
+    }
+}
@@ -2,6 +2,7 @@ use bevy::{
     app::Update,
     asset::load_internal_asset,
     core_pipeline::core_2d::Transparent2d,
+    math::Vec3,
     prelude::{Handle, IntoSystemConfigs, Plugin, Shader},
     render::{
         mesh::MeshVertexAttribute,
@@ -13,10 +14,10 @@ use bevy::{
 
 use crate::render::{
     binding::{TilemapBindGroupLayouts, TilemapBindGroups},
-    buffer::{TilemapStorageBuffers, TilemapUniformBuffer},
+    buffer::{TilemapLayerStyleBuffers, TilemapStorageBuffers, TilemapUniformBuffer},
     chunk::{ChunkUnload, RenderChunkStorage, UnloadRenderChunk},
     culling::FrustumCulling,
-    draw::{DrawTilemap, DrawTilemapPureColor},
+    draw::{DrawTilemap, DrawTilemapLayered, DrawTilemapPureColor},
     pipeline::EntiTilesPipeline,
     texture::TilemapTexturesStorage,
 };
@@ -27,6 +28,7 @@ pub mod chunk;
 pub mod culling;
 pub mod draw;
 pub mod extract;
+pub mod material;
 pub mod pipeline;
 pub mod prepare;
 pub mod queue;
@@ -36,16 +38,25 @@ const SQUARE: Handle<Shader> = Handle::weak_from_u128(54311635145631);
 const ISOMETRIC: Handle<Shader> = Handle::weak_from_u128(45522415151365135);
 const HEXAGONAL: Handle<Shader> = Handle::weak_from_u128(341658413214563135);
 const COMMON: Handle<Shader> = Handle::weak_from_u128(1321023135616351);
+const FUNCTIONS: Handle<Shader> = Handle::weak_from_u128(7841265132056541);
 const TILEMAP_SHADER: Handle<Shader> = Handle::weak_from_u128(89646584153215);
 
 pub const TILEMAP_MESH_ATTR_INDEX: MeshVertexAttribute =
     MeshVertexAttribute::new("GridIndex", 14513156146, VertexFormat::Sint32x4);
 pub const TILEMAP_MESH_ATTR_COLOR: MeshVertexAttribute =
     MeshVertexAttribute::new("Color", 85415341854, VertexFormat::Float32x4);
+pub const TILEMAP_MESH_ATTR_FOG: MeshVertexAttribute =
+    MeshVertexAttribute::new("Fog", 95614213256, VertexFormat::Float32);
+pub const TILEMAP_MESH_ATTR_LIGHT: MeshVertexAttribute =
+    MeshVertexAttribute::new("Light", 125614846135, VertexFormat::Float32x4);
+pub const TILEMAP_MESH_ATTR_RECT: MeshVertexAttribute =
+    MeshVertexAttribute::new("RenderRect", 135614846135, VertexFormat::Float32x4);
 pub const TILEMAP_MESH_ATTR_TEX_INDICES: MeshVertexAttribute =
     MeshVertexAttribute::new("TextureIndex", 186541653135, VertexFormat::Sint32x4);
 pub const TILEMAP_MESH_ATTR_FLIP: MeshVertexAttribute =
     MeshVertexAttribute::new("Flip", 7365156123161, VertexFormat::Uint32x4);
+pub const TILEMAP_MESH_ATTR_ANIM: MeshVertexAttribute =
+    MeshVertexAttribute::new("Animation", 8451236845123, VertexFormat::Float32x4);
 
 pub struct EntiTilesRendererPlugin;
 
@@ -55,6 +66,7 @@ impl Plugin for EntiTilesRendererPlugin {
         load_internal_asset!(app, ISOMETRIC, "shaders/isometric.wgsl", Shader::from_wgsl);
         load_internal_asset!(app, HEXAGONAL, "shaders/hexagonal.wgsl", Shader::from_wgsl);
         load_internal_asset!(app, COMMON, "shaders/common.wgsl", Shader::from_wgsl);
+        load_internal_asset!(app, FUNCTIONS, "shaders/functions.wgsl", Shader::from_wgsl);
 
         app.add_systems(Update, culling::cull_tilemaps);
 
@@ -80,6 +92,10 @@ impl Plugin for EntiTilesRendererPlugin {
                     extract::extract_tiles,
                     extract::extract_view,
                     extract::extract_unloaded_chunks,
+                    // fog
+                    extract::extract_chunk_corners::<f32>,
+                    // light
+                    extract::extract_chunk_corners::<Vec3>,
                     extract::extract_resources,
                     extract::extract_despawned_tilemaps,
                     extract::extract_despawned_tiles,
@@ -91,23 +107,41 @@ impl Plugin for EntiTilesRendererPlugin {
                     prepare::prepare_tilemaps,
                     prepare::prepare_tiles,
                     prepare::prepare_unloaded_chunks,
+                    // fog
+                    prepare::prepare_chunk_corners::<f32>.after(prepare::prepare_tiles),
+                    // light
+                    prepare::prepare_chunk_corners::<Vec3>.after(prepare::prepare_tiles),
+                    prepare::prepare_depth_slices
+                        .after(prepare::prepare_tilemaps)
+                        .after(culling::cull_chunks),
+                    prepare::prepare_chunk_layers
+                        .after(prepare::prepare_tilemaps)
+                        .after(prepare::prepare_tiles)
+                        .after(culling::cull_chunks),
                     prepare::prepare_despawned_tilemaps,
                     prepare::prepare_despawned_tiles,
                     culling::cull_chunks,
                 )
                     .in_set(RenderSet::Prepare),
             )
-            .add_systems(Render, queue::queue.in_set(RenderSet::Queue));
+            .add_systems(
+                Render,
+                (queue::queue_textures, queue::queue)
+                    .chain()
+                    .in_set(RenderSet::Queue),
+            );
 
         render_app
             .init_resource::<RenderChunkStorage>()
             .init_resource::<TilemapTexturesStorage>()
             .init_resource::<TilemapUniformBuffer>()
             .init_resource::<TilemapStorageBuffers>()
+            .init_resource::<TilemapLayerStyleBuffers>()
             .init_resource::<TilemapBindGroups>();
 
         render_app
             .add_render_command::<Transparent2d, DrawTilemap>()
+            .add_render_command::<Transparent2d, DrawTilemapLayered>()
             .add_render_command::<Transparent2d, DrawTilemapPureColor>();
     }
 
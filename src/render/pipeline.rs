@@ -15,12 +15,14 @@ use crate::tilemap::map::TilemapType;
 
 use super::{binding::TilemapBindGroupLayouts, TILEMAP_SHADER};
 
-#[derive(Resource)]
+#[derive(Resource, Clone)]
 pub struct EntiTilesPipeline {
     pub view_layout: BindGroupLayout,
     pub uniform_buffers_layout: BindGroupLayout,
     pub storage_buffers_layout: BindGroupLayout,
-    pub color_texture_layout: BindGroupLayout,
+    pub layered_storage_buffers_layout: BindGroupLayout,
+    pub color_texture_array_layout: BindGroupLayout,
+    pub color_texture_atlas_layout: BindGroupLayout,
 }
 
 #[derive(PartialEq, Eq, Hash, Clone)]
@@ -28,6 +30,10 @@ pub struct EntiTilesPipelineKey {
     pub msaa: u32,
     pub map_type: TilemapType,
     pub is_pure_color: bool,
+    /// Has more than `DEFAULT_LAYER_COUNT` layers.
+    pub is_layered: bool,
+    /// The texture is sampled as an atlas instead of a texture array.
+    pub is_atlas: bool,
 }
 
 impl FromWorld for EntiTilesPipeline {
@@ -37,7 +43,9 @@ impl FromWorld for EntiTilesPipeline {
             view_layout: layouts.view_layout.clone(),
             uniform_buffers_layout: layouts.tilemap_uniforms_layout.clone(),
             storage_buffers_layout: layouts.tilemap_storage_layout.clone(),
-            color_texture_layout: layouts.color_texture_layout.clone(),
+            layered_storage_buffers_layout: layouts.tilemap_layered_storage_layout.clone(),
+            color_texture_array_layout: layouts.color_texture_array_layout.clone(),
+            color_texture_atlas_layout: layouts.color_texture_atlas_layout.clone(),
         }
     }
 }
@@ -57,8 +65,9 @@ impl SpecializedRenderPipeline for EntiTilesPipeline {
             }
             .into(),
         );
-        #[cfg(feature = "atlas")]
-        shader_defs.push("ATLAS".into());
+        if key.is_atlas {
+            shader_defs.push("ATLAS".into());
+        }
 
         let mut vtx_fmt = vec![
             // position
@@ -67,15 +76,26 @@ impl SpecializedRenderPipeline for EntiTilesPipeline {
             VertexFormat::Sint32x4,
             // color
             VertexFormat::Float32x4,
+            // fog
+            VertexFormat::Float32,
+            // light
+            VertexFormat::Float32x4,
+            // render size + anchor
+            VertexFormat::Float32x4,
         ];
 
         if key.is_pure_color {
             shader_defs.push("PURE_COLOR".into());
         } else {
+            if key.is_layered {
+                shader_defs.push("LAYER_STORAGE".into());
+            }
             // texture_indices
             vtx_fmt.push(VertexFormat::Sint32x4);
             // flip
             vtx_fmt.push(VertexFormat::Uint32x4);
+            // anim
+            vtx_fmt.push(VertexFormat::Float32x4);
         }
 
         let vertex_layout =
@@ -90,9 +110,17 @@ impl SpecializedRenderPipeline for EntiTilesPipeline {
 
         if !key.is_pure_color {
             // group(2)
-            layout.push(self.color_texture_layout.clone());
+            if key.is_atlas {
+                layout.push(self.color_texture_atlas_layout.clone());
+            } else {
+                layout.push(self.color_texture_array_layout.clone());
+            }
             // group(3)
-            layout.push(self.storage_buffers_layout.clone());
+            if key.is_layered {
+                layout.push(self.layered_storage_buffers_layout.clone());
+            } else {
+                layout.push(self.storage_buffers_layout.clone());
+            }
         }
 
         RenderPipelineDescriptor {
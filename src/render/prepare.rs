@@ -1,17 +1,27 @@
 use bevy::{
     ecs::entity::Entity,
     prelude::{Commands, Query, Res, ResMut},
-    render::renderer::{RenderDevice, RenderQueue},
+    render::{
+        render_resource::BindGroupEntry,
+        renderer::{RenderDevice, RenderQueue},
+    },
 };
 
-use crate::tilemap::despawn::{DespawnedTile, DespawnedTilemap};
+use crate::{
+    tilemap::{
+        despawn::{DespawnedTile, DespawnedTilemap},
+        map::TilemapLayerStyle,
+    },
+    DEFAULT_LAYER_COUNT,
+};
 
 use super::{
     binding::TilemapBindGroups,
     buffer::{
-        PerTilemapBuffersStorage, TilemapStorageBuffers, TilemapUniformBuffer, UniformBuffer,
+        PerTilemapBuffersStorage, TilemapLayerStyleBuffers, TilemapLayerStyleUniform,
+        TilemapStorageBuffers, TilemapUniformBuffer, UniformBuffer,
     },
-    chunk::{TilemapRenderChunk, UnloadRenderChunk},
+    chunk::{CornerRenderChunks, RenderChunkCorners, TilemapRenderChunk, UnloadRenderChunk},
     extract::{ExtractedTile, ExtractedTilemap},
     pipeline::EntiTilesPipeline,
     texture::TilemapTexturesStorage,
@@ -26,12 +36,14 @@ pub fn prepare_tilemaps(
     mut render_chunks: ResMut<RenderChunkStorage>,
     mut uniform_buffers: ResMut<TilemapUniformBuffer>,
     mut storage_buffers: ResMut<TilemapStorageBuffers>,
+    mut layer_style_buffers: ResMut<TilemapLayerStyleBuffers>,
     mut textures_storage: ResMut<TilemapTexturesStorage>,
     entitiles_pipeline: Res<EntiTilesPipeline>,
     mut bind_groups: ResMut<TilemapBindGroups>,
 ) {
     uniform_buffers.clear();
     storage_buffers.clear();
+    layer_style_buffers.clear();
 
     extracted_tilemaps.for_each(|tilemap| {
         commands
@@ -43,21 +55,102 @@ pub fn prepare_tilemaps(
         if let Some(texture) = tilemap.texture.as_ref() {
             storage_buffers
                 .get_or_insert_buffer(tilemap.id)
-                .extend(&tilemap.animations.as_ref().unwrap().0);
+                .extend(&tilemap.animations.as_ref().unwrap().seqs);
+
+            let styles = layer_style_buffers.get_or_insert_buffer(tilemap.id);
+            styles.extend(
+                tilemap
+                    .layer_styles
+                    .iter()
+                    .map(TilemapLayerStyleUniform::from),
+            );
+            // The fast path always reads the styles of the first 4 layers.
+            if styles.len() < DEFAULT_LAYER_COUNT {
+                styles.resize(
+                    DEFAULT_LAYER_COUNT,
+                    TilemapLayerStyleUniform::from(&TilemapLayerStyle::default()),
+                );
+            }
 
-            if !textures_storage.contains(&texture.texture) {
-                textures_storage.insert(texture.clone_weak(), texture.desc());
+            let render_handle = texture.get_render_handle();
+            if !textures_storage.contains(&render_handle) {
+                textures_storage.insert(render_handle, texture);
             }
         }
     });
 
-    #[cfg(not(feature = "atlas"))]
     textures_storage.prepare_textures(&render_device);
     uniform_buffers.write(&render_device, &render_queue);
     storage_buffers.write(&render_device, &render_queue);
+    layer_style_buffers.write(&render_device, &render_queue);
 
     bind_groups.bind_uniform_buffers(&render_device, &mut uniform_buffers, &entitiles_pipeline);
-    bind_groups.bind_storage_buffers(&render_device, &mut storage_buffers, &entitiles_pipeline);
+    bind_groups.bind_storage_buffers(
+        &render_device,
+        &mut storage_buffers,
+        &mut layer_style_buffers,
+        &entitiles_pipeline,
+    );
+}
+
+/// Create the storage bind groups for the visible chunks of
+/// tilemaps with more than `DEFAULT_LAYER_COUNT` layers.
+///
+/// This runs after the chunk meshes are updated, as the layer buffers are recreated with them.
+pub fn prepare_depth_slices(
+    extracted_tilemaps: Query<&ExtractedTilemap>,
+    mut render_chunks: ResMut<RenderChunkStorage>,
+) {
+    extracted_tilemaps.for_each(|tilemap| {
+        render_chunks.prepare_depth_slices(tilemap);
+    });
+}
+
+pub fn prepare_chunk_layers(
+    render_device: Res<RenderDevice>,
+    mut render_chunks: ResMut<RenderChunkStorage>,
+    mut storage_buffers: ResMut<TilemapStorageBuffers>,
+    mut layer_style_buffers: ResMut<TilemapLayerStyleBuffers>,
+    entitiles_pipeline: Res<EntiTilesPipeline>,
+) {
+    let mut anim_seqs = storage_buffers.bindings();
+    let mut styles = layer_style_buffers.bindings();
+
+    render_chunks
+        .value
+        .iter_mut()
+        .for_each(|(tilemap, chunks)| {
+            let (Some(anim_seqs), Some(styles)) =
+                (anim_seqs.remove(tilemap), styles.remove(tilemap))
+            else {
+                return;
+            };
+
+            chunks.values_mut().filter(|c| c.visible).for_each(|chunk| {
+                let Some(layers) = &chunk.layers_buffer else {
+                    return;
+                };
+
+                chunk.layers_bind_group = Some(render_device.create_bind_group(
+                    Some("tilemap_layered_storage_bind_group"),
+                    &entitiles_pipeline.layered_storage_buffers_layout,
+                    &[
+                        BindGroupEntry {
+                            binding: 0,
+                            resource: anim_seqs.clone(),
+                        },
+                        BindGroupEntry {
+                            binding: 1,
+                            resource: styles.clone(),
+                        },
+                        BindGroupEntry {
+                            binding: 2,
+                            resource: layers.as_entire_binding(),
+                        },
+                    ],
+                ));
+            });
+        });
 }
 
 pub fn prepare_tiles(
@@ -91,14 +184,38 @@ pub fn prepare_unloaded_chunks(
     });
 }
 
+pub fn prepare_chunk_corners<T: RenderChunkCorners>(
+    extracted_tilemaps: Query<&ExtractedTilemap>,
+    corner_chunks_query: Query<(Entity, &CornerRenderChunks<T>)>,
+    mut render_chunks: ResMut<RenderChunkStorage>,
+) {
+    corner_chunks_query.for_each(|(entity, corner_chunks)| {
+        let Ok(tilemap) = extracted_tilemaps.get(entity) else {
+            return;
+        };
+
+        let chunks = render_chunks.value.entry(entity).or_default();
+        corner_chunks.0.iter().for_each(|(index, corners)| {
+            T::apply(
+                chunks
+                    .entry(*index)
+                    .or_insert_with(|| TilemapRenderChunk::from_index(*index, tilemap)),
+                corners,
+            );
+        });
+    });
+}
+
 pub fn prepare_despawned_tilemaps(
     mut render_chunks: ResMut<RenderChunkStorage>,
     mut storage_buffers: ResMut<TilemapStorageBuffers>,
+    mut layer_style_buffers: ResMut<TilemapLayerStyleBuffers>,
     tilemaps_query: Query<&DespawnedTilemap>,
 ) {
     tilemaps_query.for_each(|map| {
         render_chunks.remove_tilemap(map.0);
         storage_buffers.remove(map.0);
+        layer_style_buffers.remove(map.0);
     });
 }
 
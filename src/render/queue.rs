@@ -1,57 +1,70 @@
 use bevy::{
     core_pipeline::core_2d::Transparent2d,
-    prelude::{Commands, Entity, Msaa, Query, Res, ResMut},
+    ecs::system::SystemParam,
+    prelude::{Commands, Entity, Msaa, Query, Res, ResMut, Without},
     render::{
         render_asset::RenderAssets,
         render_phase::{DrawFunctions, RenderPhase},
         render_resource::{BindGroupEntry, PipelineCache, SpecializedRenderPipelines},
-        renderer::RenderDevice,
+        renderer::{RenderDevice, RenderQueue},
         texture::Image,
         view::ViewUniforms,
     },
     utils::FloatOrd,
 };
 
+use crate::DEFAULT_LAYER_COUNT;
+
 use super::{
     binding::{TilemapBindGroups, TilemapViewBindGroup},
-    draw::{DrawTilemap, DrawTilemapPureColor},
+    chunk::RenderChunkStorage,
+    draw::{DrawTilemap, DrawTilemapLayered, DrawTilemapPureColor},
     extract::ExtractedTilemap,
+    material::ExtractedTilemapMaterial,
     pipeline::{EntiTilesPipeline, EntiTilesPipelineKey},
     texture::TilemapTexturesStorage,
 };
 
-#[cfg(not(feature = "atlas"))]
-use bevy::render::renderer::RenderQueue;
+/// The resources used to queue tilemaps, with or without a `TilemapMaterial`.
+#[derive(SystemParam)]
+pub struct TilemapQueueParams<'w> {
+    pub(crate) pipeline_cache: Res<'w, PipelineCache>,
+    pub(crate) draw_functions: Res<'w, DrawFunctions<Transparent2d>>,
+    pub(crate) render_device: Res<'w, RenderDevice>,
+    pub(crate) bind_groups: ResMut<'w, TilemapBindGroups>,
+    pub(crate) textures_storage: Res<'w, TilemapTexturesStorage>,
+    pub(crate) render_chunks: Res<'w, RenderChunkStorage>,
+    pub(crate) msaa: Res<'w, Msaa>,
+}
+
+pub type TilemapsWithoutMaterialQuery<'w, 's> =
+    Query<'w, 's, &'static ExtractedTilemap, Without<ExtractedTilemapMaterial>>;
+
+pub fn queue_textures(
+    render_device: Res<RenderDevice>,
+    render_queue: Res<RenderQueue>,
+    mut textures_storage: ResMut<TilemapTexturesStorage>,
+    mut render_images: ResMut<RenderAssets<Image>>,
+) {
+    textures_storage.queue_textures(&render_device, &render_queue, &mut render_images);
+}
 
 pub fn queue(
     mut commands: Commands,
     mut views_query: Query<(Entity, &mut RenderPhase<Transparent2d>)>,
-    tilemaps_query: Query<&ExtractedTilemap>,
-    pipeline_cache: Res<PipelineCache>,
-    draw_functions: Res<DrawFunctions<Transparent2d>>,
+    tilemaps_query: TilemapsWithoutMaterialQuery,
     mut sp_entitiles_pipeline: ResMut<SpecializedRenderPipelines<EntiTilesPipeline>>,
     entitiles_pipeline: Res<EntiTilesPipeline>,
     view_uniforms: Res<ViewUniforms>,
-    render_device: Res<RenderDevice>,
-    mut bind_groups: ResMut<TilemapBindGroups>,
-    mut textures_storage: ResMut<TilemapTexturesStorage>,
-    msaa: Res<Msaa>,
-    #[cfg(not(feature = "atlas"))] render_queue: Res<RenderQueue>,
-    #[cfg(not(feature = "atlas"))] render_images: Res<RenderAssets<Image>>,
-    #[cfg(feature = "atlas")] mut render_images: ResMut<RenderAssets<Image>>,
+    mut params: TilemapQueueParams,
 ) {
     let Some(view_binding) = view_uniforms.uniforms.binding() else {
         return;
     };
 
-    #[cfg(not(feature = "atlas"))]
-    textures_storage.queue_textures(&render_device, &render_queue, &render_images);
-    #[cfg(feature = "atlas")]
-    textures_storage.queue_textures(&render_device, &mut render_images);
-
     for (view_entity, mut transparent_phase) in views_query.iter_mut() {
         commands.entity(view_entity).insert(TilemapViewBindGroup {
-            value: render_device.create_bind_group(
+            value: params.render_device.create_bind_group(
                 "tilemap_view_bind_group",
                 &entitiles_pipeline.view_layout,
                 &[BindGroupEntry {
@@ -65,42 +78,81 @@ pub fn queue(
         radsort::sort_by_key(&mut tilemaps, |m| m.transform.z_index);
 
         for tilemap in tilemaps.iter() {
-            let is_pure_color = bind_groups.queue_textures(
-                &tilemap,
-                &render_device,
-                &textures_storage,
+            let is_pure_color = params.bind_groups.queue_textures(
+                tilemap,
+                &params.render_device,
+                &params.textures_storage,
                 &entitiles_pipeline,
             );
 
+            let is_layered = !is_pure_color && tilemap.layer_styles.len() > DEFAULT_LAYER_COUNT;
+
             let pipeline = sp_entitiles_pipeline.specialize(
-                &pipeline_cache,
+                &params.pipeline_cache,
                 &entitiles_pipeline,
                 EntiTilesPipelineKey {
-                    msaa: msaa.samples(),
+                    msaa: params.msaa.samples(),
                     map_type: tilemap.ty,
                     is_pure_color,
+                    is_layered,
+                    is_atlas: tilemap.is_atlas(),
                 },
             );
 
             let draw_function = {
                 if is_pure_color {
-                    draw_functions
+                    params
+                        .draw_functions
                         .read()
                         .get_id::<DrawTilemapPureColor>()
                         .unwrap()
+                } else if is_layered {
+                    params
+                        .draw_functions
+                        .read()
+                        .get_id::<DrawTilemapLayered>()
+                        .unwrap()
                 } else {
-                    draw_functions.read().get_id::<DrawTilemap>().unwrap()
+                    params
+                        .draw_functions
+                        .read()
+                        .get_id::<DrawTilemap>()
+                        .unwrap()
                 }
             };
 
-            transparent_phase.add(Transparent2d {
-                sort_key: FloatOrd(tilemap.transform.z_index as f32),
-                entity: tilemap.id,
-                pipeline,
-                draw_function,
-                batch_range: 0..1,
-                dynamic_offset: None,
-            });
+            for sort_key in get_sort_keys(tilemap, &params.render_chunks) {
+                transparent_phase.add(Transparent2d {
+                    sort_key,
+                    entity: tilemap.id,
+                    pipeline,
+                    draw_function,
+                    batch_range: 0..1,
+                    dynamic_offset: None,
+                });
+            }
         }
     }
 }
+
+/// Get the sort keys of the phase items of a tilemap. Tilemaps without
+/// `TilemapDepthMode` are drawn by one item at the z index, otherwise there's
+/// one item for each depth of the visible tiles.
+///
+/// The depths are grouped in `prepare_depth_slices`. As it runs after queueing,
+/// the keys are the ones of the previous frame, and the items whose depth is gone
+/// are skipped when drawing.
+pub fn get_sort_keys(
+    tilemap: &ExtractedTilemap,
+    render_chunks: &RenderChunkStorage,
+) -> Vec<FloatOrd> {
+    if tilemap.depth_mode.is_none() {
+        return vec![FloatOrd(tilemap.get_base_depth())];
+    }
+
+    render_chunks
+        .depth_slices
+        .get(&tilemap.id)
+        .map(|slices| slices.keys().copied().collect())
+        .unwrap_or_default()
+}
@@ -3,25 +3,34 @@
 struct VertexInput {
     @builtin(vertex_index) v_index: u32,
     @location(0) position: vec3<f32>,
-    // When the third and forth component of index are not -1,
-    // it means this tile is a animated tile
-    // So the zw components are the start index and the length of the animation sequence
     @location(1) index: vec4<i32>,
     @location(2) color: vec4<f32>,
+    // The brightness of the fog. Negative means using the default one.
+    @location(3) fog: f32,
+    // The light of the tile. Negative alpha means using the ambient light.
+    @location(4) light: vec4<f32>,
+    // The render size and the anchor of the tile. Negative means using the ones in the uniform.
+    @location(5) rect: vec4<f32>,
 #ifndef PURE_COLOR
-    @location(3) texture_indices: vec4<i32>,
-    @location(4) flip: vec4<u32>,
+    // Values less than -1 are animated layers, the negated value is the start of the sequence.
+    // With LAYER_STORAGE, xy are the offset and the count of the layers in tile_layers.
+    @location(6) texture_indices: vec4<i32>,
+    // The third bit means the animation of the layer is paused, the fourth bit is the diagonal flip.
+    @location(7) flip: vec4<u32>,
+    // The phase of the animation of each layer.
+    @location(8) anim: vec4<f32>,
 #endif
 }
 
 struct VertexOutput {
     @builtin(position) position: vec4<f32>,
     @location(0) color: vec4<f32>,
+    @location(5) fog: f32,
+    @location(6) light: vec3<f32>,
 #ifndef PURE_COLOR
     @location(1) uv: vec2<f32>,
     @location(2) flip: vec4<u32>,
     @location(3) texture_indices: vec4<i32>,
-    @location(4) anim_flag: i32,
 #endif
 }
 
@@ -36,11 +45,13 @@ struct Tilemap {
     // this value will only be meaningful when the tilemap is hexagonal!
     hex_legs: f32,
     time: f32,
-#ifdef ATLAS
-    // texture size in tiles
+    // the brightness of tiles without fog data
+    unexplored_brightness: f32,
+    // the light of tiles without light data
+    ambient_light: vec4<f32>,
+    // texture size in tiles, only used by atlas textures
     texture_tiled_size: vec2<i32>,
     tile_uv_size: vec2<f32>,
-#endif
 }
 
 @group(1) @binding(0)
@@ -58,6 +69,27 @@ var color_texture: texture_2d_array<f32>;
 @group(2) @binding(1)
 var color_texture_sampler: sampler;
 
+struct LayerStyle {
+    tint: vec4<f32>,
+    opacity: f32,
+    // 0: normal, 1: additive, 2: multiply
+    blend_mode: u32,
+}
+
 @group(3) @binding(0)
 var<storage> anim_seqs: array<i32>;
+
+@group(3) @binding(1)
+var<storage> layer_styles: array<LayerStyle>;
+
+#ifdef LAYER_STORAGE
+struct TileLayer {
+    texture_index: i32,
+    flip: u32,
+    anim_phase: f32,
+}
+
+@group(3) @binding(2)
+var<storage> tile_layers: array<TileLayer>;
+#endif
 #endif
@@ -0,0 +1,230 @@
+#define_import_path bevy_entitiles::functions
+
+#import bevy_entitiles::common::{
+    VertexInput, VertexOutput, tilemap, atlas_uvs, anim_seqs, layer_styles
+}
+#import bevy_sprite::mesh2d_view_bindings::view
+
+#ifdef SQUARE
+    #import bevy_entitiles::square::get_mesh_origin
+#endif
+
+#ifdef ISOMETRIC
+    #import bevy_entitiles::isometric::get_mesh_origin
+#endif
+
+#ifdef HEXAGONAL
+    #import bevy_entitiles::hexagonal::get_mesh_origin
+#endif
+
+#ifndef PURE_COLOR
+// The three numbers before the start index are the length, mode and fps.
+// See register_animation_with_mode function in TilemapAnimations.
+fn get_animation_frame(start: i32, phase: f32, paused: bool) -> i32 {
+    let length = anim_seqs[start - 3];
+    let mode = anim_seqs[start - 2];
+    let fps = anim_seqs[start - 1];
+    let time = max(select(tilemap.time + phase, phase, paused), 0.);
+
+    if fps > 0 {
+        let frame = i32(time * f32(fps));
+        switch mode {
+            // PingPong
+            case 1: {
+                if length < 2 {
+                    return 0;
+                }
+                let cycle = 2 * length - 2;
+                let f = frame % cycle;
+                return select(f, cycle - f, f >= length);
+            }
+            // Once
+            case 2: {
+                return min(frame, length - 1);
+            }
+            // Loop
+            default: {
+                return frame % length;
+            }
+        }
+    }
+
+    // The cumulative end time of each frame is stored after the indices.
+    let duration = bitcast<f32>(anim_seqs[start + length * 2 - 1]);
+    var t = time;
+    switch mode {
+        case 1: {
+            let cycle_time = t % (duration * 2.);
+            t = select(cycle_time, duration * 2. - cycle_time, cycle_time >= duration);
+        }
+        case 2: {
+            t = min(t, duration);
+        }
+        default: {
+            t = t % duration;
+        }
+    }
+
+    for (var i = 0; i < length; i++) {
+        if t < bitcast<f32>(anim_seqs[start + length + i]) {
+            return i;
+        }
+    }
+    return length - 1;
+}
+
+// Returns the texture index of the current frame if the layer is animated.
+fn resolve_texture_index(texture_index: i32, anim_phase: f32, flip: u32) -> i32 {
+    if texture_index < -1 {
+        let start = -texture_index;
+        let paused = (flip & 4u) != 0u;
+        return anim_seqs[start + get_animation_frame(start, anim_phase, paused)];
+    }
+    return texture_index;
+}
+
+fn sample_layer(texture_index: i32, flip: u32, input_uv: vec2<f32>) -> vec4<f32> {
+    var uv = input_uv;
+    if (flip & 1u) != 0u {
+        uv.x = 1. - uv.x;
+    }
+    if (flip & 2u) != 0u {
+        uv.y = 1. - uv.y;
+    }
+    // The diagonal flip is applied to the texture before the other flips,
+    // so it's applied to the uv after them.
+    if (flip & 8u) != 0u {
+        uv = uv.yx;
+    }
+#ifdef ATLAS
+    let tile_index = vec2<f32>(f32(texture_index % tilemap.texture_tiled_size.x),
+                               f32(texture_index / tilemap.texture_tiled_size.x));
+    let atlas_uv = (tile_index + uv) * tilemap.tile_uv_size;
+    return textureSample(bevy_entitiles::common::color_texture,
+                         bevy_entitiles::common::color_texture_sampler,
+                         atlas_uv);
+#else
+    return textureSample(bevy_entitiles::common::color_texture,
+                         bevy_entitiles::common::color_texture_sampler,
+                         uv, texture_index);
+#endif
+}
+
+fn blend_layer(color: vec4<f32>, tex_color: vec4<f32>, layer: u32) -> vec4<f32> {
+    let style = layer_styles[layer];
+    let src = tex_color * style.tint;
+    var alpha = src.a * style.opacity;
+    if layer < 4u {
+        alpha *= tilemap.layer_opacities[layer];
+    }
+    let out_alpha = color.a + alpha * (1. - color.a);
+
+    switch style.blend_mode {
+        // Additive
+        case 1u: {
+            return vec4<f32>(color.rgb + src.rgb * alpha, out_alpha);
+        }
+        // Multiply
+        case 2u: {
+            let multiplied = mix(src.rgb, color.rgb * src.rgb, color.a);
+            return vec4<f32>(mix(color.rgb, multiplied, alpha), out_alpha);
+        }
+        // Normal
+        default: {
+            return mix(color, src, alpha);
+        }
+    }
+}
+#endif
+
+fn get_vertex_output(input: VertexInput) -> VertexOutput {
+    var output: VertexOutput;
+    var mesh_origin = get_mesh_origin(input);
+    
+    var translations = array<vec2<f32>, 4>(
+        vec2<f32>(0., 0.),
+        vec2<f32>(0., 1.),
+        vec2<f32>(1., 1.),
+        vec2<f32>(1., 0.),
+    );
+
+    let rect = select(input.rect,
+                      vec4<f32>(tilemap.tile_render_size, tilemap.pivot),
+                      input.rect.x < 0.);
+    var position_model = (translations[input.v_index % 4u] - rect.zw) * rect.xy + mesh_origin;
+    var position_world = vec4<f32>((tilemap.rot_mat * position_model) + tilemap.translation, 0., 1.);
+
+    output.position = view.view_proj * position_world;
+    output.color = vec4<f32>(pow(input.color.rgb, vec3<f32>(2.2)), input.color.a);
+    output.fog = select(input.fog, tilemap.unexplored_brightness, input.fog < 0.);
+    output.light = select(input.light.rgb, tilemap.ambient_light.rgb, input.light.a < 0.);
+
+#ifndef PURE_COLOR
+#ifdef ATLAS
+    var uvs = array<vec2<f32>, 4>(
+        vec2<f32>(0., 1.),
+        vec2<f32>(0., 0.),
+        vec2<f32>(1., 0.),
+        vec2<f32>(1., 1.),
+    );
+#else
+    var uvs = array<vec2<f32>, 4>(
+        vec2<f32>(0., 1.),
+        vec2<f32>(0., 0.),
+        vec2<f32>(1., 0.),
+        vec2<f32>(1., 1.),
+    );
+#endif
+    output.uv = uvs[(input.v_index + tilemap.uv_rot) % 4u];
+    output.flip = input.flip;
+
+#ifdef LAYER_STORAGE
+    output.texture_indices = input.texture_indices;
+#else
+    for (var i = 0u; i < 4u; i++) {
+        output.texture_indices[i] = resolve_texture_index(input.texture_indices[i],
+                                                          input.anim[i],
+                                                          input.flip[i]);
+    }
+#endif
+#endif
+
+    return output;
+}
+
+// The color of the tile before the fog and light are applied.
+fn get_tile_color(input: VertexOutput) -> vec4<f32> {
+#ifdef PURE_COLOR
+    return input.color;
+#else
+    var color = vec4<f32>(0., 0., 0., 0.);
+
+#ifdef LAYER_STORAGE
+    let offset = u32(input.texture_indices.x);
+    let count = u32(input.texture_indices.y);
+    for (var i = 0u; i < count; i++) {
+        let layer = bevy_entitiles::common::tile_layers[offset + i];
+        let texture_index = resolve_texture_index(layer.texture_index, layer.anim_phase, layer.flip);
+        if texture_index < 0 {
+            continue;
+        }
+
+        color = blend_layer(color, sample_layer(texture_index, layer.flip, input.uv), i);
+    }
+#else
+    for (var i = 0u; i < 4u; i++) {
+        if input.texture_indices[i] < 0 {
+            continue;
+        }
+
+        color = blend_layer(color, sample_layer(input.texture_indices[i], input.flip[i], input.uv), i);
+    }
+#endif
+    return color * input.color;
+#endif
+}
+
+// The fog and light of the tile, which should be multiplied with the tile color.
+fn get_shade(input: VertexOutput) -> vec4<f32> {
+    return vec4<f32>(vec3<f32>(input.fog) * input.light, 1.);
+}
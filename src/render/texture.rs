@@ -4,44 +4,38 @@ use bevy::{
         query::Added,
         system::{Query, ResMut, Resource},
     },
+    math::Vec2,
     prelude::Image,
     render::{
         render_asset::RenderAssets,
-        render_resource::{AddressMode, SamplerDescriptor},
-        renderer::RenderDevice,
-        texture::GpuImage,
-    },
-    utils::HashMap,
-};
-
-#[cfg(not(feature = "atlas"))]
-use bevy::{
-    math::Vec2,
-    render::{
         render_resource::{
-            Extent3d, ImageCopyTexture, Origin3d, TextureAspect, TextureDescriptor,
-            TextureDimension, TextureFormat, TextureUsages, TextureViewDescriptor,
-            TextureViewDimension,
+            AddressMode, Extent3d, ImageCopyTexture, Origin3d, SamplerDescriptor, TextureAspect,
+            TextureDescriptor, TextureDimension, TextureFormat, TextureUsages,
+            TextureViewDescriptor, TextureViewDimension,
         },
-        renderer::RenderQueue,
+        renderer::{RenderDevice, RenderQueue},
+        texture::GpuImage,
     },
+    utils::HashMap,
 };
 
-use crate::tilemap::map::{TilemapTexture, TilemapTextureDescriptor};
+use crate::tilemap::map::{TilemapTexture, TilemapTextureMode};
 
+/// The textures are keyed by `TilemapTexture::get_render_handle`,
+/// so tilemaps that use the same set of texture slots share the gpu texture.
 #[derive(Resource, Default)]
 pub struct TilemapTexturesStorage {
     textures: HashMap<Handle<Image>, GpuImage>,
-    prepare_queue: HashMap<Handle<Image>, TilemapTextureDescriptor>,
-    queue_queue: HashMap<Handle<Image>, TilemapTextureDescriptor>,
+    prepare_queue: HashMap<Handle<Image>, TilemapTexture>,
+    queue_queue: HashMap<Handle<Image>, TilemapTexture>,
 }
 
 impl TilemapTexturesStorage {
-    pub fn insert(&mut self, handle: Handle<Image>, desc: &TilemapTextureDescriptor) {
-        #[cfg(not(feature = "atlas"))]
-        self.prepare_queue.insert(handle, desc.clone());
-        #[cfg(feature = "atlas")]
-        self.queue_queue.insert(handle, desc.clone());
+    pub fn insert(&mut self, handle: Handle<Image>, texture: &TilemapTexture) {
+        match texture.mode() {
+            TilemapTextureMode::Array => self.prepare_queue.insert(handle, texture.clone()),
+            TilemapTextureMode::Atlas => self.queue_queue.insert(handle, texture.clone()),
+        };
     }
 
     /// Try to get the processed texture array.
@@ -50,7 +44,6 @@ impl TilemapTexturesStorage {
     }
 
     /// Prepare the texture, creating the texture array and translate images in `queue_texture` function.
-    #[cfg(not(feature = "atlas"))]
     pub fn prepare_textures(&mut self, render_device: &RenderDevice) {
         if self.prepare_queue.is_empty() {
             return;
@@ -58,19 +51,23 @@ impl TilemapTexturesStorage {
 
         let to_prepare = self.prepare_queue.drain().collect::<Vec<_>>();
 
-        for (image_handle, desc) in to_prepare.iter() {
-            if image_handle.id() == Handle::<Image>::default().id() {
+        for (image_handle, tilemap_texture) in to_prepare.iter() {
+            if tilemap_texture.handle().id() == Handle::<Image>::default().id() {
                 continue;
             }
 
-            let tile_count = desc.size / desc.tile_size;
+            let desc = tilemap_texture.desc();
+            let tile_count = tilemap_texture
+                .iter_slots()
+                .map(|(_, desc)| desc.tile_count())
+                .sum::<u32>();
 
             let texture = render_device.create_texture(&TextureDescriptor {
                 label: Some("tilemap_texture_array"),
                 size: Extent3d {
                     width: desc.tile_size.x,
                     height: desc.tile_size.y,
-                    depth_or_array_layers: tile_count.x * tile_count.y,
+                    depth_or_array_layers: tile_count,
                 },
                 mip_level_count: 1,
                 sample_count: 1,
@@ -103,7 +100,7 @@ impl TilemapTexturesStorage {
                 base_mip_level: 0,
                 base_array_layer: 0,
                 mip_level_count: None,
-                array_layer_count: Some(tile_count.x * tile_count.y),
+                array_layer_count: Some(tile_count),
             });
 
             let gpu_image = GpuImage {
@@ -117,17 +114,16 @@ impl TilemapTexturesStorage {
 
             self.textures.insert(image_handle.clone_weak(), gpu_image);
             self.queue_queue
-                .insert(image_handle.clone_weak(), desc.clone());
+                .insert(image_handle.clone_weak(), tilemap_texture.clone());
         }
     }
 
-    /// Translate images to texture array.
-    #[cfg(not(feature = "atlas"))]
+    /// Translate images to texture arrays, or set up the atlases.
     pub fn queue_textures(
         &mut self,
         render_device: &RenderDevice,
         render_queue: &RenderQueue,
-        render_images: &RenderAssets<Image>,
+        render_images: &mut RenderAssets<Image>,
     ) {
         if self.queue_queue.is_empty() {
             return;
@@ -135,16 +131,50 @@ impl TilemapTexturesStorage {
 
         let to_queue = self.queue_queue.drain().collect::<Vec<_>>();
 
-        for (image_handle, desc) in to_queue.iter() {
-            let Some(raw_gpu_image) = render_images.get(image_handle) else {
-                self.queue_queue
-                    .insert(image_handle.clone_weak(), desc.clone());
-                continue;
+        for (image_handle, tilemap_texture) in to_queue.into_iter() {
+            let is_queued = match tilemap_texture.mode() {
+                TilemapTextureMode::Array => self.queue_array_texture(
+                    &image_handle,
+                    &tilemap_texture,
+                    render_device,
+                    render_queue,
+                    render_images,
+                ),
+                TilemapTextureMode::Atlas => self.queue_atlas_texture(
+                    &image_handle,
+                    &tilemap_texture,
+                    render_device,
+                    render_queue,
+                    render_images,
+                ),
             };
 
+            if !is_queued {
+                self.queue_queue.insert(image_handle, tilemap_texture);
+            }
+        }
+    }
+
+    /// Copy the tiles of all the slots to the texture array.
+    /// Returns false if the images are not ready yet.
+    fn queue_array_texture(
+        &mut self,
+        image_handle: &Handle<Image>,
+        tilemap_texture: &TilemapTexture,
+        render_device: &RenderDevice,
+        render_queue: &RenderQueue,
+        render_images: &RenderAssets<Image>,
+    ) -> bool {
+        let Some(raw_gpu_images) = Self::get_slot_images(tilemap_texture, render_images) else {
+            return false;
+        };
+
+        let array_gpu_image = self.textures.get(image_handle).unwrap();
+        let mut command_encoder = render_device.create_command_encoder(&Default::default());
+        let mut array_index = 0;
+
+        for ((_, desc), raw_gpu_image) in tilemap_texture.iter_slots().zip(raw_gpu_images) {
             let tile_count = desc.size / desc.tile_size;
-            let array_gpu_image = self.textures.get(image_handle).unwrap();
-            let mut command_encoder = render_device.create_command_encoder(&Default::default());
 
             for index_y in 0..tile_count.y {
                 for index_x in 0..tile_count.x {
@@ -165,7 +195,7 @@ impl TilemapTexturesStorage {
                             origin: Origin3d {
                                 x: 0,
                                 y: 0,
-                                z: index_x + index_y * tile_count.x,
+                                z: array_index,
                             },
                             aspect: TextureAspect::All,
                         },
@@ -175,51 +205,182 @@ impl TilemapTexturesStorage {
                             depth_or_array_layers: 1,
                         },
                     );
+                    array_index += 1;
                 }
             }
-
-            render_queue.submit(vec![command_encoder.finish()]);
         }
+
+        render_queue.submit(vec![command_encoder.finish()]);
+        true
     }
 
-    #[cfg(feature = "atlas")]
-    pub fn queue_textures(
+    /// Use the image as the atlas directly if there's only one slot.
+    /// Otherwise, copy the tiles of all the slots into a new atlas.
+    /// Returns false if the images are not ready yet.
+    fn queue_atlas_texture(
         &mut self,
+        image_handle: &Handle<Image>,
+        tilemap_texture: &TilemapTexture,
         render_device: &RenderDevice,
+        render_queue: &RenderQueue,
         render_images: &mut RenderAssets<Image>,
-    ) {
-        if self.queue_queue.is_empty() {
-            return;
+    ) -> bool {
+        if tilemap_texture.slot_count() > 1 {
+            let Some(raw_gpu_images) = Self::get_slot_images(tilemap_texture, render_images) else {
+                return false;
+            };
+
+            let combined = Self::combine_atlases(
+                tilemap_texture,
+                &raw_gpu_images,
+                render_device,
+                render_queue,
+            );
+            self.textures.insert(image_handle.clone_weak(), combined);
+            return true;
         }
 
-        let to_queue = self.queue_queue.drain().collect::<Vec<_>>();
+        let Some(texture) = render_images.get_mut(image_handle) else {
+            return false;
+        };
 
-        for (image_handle, desc) in to_queue.into_iter() {
-            let Some(texture) = render_images.get_mut(&image_handle) else {
-                self.queue_queue.insert(image_handle, desc);
-                continue;
-            };
+        let desc = tilemap_texture.desc();
+        let sampler = render_device.create_sampler(&SamplerDescriptor {
+            label: Some("tilemap_texture_atlas_sampler"),
+            address_mode_u: AddressMode::ClampToEdge,
+            address_mode_v: AddressMode::ClampToEdge,
+            address_mode_w: AddressMode::ClampToEdge,
+            mag_filter: desc.filter_mode,
+            min_filter: desc.filter_mode,
+            mipmap_filter: desc.filter_mode,
+            lod_min_clamp: 0.,
+            lod_max_clamp: f32::MAX,
+            compare: None,
+            anisotropy_clamp: 1,
+            border_color: None,
+        });
 
-            let sampler = render_device.create_sampler(&SamplerDescriptor {
-                label: Some("tilemap_texture_atlas_sampler"),
-                address_mode_u: AddressMode::ClampToEdge,
-                address_mode_v: AddressMode::ClampToEdge,
-                address_mode_w: AddressMode::ClampToEdge,
-                mag_filter: desc.filter_mode,
-                min_filter: desc.filter_mode,
-                mipmap_filter: desc.filter_mode,
-                lod_min_clamp: 0.,
-                lod_max_clamp: f32::MAX,
-                compare: None,
-                anisotropy_clamp: 1,
-                border_color: None,
-            });
+        texture.sampler = sampler;
+        self.textures
+            .insert(image_handle.clone_weak(), texture.clone());
+        true
+    }
+
+    fn combine_atlases(
+        tilemap_texture: &TilemapTexture,
+        raw_gpu_images: &[&GpuImage],
+        render_device: &RenderDevice,
+        render_queue: &RenderQueue,
+    ) -> GpuImage {
+        let desc = tilemap_texture.desc();
+        let combined_tiled_size = tilemap_texture.get_combined_tiled_size();
+        let size = combined_tiled_size * desc.tile_size;
+        let format = raw_gpu_images[0].texture_format;
+
+        let texture = render_device.create_texture(&TextureDescriptor {
+            label: Some("tilemap_combined_texture_atlas"),
+            size: Extent3d {
+                width: size.x,
+                height: size.y,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: TextureDimension::D2,
+            format,
+            usage: TextureUsages::COPY_DST | TextureUsages::TEXTURE_BINDING,
+            view_formats: &[],
+        });
+
+        let mut command_encoder = render_device.create_command_encoder(&Default::default());
+        let mut combined_index = 0;
+
+        for ((_, desc), raw_gpu_image) in tilemap_texture.iter_slots().zip(raw_gpu_images) {
+            let tile_count = desc.size / desc.tile_size;
+
+            for index_y in 0..tile_count.y {
+                for index_x in 0..tile_count.x {
+                    command_encoder.copy_texture_to_texture(
+                        ImageCopyTexture {
+                            texture: &raw_gpu_image.texture,
+                            mip_level: 0,
+                            origin: Origin3d {
+                                x: index_x * desc.tile_size.x,
+                                y: index_y * desc.tile_size.y,
+                                z: 0,
+                            },
+                            aspect: TextureAspect::All,
+                        },
+                        ImageCopyTexture {
+                            texture: &texture,
+                            mip_level: 0,
+                            origin: Origin3d {
+                                x: combined_index % combined_tiled_size.x * desc.tile_size.x,
+                                y: combined_index / combined_tiled_size.x * desc.tile_size.y,
+                                z: 0,
+                            },
+                            aspect: TextureAspect::All,
+                        },
+                        Extent3d {
+                            width: desc.tile_size.x,
+                            height: desc.tile_size.y,
+                            depth_or_array_layers: 1,
+                        },
+                    );
+                    combined_index += 1;
+                }
+            }
+        }
 
-            texture.sampler = sampler;
-            self.textures.insert(image_handle, texture.clone());
+        render_queue.submit(vec![command_encoder.finish()]);
+
+        let sampler = render_device.create_sampler(&SamplerDescriptor {
+            label: Some("tilemap_texture_atlas_sampler"),
+            address_mode_u: AddressMode::ClampToEdge,
+            address_mode_v: AddressMode::ClampToEdge,
+            address_mode_w: AddressMode::ClampToEdge,
+            mag_filter: desc.filter_mode,
+            min_filter: desc.filter_mode,
+            mipmap_filter: desc.filter_mode,
+            lod_min_clamp: 0.,
+            lod_max_clamp: f32::MAX,
+            compare: None,
+            anisotropy_clamp: 1,
+            border_color: None,
+        });
+
+        let texture_view = texture.create_view(&TextureViewDescriptor {
+            label: Some("tilemap_combined_texture_atlas_view"),
+            format: Some(format),
+            dimension: Some(TextureViewDimension::D2),
+            aspect: TextureAspect::All,
+            base_mip_level: 0,
+            base_array_layer: 0,
+            mip_level_count: None,
+            array_layer_count: None,
+        });
+
+        GpuImage {
+            texture_format: format,
+            mip_level_count: texture.mip_level_count(),
+            texture,
+            texture_view,
+            sampler,
+            size: size.as_vec2(),
         }
     }
 
+    /// Get the gpu images of all the slots, or `None` if any of them is not ready yet.
+    fn get_slot_images<'a>(
+        tilemap_texture: &TilemapTexture,
+        render_images: &'a RenderAssets<Image>,
+    ) -> Option<Vec<&'a GpuImage>> {
+        tilemap_texture
+            .iter_slots()
+            .map(|(handle, _)| render_images.get(handle))
+            .collect()
+    }
+
     pub fn contains(&self, handle: &Handle<Image>) -> bool {
         self.textures.contains_key(handle)
             || self.queue_queue.contains_key(handle)
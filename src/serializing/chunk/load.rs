@@ -16,13 +16,14 @@ use crate::{
     math::extension::ChunkIndex,
     serializing::{load_object, map::TilemapLayer},
     tilemap::{
-        buffers::TileBuilderBuffer,
+        buffers::{FogTileBuffer, TileBuilderBuffer},
+        fog::FogTilemap,
         map::{TilemapName, TilemapStorage},
         tile::Tile,
     },
 };
 
-use super::TILE_CHUNKS_FOLDER;
+use super::{FOG_TILE_CHUNKS_FOLDER, TILE_CHUNKS_FOLDER};
 
 #[cfg(feature = "algorithm")]
 use crate::{
@@ -144,6 +145,9 @@ pub fn load_color_layer(
                             index: chunk_origin + in_chunk_index,
                             texture: tile.texture,
                             color: tile.color,
+                            depth: tile.depth,
+                            render_size: tile.render_size,
+                            anchor: tile.anchor,
                         },
                     ));
                     entities[in_chunk_index_vec] = Some(e);
@@ -239,3 +243,34 @@ pub fn load_physics_layer(
         });
     });
 }
+
+pub fn load_fog_layer(
+    mut tilemaps_query: Query<(Entity, &TilemapName, &mut FogTilemap), With<ScheduledLoadChunks>>,
+    config: Res<ChunkLoadConfig>,
+    mut cache: ResMut<ChunkLoadCache>,
+) {
+    tilemaps_query.for_each_mut(|(entity, name, mut fog_tilemap)| {
+        let chunk_size = fog_tilemap.storage.chunk_size as i32;
+        (0..config.chunks_per_frame).into_iter().for_each(|_| {
+            let Some(chunk_index) = cache.pop_chunk(entity, TilemapLayer::FOG) else {
+                cache.0.get_mut(&entity).unwrap().remove(&TilemapLayer::FOG);
+                return;
+            };
+
+            let Ok(chunk) = load_object::<FogTileBuffer>(
+                &Path::new(&config.path)
+                    .join(&name.0)
+                    .join(FOG_TILE_CHUNKS_FOLDER),
+                format!("{}.ron", chunk_index.chunk_file_name()).as_str(),
+            ) else {
+                return;
+            };
+
+            let mut new_chunk = vec![None; (chunk_size * chunk_size) as usize];
+            chunk.tiles.into_iter().for_each(|(in_chunk_index, tile)| {
+                new_chunk[(in_chunk_index.y * chunk_size + in_chunk_index.x) as usize] = Some(tile);
+            });
+            fog_tilemap.set_chunk(chunk_index, new_chunk);
+        });
+    });
+}
@@ -18,6 +18,7 @@ pub mod save;
 pub const TILE_CHUNKS_FOLDER: &str = "tile_chunks";
 pub const PATH_TILE_CHUNKS_FOLDER: &str = "path_tile_chunks";
 pub const PHYSICS_TILE_CHUNKS_FOLDER: &str = "physics_tile_chunks";
+pub const FOG_TILE_CHUNKS_FOLDER: &str = "fog_tile_chunks";
 
 pub struct EntiTilesChunkSerializingPlugin;
 
@@ -31,12 +32,14 @@ impl Plugin for EntiTilesChunkSerializingPlugin {
                 save::save_path_layer,
                 #[cfg(feature = "physics")]
                 save::save_physics_layer,
+                save::save_fog_layer,
                 save::render_chunk_remover,
                 load::load_color_layer,
                 #[cfg(feature = "algorithm")]
                 load::load_path_layer,
                 #[cfg(feature = "physics")]
                 load::load_physics_layer,
+                load::load_fog_layer,
                 chunk_tag_remover,
             ),
         );
@@ -18,7 +18,8 @@ use crate::{
     render::chunk::{ChunkUnload, UnloadRenderChunk},
     serializing::{map::TilemapLayer, save_object},
     tilemap::{
-        buffers::TileBuilderBuffer,
+        buffers::{FogTileBuffer, TileBuilderBuffer},
+        fog::FogTilemap,
         map::{TilemapName, TilemapStorage},
         tile::Tile,
     },
@@ -35,7 +36,7 @@ use crate::{
     tilemap::{buffers::PackedPhysicsTileBuffer, physics::PhysicsTilemap},
 };
 
-use super::TILE_CHUNKS_FOLDER;
+use super::{FOG_TILE_CHUNKS_FOLDER, TILE_CHUNKS_FOLDER};
 
 #[derive(Component)]
 pub struct ScheduledSaveChunks;
@@ -308,3 +309,57 @@ pub fn save_physics_layer(
         });
     });
 }
+
+pub fn save_fog_layer(
+    mut tilemaps_query: Query<(Entity, &TilemapName, &mut FogTilemap), With<ScheduledSaveChunks>>,
+    config: Res<ChunkSaveConfig>,
+    mut cache: ResMut<ChunkSaveCache>,
+) {
+    tilemaps_query.for_each_mut(|(entity, name, mut fog_tilemap)| {
+        let map_path = Path::new(&config.path).join(&name.0);
+
+        (0..config.chunks_per_frame).into_iter().for_each(|_| {
+            let Some((chunk_index, remove_after_save)) = cache.pop_chunk(entity, TilemapLayer::FOG)
+            else {
+                cache.0.get_mut(&entity).unwrap().remove(&TilemapLayer::FOG);
+                return;
+            };
+
+            let Some(chunk) = fog_tilemap.storage.get_chunk(chunk_index) else {
+                return;
+            };
+
+            let tiles = chunk
+                .iter()
+                .enumerate()
+                .filter_map(|(index, tile)| {
+                    tile.map(|t| {
+                        (
+                            IVec2 {
+                                x: (index as u32 % fog_tilemap.storage.chunk_size) as i32,
+                                y: (index as u32 / fog_tilemap.storage.chunk_size) as i32,
+                            },
+                            t,
+                        )
+                    })
+                })
+                .collect();
+
+            save_object(
+                &map_path.join(FOG_TILE_CHUNKS_FOLDER),
+                format!("{}.ron", chunk_index.chunk_file_name()).as_str(),
+                &FogTileBuffer {
+                    tiles,
+                    aabb: IAabb2d {
+                        min: IVec2::ZERO,
+                        max: IVec2::splat(fog_tilemap.storage.chunk_size as i32 - 1),
+                    },
+                },
+            );
+
+            if remove_after_save {
+                fog_tilemap.remove_chunk(chunk_index);
+            }
+        });
+    });
+}
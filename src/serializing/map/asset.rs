@@ -0,0 +1,330 @@
+use std::fmt::Display;
+
+use bevy::{
+    asset::{
+        io::{AssetReaderError, Reader},
+        Asset, AssetEvent, AssetId, AssetLoader, Assets, AsyncReadExt, Handle, LoadContext,
+        ParseAssetPathError, ReadAssetBytesError,
+    },
+    ecs::{
+        bundle::Bundle,
+        entity::Entity,
+        event::EventReader,
+        query::Added,
+        system::{Commands, Query, Res},
+    },
+    log::warn,
+    math::IVec2,
+    reflect::TypePath,
+    utils::{BoxedFuture, HashSet},
+};
+use ron::error::SpannedError;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    math::extension::ChunkIndex,
+    serializing::{
+        chunk::{FOG_TILE_CHUNKS_FOLDER, TILE_CHUNKS_FOLDER},
+        pattern::TilemapPattern,
+    },
+    tilemap::{
+        buffers::{FogTileBuffer, TileBuilderBuffer},
+        chunking::storage::ChunkedStorage,
+        fog::FogTilemap,
+        map::TilemapStorage,
+    },
+};
+
+use super::{
+    load::SerializedTilemapFiles, SerializedTilemap, FOG_TILES, PATTERN_EXT, TILEMAP_EXT, TILES,
+};
+
+#[cfg(feature = "algorithm")]
+use crate::{
+    serializing::{chunk::PATH_TILE_CHUNKS_FOLDER, map::PATH_TILES},
+    tilemap::{algorithm::path::PathTilemap, buffers::PathTileBuffer},
+};
+
+#[cfg(feature = "physics")]
+use {
+    crate::{
+        serializing::{chunk::PHYSICS_TILE_CHUNKS_FOLDER, map::PHYSICS_TILES},
+        tilemap::{buffers::PackedPhysicsTileBuffer, physics::PhysicsTilemap},
+    },
+    bevy::hierarchy::DespawnRecursiveExt,
+};
+
+/// A tilemap or a pattern saved by the `TilemapSaver`.
+///
+/// A `.tilemap.ron` file is loaded as a tilemap, together with the files of the layers
+/// next to it and the chunks listed in [`SerializedTilemapAssetSettings`].
+/// A `.pattern.ron` file is loaded as a pattern.
+#[derive(Asset, TypePath)]
+pub enum SerializedTilemapAsset {
+    Tilemap(Box<SerializedTilemapFiles>),
+    Pattern(TilemapPattern),
+}
+
+/// Spawn this on an entity to turn it into the tilemap of the asset.
+/// The tilemap is spawned again when the files are changed.
+///
+/// Patterns are applied to the tilemap that's already on the entity, at the origin.
+#[derive(Bundle, Default)]
+pub struct SerializedTilemapAssetBundle {
+    pub asset: Handle<SerializedTilemapAsset>,
+}
+
+#[derive(Debug)]
+pub enum SerializedTilemapAssetError {
+    Io(std::io::Error),
+    Read(ReadAssetBytesError),
+    Ron(SpannedError),
+    Path(ParseAssetPathError),
+}
+
+impl Display for SerializedTilemapAssetError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Io(e) => write!(f, "Could not read the file! {}", e),
+            Self::Read(e) => write!(f, "Could not read the file! {}", e),
+            Self::Ron(e) => write!(f, "Could not parse the file! {}", e),
+            Self::Path(e) => write!(f, "Could not resolve the path of a layer! {}", e),
+        }
+    }
+}
+
+impl std::error::Error for SerializedTilemapAssetError {}
+
+impl From<std::io::Error> for SerializedTilemapAssetError {
+    fn from(value: std::io::Error) -> Self {
+        Self::Io(value)
+    }
+}
+
+impl From<ReadAssetBytesError> for SerializedTilemapAssetError {
+    fn from(value: ReadAssetBytesError) -> Self {
+        Self::Read(value)
+    }
+}
+
+impl From<SpannedError> for SerializedTilemapAssetError {
+    fn from(value: SpannedError) -> Self {
+        Self::Ron(value)
+    }
+}
+
+impl From<ParseAssetPathError> for SerializedTilemapAssetError {
+    fn from(value: ParseAssetPathError) -> Self {
+        Self::Path(value)
+    }
+}
+
+/// The settings of [`SerializedTilemapAssetLoader`].
+#[derive(Serialize, Deserialize, Default)]
+pub struct SerializedTilemapAssetSettings {
+    /// The chunks to read from the chunk folders saved using `ChunkSaveConfig`.
+    ///
+    /// The asset sources can't list the files in a folder,
+    /// so the indices of the chunks need to be given here.
+    /// Chunks that were not saved are skipped.
+    pub chunks: Vec<IVec2>,
+}
+
+#[derive(Default)]
+pub struct SerializedTilemapAssetLoader;
+
+impl AssetLoader for SerializedTilemapAssetLoader {
+    type Asset = SerializedTilemapAsset;
+    type Settings = SerializedTilemapAssetSettings;
+    type Error = SerializedTilemapAssetError;
+
+    fn load<'a>(
+        &'a self,
+        reader: &'a mut Reader,
+        settings: &'a Self::Settings,
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<Self::Asset, Self::Error>> {
+        Box::pin(async move {
+            let mut bytes = Vec::new();
+            reader.read_to_end(&mut bytes).await?;
+
+            if load_context
+                .asset_path()
+                .get_full_extension()
+                .is_some_and(|ext| ext.ends_with(PATTERN_EXT))
+            {
+                return Ok(SerializedTilemapAsset::Pattern(ron::de::from_bytes(
+                    &bytes,
+                )?));
+            }
+
+            let tilemap = ron::de::from_bytes::<SerializedTilemap>(&bytes)?;
+            let texture = tilemap
+                .texture
+                .as_ref()
+                .map(|tex| tex.to_texture(|path| load_context.load(path.to_string())));
+
+            let mut files = SerializedTilemapFiles {
+                texture,
+                tiles: read_layer(load_context, TILES).await?,
+                fog_tiles: read_layer(load_context, FOG_TILES).await?,
+                #[cfg(feature = "algorithm")]
+                path_tiles: read_layer(load_context, PATH_TILES).await?,
+                #[cfg(feature = "physics")]
+                physics_tiles: read_layer(load_context, PHYSICS_TILES).await?,
+                tilemap,
+            };
+
+            for chunk_index in settings.chunks.iter().copied() {
+                read_chunks(load_context, &mut files, chunk_index).await?;
+            }
+
+            Ok(SerializedTilemapAsset::Tilemap(Box::new(files)))
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &[TILEMAP_EXT, PATTERN_EXT]
+    }
+}
+
+/// Read the chunk at `chunk_index` of every layer from the chunk folders
+/// and put the tiles into the layers of `files`.
+async fn read_chunks(
+    load_context: &mut LoadContext<'_>,
+    files: &mut SerializedTilemapFiles,
+    chunk_index: IVec2,
+) -> Result<(), SerializedTilemapAssetError> {
+    let chunk_size = files.tilemap.chunk_size;
+    let file_name = format!("{}.ron", chunk_index.chunk_file_name());
+    let to_index = |index: IVec2| (index.y * chunk_size as i32 + index.x) as usize;
+
+    let path = format!("{}/{}", TILE_CHUNKS_FOLDER, file_name);
+    if let Some(chunk) = read_layer::<TileBuilderBuffer>(load_context, &path).await? {
+        let tiles = files
+            .tiles
+            .get_or_insert_with(|| ChunkedStorage::new(chunk_size));
+        chunk.tiles.into_iter().for_each(|(index, tile)| {
+            tiles.set_elem_precise(chunk_index, to_index(index), tile);
+        });
+    }
+
+    let path = format!("{}/{}", FOG_TILE_CHUNKS_FOLDER, file_name);
+    if let Some(chunk) = read_layer::<FogTileBuffer>(load_context, &path).await? {
+        let mut new_chunk = vec![None; (chunk_size * chunk_size) as usize];
+        chunk.tiles.into_iter().for_each(|(index, tile)| {
+            new_chunk[to_index(index)] = Some(tile);
+        });
+        files
+            .fog_tiles
+            .get_or_insert_with(|| FogTilemap::new_with_chunk_size(chunk_size))
+            .set_chunk(chunk_index, new_chunk);
+    }
+
+    #[cfg(feature = "algorithm")]
+    {
+        let path = format!("{}/{}", PATH_TILE_CHUNKS_FOLDER, file_name);
+        if let Some(chunk) = read_layer::<PathTileBuffer>(load_context, &path).await? {
+            let path_tiles = files
+                .path_tiles
+                .get_or_insert_with(|| ChunkedStorage::new(chunk_size));
+            chunk.tiles.into_iter().for_each(|(index, tile)| {
+                path_tiles.set_elem_precise(chunk_index, to_index(index), tile);
+            });
+        }
+    }
+
+    #[cfg(feature = "physics")]
+    {
+        let path = format!("{}/{}", PHYSICS_TILE_CHUNKS_FOLDER, file_name);
+        if let Some(chunk) = read_layer::<PackedPhysicsTileBuffer>(load_context, &path).await? {
+            let physics_tiles = files
+                .physics_tiles
+                .get_or_insert_with(|| ChunkedStorage::new(chunk_size));
+            chunk.tiles.into_iter().for_each(|(index, tile)| {
+                physics_tiles.set_elem_precise(chunk_index, to_index(index), tile);
+            });
+        }
+    }
+
+    Ok(())
+}
+
+/// Read a file relative to the folder of the `.tilemap.ron`. Files that were not saved are `None`.
+async fn read_layer<T: for<'a> Deserialize<'a>>(
+    load_context: &mut LoadContext<'_>,
+    file_name: &str,
+) -> Result<Option<T>, SerializedTilemapAssetError> {
+    let path = load_context.asset_path().resolve_embed(file_name)?;
+
+    match load_context.read_asset_bytes(path).await {
+        Ok(bytes) => Ok(Some(ron::de::from_bytes(&bytes)?)),
+        Err(ReadAssetBytesError::AssetReaderError(AssetReaderError::NotFound(_))) => Ok(None),
+        Err(err) => Err(err.into()),
+    }
+}
+
+pub fn spawn_tilemap_assets(
+    mut commands: Commands,
+    mut asset_events: EventReader<AssetEvent<SerializedTilemapAsset>>,
+    assets: Res<Assets<SerializedTilemapAsset>>,
+    new_tilemaps_query: Query<Entity, Added<Handle<SerializedTilemapAsset>>>,
+    mut tilemaps_query: Query<(
+        Entity,
+        &Handle<SerializedTilemapAsset>,
+        Option<&mut TilemapStorage>,
+    )>,
+    #[cfg(feature = "algorithm")] mut path_tilemaps_query: Query<&mut PathTilemap>,
+    #[cfg(feature = "physics")] physics_tilemaps_query: Query<&PhysicsTilemap>,
+) {
+    let changed = asset_events
+        .read()
+        .filter_map(|event| match event {
+            AssetEvent::LoadedWithDependencies { id } | AssetEvent::Modified { id } => Some(*id),
+            _ => None,
+        })
+        .collect::<HashSet<AssetId<SerializedTilemapAsset>>>();
+
+    for (entity, handle, storage) in tilemaps_query.iter_mut() {
+        if !changed.contains(&handle.id()) && !new_tilemaps_query.contains(entity) {
+            continue;
+        }
+        let Some(asset) = assets.get(handle) else {
+            continue;
+        };
+
+        match asset {
+            SerializedTilemapAsset::Tilemap(files) => {
+                if let Some(mut storage) = storage {
+                    storage.remove_all(&mut commands);
+                }
+                #[cfg(feature = "physics")]
+                if let Ok(physics_tilemap) = physics_tilemaps_query.get(entity) {
+                    physics_tilemap
+                        .storage
+                        .chunks
+                        .values()
+                        .flat_map(|chunk| chunk.iter().filter_map(|e| *e))
+                        .for_each(|e| commands.entity(e).despawn_recursive());
+                }
+
+                files.spawn(&mut commands, entity);
+            }
+            SerializedTilemapAsset::Pattern(pattern) => {
+                let Some(mut storage) = storage else {
+                    warn!("Patterns can only be spawned on tilemaps! Skipping the pattern.");
+                    continue;
+                };
+
+                storage.remove_all(&mut commands);
+                storage.fill_with_buffer(&mut commands, IVec2::ZERO, pattern.tiles.clone());
+
+                #[cfg(feature = "algorithm")]
+                if let Ok(mut path_tilemap) = path_tilemaps_query.get_mut(entity) {
+                    path_tilemap.storage = ChunkedStorage::new(storage.storage.chunk_size);
+                    path_tilemap.fill_with_buffer(IVec2::ZERO, pattern.path_tiles.clone());
+                }
+            }
+        }
+    }
+}
@@ -3,7 +3,6 @@ use std::path::Path;
 use bevy::{
     asset::AssetServer,
     ecs::{
-        bundle::Bundle,
         component::Component,
         entity::Entity,
         system::{Commands, Query, Res},
@@ -11,16 +10,20 @@ use bevy::{
     hierarchy::DespawnRecursiveExt,
 };
 
+use ron::error::SpannedError;
+use serde::Deserialize;
+
 use crate::{
     serializing::load_object,
     tilemap::{
         chunking::storage::{ChunkedStorage, TileBuilderChunkedStorage},
+        fog::FogTilemap,
         map::{TilemapStorage, TilemapTexture},
         tile::Tile,
     },
 };
 
-use super::{SerializedTilemap, TilemapLayer, TILEMAP_META, TILES};
+use super::{SerializedTilemap, TilemapLayer, FOG_TILES, LEGACY_TILEMAP_META, TILEMAP_META, TILES};
 
 #[cfg(feature = "algorithm")]
 use crate::{
@@ -42,10 +45,13 @@ pub struct TilemapLoader {
     /// C
     /// └── maps
     ///     └── beautiful map
-    ///         ├── tilemap.ron
+    ///         ├── map.tilemap.ron
     ///         └── (and other data)
     /// ```
     /// Then path = `C:\\maps` and map_name = `beautiful map`
+    ///
+    /// Tilemaps saved before the meta file was renamed to `map.tilemap.ron`
+    /// are loaded from `tilemap.ron`.
     pub path: String,
     pub map_name: String,
     pub layers: TilemapLayer,
@@ -59,28 +65,71 @@ pub fn load(
     for (entity, loader) in tilemaps_query.iter() {
         let map_path = Path::new(&loader.path).join(&loader.map_name);
 
-        let Ok(ser_tilemap) = load_object::<SerializedTilemap>(&map_path, TILEMAP_META) else {
-            complete(&mut commands, entity, (), false);
+        let Ok(files) = load_files(&map_path, loader.layers, &asset_server) else {
+            commands.entity(entity).despawn_recursive();
             continue;
         };
 
-        let texture = if let Some(tex) = &ser_tilemap.texture {
-            Some(TilemapTexture {
-                texture: asset_server.load(tex.path.clone()),
-                desc: tex.desc.clone().into(),
-                rotation: tex.rotation,
-            })
-        } else {
-            None
-        };
+        commands.entity(entity).remove::<TilemapLoader>();
+        files.spawn(&mut commands, entity);
+    }
+}
 
-        // texture
-        let ser_tiles = if loader.layers.contains(TilemapLayer::COLOR) {
-            Some(load_object::<TileBuilderChunkedStorage>(&map_path, TILES))
-        } else {
-            None
-        };
+fn load_files(
+    map_path: &Path,
+    layers: TilemapLayer,
+    asset_server: &AssetServer,
+) -> Result<SerializedTilemapFiles, SpannedError> {
+    let tilemap = if map_path.join(TILEMAP_META).exists() {
+        load_object::<SerializedTilemap>(map_path, TILEMAP_META)?
+    } else {
+        load_object::<SerializedTilemap>(map_path, LEGACY_TILEMAP_META)?
+    };
+
+    Ok(SerializedTilemapFiles {
+        texture: tilemap
+            .texture
+            .as_ref()
+            .map(|tex| tex.to_texture(|path| asset_server.load(path.to_string()))),
+        tiles: load_layer(map_path, layers, TilemapLayer::COLOR, TILES)?,
+        fog_tiles: load_layer(map_path, layers, TilemapLayer::FOG, FOG_TILES)?,
+        #[cfg(feature = "algorithm")]
+        path_tiles: load_layer(map_path, layers, TilemapLayer::PATH, PATH_TILES)?,
+        #[cfg(feature = "physics")]
+        physics_tiles: load_layer(map_path, layers, TilemapLayer::PHYSICS, PHYSICS_TILES)?,
+        tilemap,
+    })
+}
 
+fn load_layer<T: for<'a> Deserialize<'a>>(
+    map_path: &Path,
+    layers: TilemapLayer,
+    layer: TilemapLayer,
+    file_name: &str,
+) -> Result<Option<T>, SpannedError> {
+    if layers.contains(layer) {
+        load_object(map_path, file_name).map(Some)
+    } else {
+        Ok(None)
+    }
+}
+
+/// The data of a saved tilemap, read from the `map.tilemap.ron` and the files of the layers.
+pub struct SerializedTilemapFiles {
+    pub tilemap: SerializedTilemap,
+    pub texture: Option<TilemapTexture>,
+    pub tiles: Option<TileBuilderChunkedStorage>,
+    pub fog_tiles: Option<FogTilemap>,
+    #[cfg(feature = "algorithm")]
+    pub path_tiles: Option<PathTileChunkedStorage>,
+    #[cfg(feature = "physics")]
+    pub physics_tiles: Option<PackedPhysicsTileChunkedStorage>,
+}
+
+impl SerializedTilemapFiles {
+    /// Spawn the tiles and insert the tilemap components on the entity.
+    pub fn spawn(&self, commands: &mut Commands, entity: Entity) {
+        let ser_tilemap = &self.tilemap;
         let mut storage = TilemapStorage {
             tilemap: entity,
             storage: ChunkedStorage::new(ser_tilemap.chunk_size),
@@ -88,12 +137,7 @@ pub fn load(
         };
 
         // color
-        if let Some(ser_tiles) = ser_tiles {
-            let Ok(ser_tiles) = ser_tiles else {
-                complete(&mut commands, entity, (), false);
-                continue;
-            };
-
+        if let Some(ser_tiles) = &self.tiles {
             let mut bundles = Vec::new();
             ser_tiles
                 .chunked_iter_some()
@@ -113,46 +157,47 @@ pub fn load(
                                 .inverse_transform_index(chunk_index, in_chunk_index),
                             texture: tile.texture.clone(),
                             color: tile.color,
+                            depth: tile.depth,
+                            render_size: tile.render_size,
+                            anchor: tile.anchor,
                         },
                     ));
                 });
             commands.insert_or_spawn_batch(bundles);
         }
 
-        if let Some(tex) = texture {
+        if let Some(tilemap_layers) = ser_tilemap.tilemap_layers.clone() {
+            commands.entity(entity).insert(tilemap_layers);
+        }
+
+        if let Some(tex) = self.texture.clone() {
             let mut bundle = ser_tilemap.into_tilemap(entity, tex);
             bundle.storage = storage;
-            complete(&mut commands, entity, bundle, true);
+            commands.entity(entity).insert(bundle);
         } else {
             let mut bundle = ser_tilemap.into_pure_color_tilemap(entity);
             bundle.storage = storage;
-            complete(&mut commands, entity, bundle, true);
+            commands.entity(entity).insert(bundle);
+        }
+
+        // fog
+        if let Some(fog_tilemap) = &self.fog_tiles {
+            let mut fog_tilemap = fog_tilemap.clone();
+            fog_tilemap.mark_all_dirty();
+            commands.entity(entity).insert(fog_tilemap);
         }
 
         // algorithm
         #[cfg(feature = "algorithm")]
-        if loader.layers.contains(TilemapLayer::PATH) {
-            let Ok(path_storage) = load_object::<PathTileChunkedStorage>(&map_path, PATH_TILES)
-            else {
-                complete(&mut commands, entity, (), false);
-                continue;
-            };
-
+        if let Some(path_storage) = &self.path_tiles {
             commands.entity(entity).insert(PathTilemap {
-                storage: path_storage,
+                storage: path_storage.clone(),
             });
         }
 
         // physics
         #[cfg(feature = "physics")]
-        if loader.layers.contains(TilemapLayer::PHYSICS) {
-            let Ok(physics_tiles) =
-                load_object::<PackedPhysicsTileChunkedStorage>(&map_path, PHYSICS_TILES)
-            else {
-                complete(&mut commands, entity, (), false);
-                continue;
-            };
-
+        if let Some(physics_tiles) = &self.physics_tiles {
             let mut physics_storage = ChunkedStorage::new(ser_tilemap.chunk_size);
 
             physics_tiles
@@ -161,24 +206,15 @@ pub fn load(
                     physics_storage.set_elem_precise(
                         chunk_index,
                         in_chunk_index,
-                        tile.spawn(&mut commands, ser_tilemap.ty),
+                        tile.spawn(commands, ser_tilemap.ty),
                     );
                 });
 
             commands.entity(entity).insert(PhysicsTilemap {
                 storage: physics_storage,
                 spawn_queue: Vec::new(),
-                data: physics_tiles,
+                data: physics_tiles.clone(),
             });
         }
     }
 }
-
-fn complete(commands: &mut Commands, entity: Entity, bundle: impl Bundle, is_success: bool) {
-    if is_success {
-        commands.entity(entity).remove::<TilemapLoader>();
-        commands.entity(entity).insert(bundle);
-    } else {
-        commands.entity(entity).despawn_recursive();
-    }
-}
@@ -1,8 +1,10 @@
 use bevy::{
     app::{App, Plugin, Update},
+    asset::{AssetApp, Handle},
     ecs::entity::Entity,
+    log::warn,
     math::UVec2,
-    render::render_resource::FilterMode,
+    render::{render_resource::FilterMode, texture::Image},
 };
 use serde::{Deserialize, Serialize};
 
@@ -10,20 +12,27 @@ use crate::tilemap::{
     bundles::{PureColorTilemapBundle, TilemapBundle},
     chunking::storage::ChunkedStorage,
     map::{
-        TilePivot, TileRenderSize, TilemapAnimations, TilemapLayerOpacities, TilemapName,
-        TilemapRotation, TilemapSlotSize, TilemapStorage, TilemapTexture, TilemapTextureDescriptor,
-        TilemapTransform, TilemapType,
+        TilePivot, TileRenderSize, TilemapAnimations, TilemapLayerOpacities, TilemapLayers,
+        TilemapName, TilemapRotation, TilemapSlotSize, TilemapStorage, TilemapTexture,
+        TilemapTextureDescriptor, TilemapTextureMode, TilemapTransform, TilemapType,
     },
     tile::TileBuilder,
 };
 
 use self::save::TilemapSaver;
 
-pub const TILEMAP_META: &str = "tilemap.ron";
+pub const TILEMAP_META: &str = "map.tilemap.ron";
+/// The name of the meta file before the asset loader was added.
+/// Tilemaps saved with it can still be loaded by the `TilemapLoader`.
+pub const LEGACY_TILEMAP_META: &str = "tilemap.ron";
 pub const TILES: &str = "tiles.ron";
 pub const PATH_TILES: &str = "path_tiles.ron";
 pub const PHYSICS_TILES: &str = "physics_tiles.ron";
+pub const FOG_TILES: &str = "fog_tiles.ron";
+pub const TILEMAP_EXT: &str = "tilemap.ron";
+pub const PATTERN_EXT: &str = "pattern.ron";
 
+pub mod asset;
 pub mod load;
 pub mod save;
 
@@ -31,7 +40,12 @@ pub struct EntiTilesTilemapSerializingPlugin;
 
 impl Plugin for EntiTilesTilemapSerializingPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(Update, (save::save, load::load));
+        app.add_systems(
+            Update,
+            (save::save, load::load, asset::spawn_tilemap_assets),
+        )
+        .init_asset::<asset::SerializedTilemapAsset>()
+        .init_asset_loader::<asset::SerializedTilemapAssetLoader>();
     }
 }
 
@@ -49,6 +63,8 @@ pub struct SerializedTilemap {
     pub ty: TilemapType,
     pub tile_pivot: TilePivot,
     pub layer_opacities: TilemapLayerOpacities,
+    #[serde(default)]
+    pub tilemap_layers: Option<TilemapLayers>,
     pub tilemap_transform: TilemapTransform,
     pub texture: Option<SerializedTilemapTexture>,
     pub animations: Option<TilemapAnimations>,
@@ -64,6 +80,7 @@ impl SerializedTilemap {
         ty: TilemapType,
         tile_pivot: TilePivot,
         layer_opacities: TilemapLayerOpacities,
+        tilemap_layers: Option<TilemapLayers>,
         storage: TilemapStorage,
         tilemap_transform: TilemapTransform,
         texture: Option<TilemapTexture>,
@@ -77,13 +94,30 @@ impl SerializedTilemap {
             slot_size,
             tile_pivot,
             texture: texture.and_then(|tex| {
+                if saver.extra_texture_paths.len() != tex.extra_textures.len() {
+                    warn!(
+                        "Tilemap {} has {} extra textures but {} extra texture paths are given! \
+                        The slots without paths won't be saved.",
+                        name.0,
+                        tex.extra_textures.len(),
+                        saver.extra_texture_paths.len()
+                    );
+                }
+
                 Some(SerializedTilemapTexture {
                     path: saver.texture_path.clone().unwrap(),
                     desc: tex.desc.into(),
                     rotation: tex.rotation,
+                    extra_textures: saver
+                        .extra_texture_paths
+                        .iter()
+                        .cloned()
+                        .zip(tex.extra_textures.into_iter().map(|(_, desc)| desc.into()))
+                        .collect(),
                 })
             }),
             layer_opacities,
+            tilemap_layers,
             tilemap_transform,
             layers: saver.layers,
             animations,
@@ -135,6 +169,20 @@ pub struct SerializedTilemapTexture {
     pub path: String,
     pub desc: SerializedTilemapTextureDescriptor,
     pub rotation: TilemapRotation,
+    #[serde(default)]
+    pub extra_textures: Vec<(String, SerializedTilemapTextureDescriptor)>,
+}
+
+impl SerializedTilemapTexture {
+    /// Create the texture, loading the images with the given function.
+    pub fn to_texture(&self, mut load: impl FnMut(&str) -> Handle<Image>) -> TilemapTexture {
+        let mut texture =
+            TilemapTexture::new(load(&self.path), self.desc.clone().into(), self.rotation);
+        self.extra_textures.iter().for_each(|(path, desc)| {
+            texture.add_texture(load(path), desc.clone().into());
+        });
+        texture
+    }
 }
 
 #[derive(Serialize, Deserialize, Clone)]
@@ -142,6 +190,8 @@ pub struct SerializedTilemapTextureDescriptor {
     pub size: UVec2,
     pub tile_size: UVec2,
     pub filter_mode: SerializedFilterMode,
+    #[serde(default)]
+    pub mode: TilemapTextureMode,
 }
 
 impl From<TilemapTextureDescriptor> for SerializedTilemapTextureDescriptor {
@@ -150,6 +200,7 @@ impl From<TilemapTextureDescriptor> for SerializedTilemapTextureDescriptor {
             size: value.size,
             tile_size: value.tile_size,
             filter_mode: value.filter_mode.into(),
+            mode: value.mode,
         }
     }
 }
@@ -160,6 +211,7 @@ impl Into<TilemapTextureDescriptor> for SerializedTilemapTextureDescriptor {
             size: self.size,
             tile_size: self.tile_size,
             filter_mode: self.filter_mode.into(),
+            mode: self.mode,
         }
     }
 }
@@ -194,5 +246,6 @@ bitflags::bitflags! {
         const COLOR = 1;
         const PATH = 1 << 1;
         const PHYSICS = 1 << 2;
+        const FOG = 1 << 3;
     }
 }
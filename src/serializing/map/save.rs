@@ -14,15 +14,19 @@ use crate::{
     tilemap::{
         chunking::storage::ChunkedStorage,
         despawn::DespawnMe,
+        fog::FogTilemap,
         map::{
-            TilePivot, TileRenderSize, TilemapAnimations, TilemapLayerOpacities, TilemapName,
-            TilemapSlotSize, TilemapStorage, TilemapTexture, TilemapTransform, TilemapType,
+            TilePivot, TileRenderSize, TilemapAnimations, TilemapLayerOpacities, TilemapLayers,
+            TilemapName, TilemapSlotSize, TilemapStorage, TilemapTexture, TilemapTransform,
+            TilemapType,
         },
         tile::{Tile, TileBuilder},
     },
 };
 
-use super::{SerializedTilemap, TilemapLayer, PHYSICS_TILES, TILEMAP_META, TILES};
+use super::{
+    SerializedTilemap, TilemapLayer, FOG_TILES, PATTERN_EXT, PHYSICS_TILES, TILEMAP_META, TILES,
+};
 
 #[cfg(feature = "algorithm")]
 use super::PATH_TILES;
@@ -40,7 +44,7 @@ pub struct TilemapSaver {
     /// C
     /// └── maps
     ///     └── (your tilemap's name)
-    ///         ├── tilemap.ron
+    ///         ├── map.tilemap.ron
     ///         └── (and other data)
     /// ```
     ///
@@ -48,12 +52,15 @@ pub struct TilemapSaver {
     /// ```
     /// C
     /// └── maps
-    ///     └── (your tilemap's name).pattern
+    ///     └── (your tilemap's name).pattern.ron
     /// ```
     pub path: String,
     pub mode: TilemapSaverMode,
     pub layers: TilemapLayer,
     pub texture_path: Option<String>,
+    /// The paths of the textures in the slots after the first one.
+    /// There should be one for each texture added using `TilemapTexture::add_texture`.
+    pub extra_texture_paths: Vec<String>,
     pub remove_after_save: bool,
 }
 
@@ -67,6 +74,7 @@ pub fn save(
         &TilemapType,
         &TilePivot,
         &TilemapLayerOpacities,
+        Option<&TilemapLayers>,
         &mut TilemapStorage,
         &TilemapTransform,
         Option<&TilemapTexture>,
@@ -74,6 +82,7 @@ pub fn save(
         &TilemapSaver,
     )>,
     tiles_query: Query<&Tile>,
+    fog_tilemaps_query: Query<&FogTilemap>,
     #[cfg(feature = "algorithm")] path_tilemaps_query: Query<
         &crate::tilemap::algorithm::path::PathTilemap,
     >,
@@ -89,6 +98,7 @@ pub fn save(
         ty,
         tile_pivot,
         layer_opacities,
+        tilemap_layers,
         mut storage,
         transform,
         texture,
@@ -107,6 +117,7 @@ pub fn save(
                 *ty,
                 *tile_pivot,
                 *layer_opacities,
+                tilemap_layers.cloned(),
                 storage.clone(),
                 transform.clone(),
                 texture.cloned(),
@@ -183,8 +194,19 @@ pub fn save(
             }
         }
 
+        // fog, patterns don't have fog so only saved in `TilemapSaverMode::Tilemap`
+        if saver.layers.contains(TilemapLayer::FOG) && saver.mode == TilemapSaverMode::Tilemap {
+            if let Ok(fog_tilemap) = fog_tilemaps_query.get(entity) {
+                save_object(&map_path, FOG_TILES, fog_tilemap);
+            }
+        }
+
         if saver.mode == TilemapSaverMode::MapPattern {
-            save_object(map_dir, format!("{}.ron", name.0).as_str(), &pattern);
+            save_object(
+                map_dir,
+                format!("{}.{}", name.0, PATTERN_EXT).as_str(),
+                &pattern,
+            );
         }
 
         if saver.remove_after_save {
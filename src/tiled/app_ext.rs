@@ -0,0 +1,24 @@
+use bevy::{app::App, ecs::bundle::Bundle};
+
+use super::traits::{PhantomTiledObject, TiledObject, TiledObjectRegistry};
+
+pub trait TiledAppExt {
+    /// Spawn the objects with this class as `T`.
+    fn register_tiled_object<T: TiledObject + Bundle>(&mut self, class: &str) -> &mut App;
+}
+
+impl TiledAppExt for App {
+    fn register_tiled_object<T: TiledObject + Bundle>(&mut self, class: &str) -> &mut App {
+        match self.world.get_resource_mut::<TiledObjectRegistry>() {
+            Some(mut mapper) => {
+                mapper.insert(class.to_string(), Box::new(PhantomTiledObject::<T>::new()));
+            }
+            None => {
+                self.world.insert_resource(TiledObjectRegistry::default());
+                self.register_tiled_object::<T>(class);
+            }
+        }
+
+        self
+    }
+}
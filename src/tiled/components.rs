@@ -0,0 +1,64 @@
+use bevy::{
+    ecs::{component::Component, entity::Entity, system::Commands},
+    math::{IVec2, Vec2},
+    reflect::Reflect,
+    utils::HashMap,
+};
+
+use super::map::{TiledProperties, TiledTileInfo};
+
+#[derive(Component, Reflect, Default)]
+pub struct TiledLoader {
+    pub(crate) map: String,
+    pub(crate) trans_ovrd: Option<Vec2>,
+}
+
+#[derive(Component, Reflect, Default)]
+pub struct TiledUnloader;
+
+#[derive(Component)]
+pub struct TiledUnloadLayer;
+
+#[derive(Component, Reflect)]
+pub struct TiledLoadedMap {
+    pub name: String,
+    /// The tilemaps of the tile layers and the sprites of the image layers.
+    pub layers: Vec<Entity>,
+    /// Object id to entity.
+    pub objects: HashMap<u32, Entity>,
+}
+
+impl TiledLoadedMap {
+    pub fn unload(&self, commands: &mut Commands) {
+        self.layers.iter().for_each(|e| {
+            commands.entity(*e).insert(TiledUnloadLayer);
+        });
+        self.objects.values().for_each(|e| {
+            commands.entity(*e).despawn();
+        });
+    }
+}
+
+/// Added to the tilemaps and the sprites spawned from a Tiled layer.
+#[derive(Component, Debug, Clone, Reflect)]
+pub struct TiledLayerInfo {
+    pub id: u32,
+    pub name: String,
+    pub class: String,
+    #[reflect(ignore)]
+    pub properties: TiledProperties,
+}
+
+/// The classes and properties of the tiles in a tilemap, if any of them has one.
+#[derive(Component, Debug, Default, Clone, Reflect)]
+pub struct TiledTileProperties(#[reflect(ignore)] pub(crate) HashMap<IVec2, TiledTileInfo>);
+
+impl TiledTileProperties {
+    #[inline]
+    pub fn get(&self, index: IVec2) -> Option<&TiledTileInfo> {
+        self.0.get(&index)
+    }
+}
+
+#[derive(Component, Debug, Reflect, Hash, Eq, PartialEq, Clone, Copy)]
+pub struct TiledObjectId(pub u32);
@@ -0,0 +1,9 @@
+use bevy::ecs::event::Event;
+
+#[derive(Event, Debug, Clone)]
+pub enum TiledEvent {
+    /// The name of the map that was loaded.
+    MapLoaded(String),
+    /// The name of the map that was unloaded.
+    MapUnloaded(String),
+}
@@ -0,0 +1,335 @@
+use std::path::Path;
+
+use bevy::{
+    log::warn,
+    math::{IVec2, UVec2, Vec2, Vec4},
+    utils::HashMap,
+};
+use serde_json::Value;
+
+use super::{
+    map::{
+        TiledError, TiledLayer, TiledLayerContent, TiledMap, TiledObjectInstance, TiledObjectShape,
+        TiledOrientation, TiledProperties, TiledProperty, TiledStaggerAxis, TiledStaggerIndex,
+        TiledTileInfo, TiledTileset,
+    },
+    parse::{collect_tiles, decode_tile_data, parse_color, resolve_path, TiledReader},
+};
+
+pub fn parse_map(reader: &TiledReader, base: &Path, source: &str) -> Result<TiledMap, TiledError> {
+    let root = serde_json::from_str::<Value>(source)?;
+
+    let orientation = match str(&root, "orientation")? {
+        "orthogonal" => TiledOrientation::Orthogonal,
+        "isometric" => TiledOrientation::Isometric,
+        "staggered" => TiledOrientation::Staggered,
+        "hexagonal" => TiledOrientation::Hexagonal,
+        other => return Err(TiledError::InvalidValue("orientation", other.to_string())),
+    };
+
+    let mut tilesets = array(&root, "tilesets")
+        .map(|tileset| {
+            let first_gid = uint(tileset, "firstgid")?;
+            match tileset.get("source").and_then(Value::as_str) {
+                Some(source) => reader.read_tileset(&resolve_path(base, source), first_gid),
+                None => parse_tileset(reader, base, tileset, first_gid),
+            }
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    tilesets.sort_by_key(|tileset| tileset.first_gid);
+
+    Ok(TiledMap {
+        orientation,
+        size: UVec2::new(uint(&root, "width")?, uint(&root, "height")?),
+        tile_size: UVec2::new(uint(&root, "tilewidth")?, uint(&root, "tileheight")?),
+        infinite: root["infinite"].as_bool().unwrap_or_default(),
+        hex_side_length: root["hexsidelength"].as_u64().unwrap_or_default() as u32,
+        stagger_axis: match root["staggeraxis"].as_str() {
+            Some("x") => TiledStaggerAxis::X,
+            _ => TiledStaggerAxis::Y,
+        },
+        stagger_index: match root["staggerindex"].as_str() {
+            Some("even") => TiledStaggerIndex::Even,
+            _ => TiledStaggerIndex::Odd,
+        },
+        background_color: root["backgroundcolor"]
+            .as_str()
+            .map(parse_color)
+            .transpose()?,
+        tilesets,
+        layers: parse_layers(reader, base, &root)?,
+        properties: parse_properties(&root)?,
+    })
+}
+
+pub fn parse_tileset_file(
+    reader: &TiledReader,
+    base: &Path,
+    source: &str,
+    first_gid: u32,
+) -> Result<TiledTileset, TiledError> {
+    let root = serde_json::from_str::<Value>(source)?;
+    parse_tileset(reader, base, &root, first_gid)
+}
+
+fn parse_tileset(
+    reader: &TiledReader,
+    base: &Path,
+    value: &Value,
+    first_gid: u32,
+) -> Result<TiledTileset, TiledError> {
+    let image = value["image"]
+        .as_str()
+        .map(|image| {
+            Ok::<_, TiledError>(reader.image(
+                base,
+                image,
+                UVec2::new(uint(value, "imagewidth")?, uint(value, "imageheight")?),
+            ))
+        })
+        .transpose()?;
+
+    let mut tiles = HashMap::new();
+    for tile in array(value, "tiles") {
+        let animation = array(tile, "animation")
+            .map(|frame| Ok((uint(frame, "tileid")?, uint(frame, "duration")?)))
+            .collect::<Result<Vec<_>, TiledError>>()?;
+
+        tiles.insert(
+            uint(tile, "id")?,
+            TiledTileInfo {
+                class: class(tile),
+                properties: parse_properties(tile)?,
+                animation,
+            },
+        );
+    }
+
+    Ok(TiledTileset {
+        first_gid,
+        name: value["name"].as_str().unwrap_or_default().to_string(),
+        tile_size: UVec2::new(uint(value, "tilewidth")?, uint(value, "tileheight")?),
+        spacing: value["spacing"].as_u64().unwrap_or_default() as u32,
+        margin: value["margin"].as_u64().unwrap_or_default() as u32,
+        tile_count: value["tilecount"].as_u64().unwrap_or_default() as u32,
+        columns: value["columns"].as_u64().unwrap_or_default() as u32,
+        image,
+        tile_offset: Vec2::new(
+            float(&value["tileoffset"], "x", 0.),
+            float(&value["tileoffset"], "y", 0.),
+        ),
+        tiles,
+        properties: parse_properties(value)?,
+    })
+}
+
+fn parse_layers(
+    reader: &TiledReader,
+    base: &Path,
+    parent: &Value,
+) -> Result<Vec<TiledLayer>, TiledError> {
+    array(parent, "layers")
+        .filter_map(|layer| {
+            let content = match layer["type"].as_str()? {
+                "tilelayer" => parse_tile_layer(layer),
+                "objectgroup" => parse_objects(layer).map(TiledLayerContent::Objects),
+                "imagelayer" => Ok(TiledLayerContent::Image(
+                    layer["image"]
+                        .as_str()
+                        .filter(|image| !image.is_empty())
+                        .map(|image| {
+                            let size = UVec2::new(
+                                layer["imagewidth"].as_u64().unwrap_or_default() as u32,
+                                layer["imageheight"].as_u64().unwrap_or_default() as u32,
+                            );
+                            reader.image(base, image, size)
+                        }),
+                )),
+                "group" => parse_layers(reader, base, layer).map(TiledLayerContent::Group),
+                _ => return None,
+            };
+            Some(content.and_then(|content| parse_layer(layer, content)))
+        })
+        .collect()
+}
+
+fn parse_layer(value: &Value, content: TiledLayerContent) -> Result<TiledLayer, TiledError> {
+    Ok(TiledLayer {
+        id: value["id"].as_u64().unwrap_or_default() as u32,
+        name: value["name"].as_str().unwrap_or_default().to_string(),
+        class: class(value),
+        visible: value["visible"].as_bool().unwrap_or(true),
+        opacity: float(value, "opacity", 1.),
+        tint: value["tintcolor"]
+            .as_str()
+            .map(parse_color)
+            .transpose()?
+            .unwrap_or(Vec4::ONE),
+        offset: Vec2::new(float(value, "offsetx", 0.), float(value, "offsety", 0.)),
+        properties: parse_properties(value)?,
+        content,
+    })
+}
+
+fn parse_tile_layer(value: &Value) -> Result<TiledLayerContent, TiledError> {
+    let encoding = value["encoding"].as_str().unwrap_or("csv");
+    let compression = value["compression"].as_str();
+    let decode = |data: &Value| -> Result<Vec<u32>, TiledError> {
+        match data {
+            Value::Array(gids) => Ok(gids
+                .iter()
+                .map(|gid| gid.as_u64().unwrap_or_default() as u32)
+                .collect()),
+            Value::String(data) => decode_tile_data(data, Some(encoding), compression),
+            _ => Ok(Vec::new()),
+        }
+    };
+
+    let mut tiles = Vec::new();
+    match value.get("chunks") {
+        Some(Value::Array(chunks)) => {
+            for chunk in chunks {
+                collect_tiles(
+                    decode(&chunk["data"])?,
+                    IVec2::new(int(chunk, "x")?, int(chunk, "y")?),
+                    uint(chunk, "width")?,
+                    &mut tiles,
+                );
+            }
+        }
+        _ => collect_tiles(
+            decode(&value["data"])?,
+            IVec2::ZERO,
+            uint(value, "width")?,
+            &mut tiles,
+        ),
+    }
+
+    Ok(TiledLayerContent::Tiles(tiles))
+}
+
+fn parse_objects(value: &Value) -> Result<Vec<TiledObjectInstance>, TiledError> {
+    array(value, "objects")
+        .filter_map(|object| {
+            if object.get("template").is_some() {
+                warn!("Object templates are not supported! Skipping the object.");
+                return None;
+            }
+            Some(parse_object(object))
+        })
+        .collect()
+}
+
+fn parse_object(value: &Value) -> Result<TiledObjectInstance, TiledError> {
+    let points = |key: &str| {
+        array(value, key)
+            .map(|point| Vec2::new(float(point, "x", 0.), float(point, "y", 0.)))
+            .collect()
+    };
+    let shape = if value["ellipse"].as_bool().unwrap_or_default() {
+        TiledObjectShape::Ellipse
+    } else if value["point"].as_bool().unwrap_or_default() {
+        TiledObjectShape::Point
+    } else if value.get("polygon").is_some() {
+        TiledObjectShape::Polygon(points("polygon"))
+    } else if value.get("polyline").is_some() {
+        TiledObjectShape::Polyline(points("polyline"))
+    } else if let Some(text) = value.get("text") {
+        TiledObjectShape::Text(text["text"].as_str().unwrap_or_default().to_string())
+    } else {
+        TiledObjectShape::Rectangle
+    };
+
+    Ok(TiledObjectInstance {
+        id: uint(value, "id")?,
+        name: value["name"].as_str().unwrap_or_default().to_string(),
+        class: class(value),
+        position: Vec2::new(float(value, "x", 0.), float(value, "y", 0.)),
+        size: Vec2::new(float(value, "width", 0.), float(value, "height", 0.)),
+        rotation: float(value, "rotation", 0.),
+        gid: value["gid"].as_u64().map(|gid| gid as u32),
+        visible: value["visible"].as_bool().unwrap_or(true),
+        shape,
+        properties: parse_properties(value)?,
+    })
+}
+
+fn parse_properties(value: &Value) -> Result<TiledProperties, TiledError> {
+    array(value, "properties")
+        .map(|property| {
+            let value = &property["value"];
+            let value = match property["type"].as_str().unwrap_or("string") {
+                "bool" => TiledProperty::Bool(value.as_bool().unwrap_or_default()),
+                "int" => TiledProperty::Int(value.as_i64().unwrap_or_default()),
+                "float" => TiledProperty::Float(value.as_f64().unwrap_or_default()),
+                "color" => match value.as_str() {
+                    Some(color) if !color.is_empty() => TiledProperty::Color(parse_color(color)?),
+                    _ => TiledProperty::Color(Vec4::ZERO),
+                },
+                "file" => TiledProperty::File(value.as_str().unwrap_or_default().to_string()),
+                "object" => TiledProperty::Object(value.as_u64().unwrap_or_default() as u32),
+                "class" => TiledProperty::Class(parse_class_members(value)),
+                _ => TiledProperty::String(value.as_str().unwrap_or_default().to_string()),
+            };
+            Ok((str(property, "name")?.to_string(), value))
+        })
+        .collect()
+}
+
+/// The members of a class property don't have types in json, so they are guessed.
+fn parse_class_members(value: &Value) -> TiledProperties {
+    let Some(members) = value.as_object() else {
+        return TiledProperties::default();
+    };
+
+    members
+        .iter()
+        .map(|(name, value)| {
+            let value = match value {
+                Value::Bool(b) => TiledProperty::Bool(*b),
+                Value::Number(n) => match n.as_i64() {
+                    Some(i) => TiledProperty::Int(i),
+                    None => TiledProperty::Float(n.as_f64().unwrap_or_default()),
+                },
+                Value::Object(_) => TiledProperty::Class(parse_class_members(value)),
+                _ => TiledProperty::String(value.as_str().unwrap_or_default().to_string()),
+            };
+            (name.clone(), value)
+        })
+        .collect()
+}
+
+/// Tiled 1.9 renamed `type` to `class`.
+fn class(value: &Value) -> String {
+    value["class"]
+        .as_str()
+        .or_else(|| value["type"].as_str())
+        .unwrap_or_default()
+        .to_string()
+}
+
+fn array<'a>(value: &'a Value, key: &str) -> impl Iterator<Item = &'a Value> {
+    value[key].as_array().into_iter().flatten()
+}
+
+fn str<'a>(value: &'a Value, key: &'static str) -> Result<&'a str, TiledError> {
+    value[key].as_str().ok_or(TiledError::MissingAttribute(key))
+}
+
+fn uint(value: &Value, key: &'static str) -> Result<u32, TiledError> {
+    value[key]
+        .as_u64()
+        .map(|v| v as u32)
+        .ok_or(TiledError::MissingAttribute(key))
+}
+
+fn int(value: &Value, key: &'static str) -> Result<i32, TiledError> {
+    value[key]
+        .as_i64()
+        .map(|v| v as i32)
+        .ok_or(TiledError::MissingAttribute(key))
+}
+
+fn float(value: &Value, key: &str, default: f32) -> f32 {
+    value[key].as_f64().map(|v| v as f32).unwrap_or(default)
+}
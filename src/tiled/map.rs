@@ -0,0 +1,234 @@
+use std::{fmt::Display, path::PathBuf};
+
+use bevy::{
+    math::{IVec2, UVec2, Vec2, Vec4},
+    reflect::Reflect,
+    utils::HashMap,
+};
+
+pub const FLIPPED_HORIZONTALLY_FLAG: u32 = 0x80000000;
+pub const FLIPPED_VERTICALLY_FLAG: u32 = 0x40000000;
+pub const FLIPPED_DIAGONALLY_FLAG: u32 = 0x20000000;
+/// Only used by hexagonal maps. Not supported.
+pub const ROTATED_HEXAGONAL_120_FLAG: u32 = 0x10000000;
+pub const GID_MASK: u32 = !(FLIPPED_HORIZONTALLY_FLAG
+    | FLIPPED_VERTICALLY_FLAG
+    | FLIPPED_DIAGONALLY_FLAG
+    | ROTATED_HEXAGONAL_120_FLAG);
+
+#[derive(Debug)]
+pub enum TiledError {
+    Io(PathBuf, std::io::Error),
+    Xml(roxmltree::Error),
+    Json(serde_json::Error),
+    MissingAttribute(&'static str),
+    InvalidValue(&'static str, String),
+    Unsupported(String),
+    UnregisteredObject(String),
+}
+
+impl Display for TiledError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TiledError::Io(path, e) => write!(f, "Could not read file at path: {:?}! {}", path, e),
+            TiledError::Xml(e) => write!(f, "Could not parse xml! {}", e),
+            TiledError::Json(e) => write!(f, "Could not parse json! {}", e),
+            TiledError::MissingAttribute(attr) => write!(f, "Missing attribute `{}`!", attr),
+            TiledError::InvalidValue(attr, value) => {
+                write!(f, "Invalid value `{}` for `{}`!", value, attr)
+            }
+            TiledError::Unsupported(feature) => write!(f, "Unsupported: {}!", feature),
+            TiledError::UnregisteredObject(class) => write!(
+                f,
+                "Could not find object type with class: {}! \
+                You need to register it using App::register_tiled_object::<T>() first!",
+                class
+            ),
+        }
+    }
+}
+
+impl std::error::Error for TiledError {}
+
+impl From<roxmltree::Error> for TiledError {
+    fn from(value: roxmltree::Error) -> Self {
+        Self::Xml(value)
+    }
+}
+
+impl From<serde_json::Error> for TiledError {
+    fn from(value: serde_json::Error) -> Self {
+        Self::Json(value)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TiledProperty {
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    String(String),
+    Color(Vec4),
+    /// The path of the file relative to the file that contains the property.
+    File(String),
+    /// The id of the object.
+    Object(u32),
+    Class(TiledProperties),
+}
+
+pub type TiledProperties = HashMap<String, TiledProperty>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Reflect)]
+pub enum TiledOrientation {
+    Orthogonal,
+    Isometric,
+    /// Staggered isometric maps. Not supported.
+    Staggered,
+    Hexagonal,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Reflect)]
+pub enum TiledStaggerAxis {
+    X,
+    Y,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Reflect)]
+pub enum TiledStaggerIndex {
+    Odd,
+    Even,
+}
+
+#[derive(Debug, Clone)]
+pub struct TiledMap {
+    pub orientation: TiledOrientation,
+    /// The size of the map in tiles. Infinite maps can have tiles outside of it.
+    pub size: UVec2,
+    pub tile_size: UVec2,
+    pub infinite: bool,
+    pub hex_side_length: u32,
+    pub stagger_axis: TiledStaggerAxis,
+    pub stagger_index: TiledStaggerIndex,
+    pub background_color: Option<Vec4>,
+    /// Sorted by the first gid.
+    pub tilesets: Vec<TiledTileset>,
+    pub layers: Vec<TiledLayer>,
+    pub properties: TiledProperties,
+}
+
+impl TiledMap {
+    /// Get the tileset that contains the tile and the local id of the tile.
+    ///
+    /// The flip flags of the gid are ignored.
+    pub fn get_tileset(&self, gid: u32) -> Option<(&TiledTileset, u32)> {
+        let gid = gid & GID_MASK;
+        if gid == 0 {
+            return None;
+        }
+
+        self.tilesets
+            .iter()
+            .rev()
+            .find(|tileset| tileset.first_gid <= gid)
+            .map(|tileset| (tileset, gid - tileset.first_gid))
+    }
+
+    /// Get the index of the tileset that contains the tile.
+    pub(crate) fn get_tileset_index(&self, gid: u32) -> Option<usize> {
+        let gid = gid & GID_MASK;
+        if gid == 0 {
+            return None;
+        }
+
+        self.tilesets
+            .iter()
+            .rposition(|tileset| tileset.first_gid <= gid)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct TiledTileset {
+    pub first_gid: u32,
+    pub name: String,
+    pub tile_size: UVec2,
+    pub spacing: u32,
+    pub margin: u32,
+    pub tile_count: u32,
+    pub columns: u32,
+    /// `None` if the tileset is a collection of images, which is not supported.
+    pub image: Option<TiledImage>,
+    /// In pixels, the y axis points down.
+    pub tile_offset: Vec2,
+    /// The tiles that have properties, a class or an animation.
+    pub tiles: HashMap<u32, TiledTileInfo>,
+    pub properties: TiledProperties,
+}
+
+#[derive(Debug, Clone)]
+pub struct TiledImage {
+    /// The asset path of the image.
+    pub path: PathBuf,
+    pub size: UVec2,
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct TiledTileInfo {
+    pub class: String,
+    pub properties: TiledProperties,
+    /// The local id and the duration in milliseconds of each frame.
+    pub animation: Vec<(u32, u32)>,
+}
+
+#[derive(Debug, Clone)]
+pub struct TiledLayer {
+    pub id: u32,
+    pub name: String,
+    pub class: String,
+    pub visible: bool,
+    pub opacity: f32,
+    pub tint: Vec4,
+    /// In pixels, the y axis points down.
+    pub offset: Vec2,
+    pub properties: TiledProperties,
+    pub content: TiledLayerContent,
+}
+
+#[derive(Debug, Clone)]
+pub enum TiledLayerContent {
+    /// The index and the gid with flip flags of the tiles. Empty tiles are skipped.
+    /// The y axis of the indices points down.
+    Tiles(Vec<(IVec2, u32)>),
+    Objects(Vec<TiledObjectInstance>),
+    Image(Option<TiledImage>),
+    Group(Vec<TiledLayer>),
+}
+
+#[derive(Debug, Clone)]
+pub struct TiledObjectInstance {
+    pub id: u32,
+    pub name: String,
+    pub class: String,
+    /// In pixels, the y axis points down. For tile objects, this is the bottom left
+    /// corner of the tile. Otherwise it's the top left corner.
+    pub position: Vec2,
+    pub size: Vec2,
+    /// Clockwise, in degrees.
+    pub rotation: f32,
+    /// The gid with flip flags if it's a tile object.
+    pub gid: Option<u32>,
+    pub visible: bool,
+    pub shape: TiledObjectShape,
+    pub properties: TiledProperties,
+}
+
+#[derive(Debug, Clone)]
+pub enum TiledObjectShape {
+    Rectangle,
+    Ellipse,
+    Point,
+    /// The points relative to the position of the object.
+    Polygon(Vec<Vec2>),
+    /// The points relative to the position of the object.
+    Polyline(Vec<Vec2>),
+    Text(String),
+}
@@ -0,0 +1,571 @@
+use bevy::{
+    app::{Plugin, Update},
+    asset::AssetServer,
+    ecs::{
+        entity::Entity,
+        event::EventWriter,
+        query::With,
+        system::{Commands, Query, Res, ResMut},
+    },
+    log::{error, warn},
+    math::{IVec2, Quat, Vec2, Vec4},
+    render::color::Color,
+    sprite::{Anchor, Sprite, SpriteBundle},
+    transform::components::Transform,
+    utils::HashMap,
+};
+
+use crate::{
+    tilemap::{
+        bundles::TilemapBundle,
+        map::{
+            TileRenderSize, TilemapLayerStyle, TilemapLayers, TilemapName, TilemapRotation,
+            TilemapSlotSize, TilemapStorage, TilemapTexture, TilemapTextureDescriptor,
+            TilemapTransform, TilemapType,
+        },
+        tile::{AnimationMode, TileBuilder, TileFlip, TileLayer},
+    },
+    DEFAULT_CHUNK_SIZE,
+};
+
+use self::{
+    components::{
+        TiledLayerInfo, TiledLoadedMap, TiledLoader, TiledObjectId, TiledTileProperties,
+        TiledUnloadLayer, TiledUnloader,
+    },
+    events::TiledEvent,
+    map::{
+        TiledError, TiledLayer, TiledLayerContent, TiledMap, TiledObjectInstance, TiledOrientation,
+        TiledStaggerAxis, TiledStaggerIndex, FLIPPED_DIAGONALLY_FLAG, FLIPPED_HORIZONTALLY_FLAG,
+        FLIPPED_VERTICALLY_FLAG, GID_MASK,
+    },
+    resources::{TiledLoadConfig, TiledMapManager},
+    traits::TiledObjectRegistry,
+};
+
+pub mod app_ext;
+pub mod components;
+pub mod events;
+pub mod json;
+pub mod map;
+pub mod parse;
+pub mod resources;
+pub mod traits;
+pub mod xml;
+
+pub struct EntiTilesTiledPlugin;
+
+impl Plugin for EntiTilesTiledPlugin {
+    fn build(&self, app: &mut bevy::prelude::App) {
+        app.add_systems(
+            Update,
+            (load_tiled_map, unload_tiled_map, unload_tiled_layer),
+        );
+
+        app.init_resource::<TiledObjectRegistry>();
+
+        app.init_resource::<TiledMapManager>()
+            .init_resource::<TiledLoadConfig>();
+
+        app.add_event::<TiledEvent>();
+
+        app.register_type::<TiledLoader>()
+            .register_type::<TiledUnloader>()
+            .register_type::<TiledLoadedMap>()
+            .register_type::<TiledLayerInfo>()
+            .register_type::<TiledTileProperties>()
+            .register_type::<TiledObjectId>();
+
+        app.register_type::<TiledMapManager>()
+            .register_type::<TiledLoadConfig>();
+    }
+}
+
+pub fn unload_tiled_map(
+    mut commands: Commands,
+    query: Query<(Entity, &TiledLoadedMap), With<TiledUnloader>>,
+    mut tiled_events: EventWriter<TiledEvent>,
+) {
+    query.iter().for_each(|(entity, map)| {
+        tiled_events.send(TiledEvent::MapUnloaded(map.name.clone()));
+        map.unload(&mut commands);
+        commands.entity(entity).despawn();
+    });
+}
+
+pub fn unload_tiled_layer(
+    mut commands: Commands,
+    mut query: Query<(Entity, Option<&mut TilemapStorage>), With<TiledUnloadLayer>>,
+) {
+    query
+        .iter_mut()
+        .for_each(|(entity, storage)| match storage {
+            Some(mut storage) => storage.despawn(&mut commands),
+            None => commands.entity(entity).despawn(),
+        });
+}
+
+pub fn load_tiled_map(
+    mut commands: Commands,
+    loader_query: Query<(Entity, &TiledLoader)>,
+    asset_server: Res<AssetServer>,
+    object_registry: Option<Res<TiledObjectRegistry>>,
+    config: Res<TiledLoadConfig>,
+    mut manager: ResMut<TiledMapManager>,
+    mut tiled_events: EventWriter<TiledEvent>,
+) {
+    for (entity, loader) in loader_query.iter() {
+        commands.entity(entity).remove::<TiledLoader>();
+
+        if manager.get_cached_map(&loader.map).is_none() {
+            if let Err(e) = manager.reload(&config, loader.map.clone()) {
+                error!("Failed to load tiled map {:?}! {}", loader.map, e);
+                manager.loaded_maps.remove(&loader.map);
+                commands.entity(entity).despawn();
+                continue;
+            }
+        }
+
+        let map = manager.get_cached_map(&loader.map).unwrap();
+        let loaded = match spawn_map(
+            &mut commands,
+            map,
+            loader,
+            &asset_server,
+            object_registry
+                .as_deref()
+                .unwrap_or(&TiledObjectRegistry::default()),
+            &config,
+        ) {
+            Ok(loaded) => loaded,
+            Err(e) => {
+                error!("Failed to load tiled map {:?}! {}", loader.map, e);
+                manager.loaded_maps.remove(&loader.map);
+                commands.entity(entity).despawn();
+                continue;
+            }
+        };
+
+        commands.entity(entity).insert(loaded);
+        tiled_events.send(TiledEvent::MapLoaded(loader.map.clone()));
+    }
+}
+
+/// The accumulated state of the group layers.
+#[derive(Clone, Copy)]
+struct GroupState {
+    offset: Vec2,
+    opacity: f32,
+    tint: Vec4,
+}
+
+fn flatten_layers<'a>(
+    layers: &'a [TiledLayer],
+    parent: GroupState,
+    result: &mut Vec<(&'a TiledLayer, GroupState)>,
+) {
+    layers
+        .iter()
+        .filter(|layer| layer.visible)
+        .for_each(|layer| {
+            let state = GroupState {
+                offset: parent.offset + layer.offset,
+                opacity: parent.opacity * layer.opacity,
+                tint: parent.tint * layer.tint,
+            };
+            match &layer.content {
+                TiledLayerContent::Group(children) => flatten_layers(children, state, result),
+                _ => result.push((layer, state)),
+            }
+        });
+}
+
+fn spawn_map(
+    commands: &mut Commands,
+    map: &TiledMap,
+    loader: &TiledLoader,
+    asset_server: &AssetServer,
+    object_registry: &TiledObjectRegistry,
+    config: &TiledLoadConfig,
+) -> Result<TiledLoadedMap, TiledError> {
+    let ty = match (map.orientation, map.stagger_axis) {
+        (TiledOrientation::Orthogonal, _) => TilemapType::Square,
+        (TiledOrientation::Isometric, _) => TilemapType::Isometric,
+        (TiledOrientation::Hexagonal, TiledStaggerAxis::Y) => {
+            TilemapType::Hexagonal(map.hex_side_length)
+        }
+        (TiledOrientation::Hexagonal, TiledStaggerAxis::X) => {
+            return Err(TiledError::Unsupported(
+                "hexagonal maps staggered along the x axis".to_string(),
+            ))
+        }
+        (TiledOrientation::Staggered, _) => {
+            return Err(TiledError::Unsupported("staggered maps".to_string()))
+        }
+    };
+
+    let translation = loader.trans_ovrd.unwrap_or_default();
+    let mut layers = Vec::new();
+    flatten_layers(
+        &map.layers,
+        GroupState {
+            offset: Vec2::ZERO,
+            opacity: 1.,
+            tint: Vec4::ONE,
+        },
+        &mut layers,
+    );
+
+    // Check the objects before spawning anything so a failed map doesn't leave entities behind.
+    if !config.ignore_unregistered_objects {
+        let unregistered = layers
+            .iter()
+            .filter_map(|(layer, _)| match &layer.content {
+                TiledLayerContent::Objects(objects) => Some(objects),
+                _ => None,
+            })
+            .flatten()
+            .find(|object| {
+                !object.class.is_empty() && !object_registry.contains_key(&object.class)
+            });
+        if let Some(object) = unregistered {
+            return Err(TiledError::UnregisteredObject(object.class.clone()));
+        }
+    }
+
+    let mut loaded = TiledLoadedMap {
+        name: loader.map.clone(),
+        layers: Vec::new(),
+        objects: HashMap::new(),
+    };
+    let mut textures = HashMap::new();
+
+    for (order, (layer, state)) in layers.into_iter().enumerate() {
+        let z_index = config.z_index + order as i32;
+        // Tiled offsets point down.
+        let translation = translation + Vec2::new(state.offset.x, -state.offset.y);
+        let info = TiledLayerInfo {
+            id: layer.id,
+            name: layer.name.clone(),
+            class: layer.class.clone(),
+            properties: layer.properties.clone(),
+        };
+
+        match &layer.content {
+            TiledLayerContent::Tiles(tiles) => {
+                let mut tilesets = HashMap::<usize, Vec<(IVec2, u32)>>::new();
+                tiles.iter().for_each(|(index, gid)| {
+                    if let Some(tileset) = map.get_tileset_index(*gid) {
+                        tilesets.entry(tileset).or_default().push((*index, *gid));
+                    }
+                });
+                let tileset_count = tilesets.len();
+
+                for (tileset_index, tiles) in tilesets {
+                    let tileset = &map.tilesets[tileset_index];
+                    let Some(texture) = textures
+                        .entry(tileset_index)
+                        .or_insert_with(|| {
+                            load_tileset_texture(map, tileset_index, asset_server, config)
+                        })
+                        .clone()
+                    else {
+                        continue;
+                    };
+
+                    let tilemap_entity = commands.spawn_empty().id();
+                    let mut tilemap = TilemapBundle {
+                        name: TilemapName(if tileset_count > 1 {
+                            format!("{}_{}", layer.name, tileset.name)
+                        } else {
+                            layer.name.clone()
+                        }),
+                        ty,
+                        tile_render_size: TileRenderSize(tileset.tile_size.as_vec2()),
+                        slot_size: TilemapSlotSize(map.tile_size.as_vec2()),
+                        texture,
+                        storage: TilemapStorage::new(DEFAULT_CHUNK_SIZE, tilemap_entity),
+                        tilemap_transform: TilemapTransform {
+                            translation: translation
+                                + get_grid_translation(map)
+                                + Vec2::new(tileset.tile_offset.x, -tileset.tile_offset.y),
+                            z_index,
+                            ..Default::default()
+                        },
+                        ..Default::default()
+                    };
+
+                    let mut animations = HashMap::new();
+                    let mut tile_properties = HashMap::new();
+                    for (index, gid) in tiles {
+                        let local = (gid & GID_MASK) - tileset.first_gid;
+                        let index = get_tile_index(map, index);
+                        let info = tileset.tiles.get(&local);
+
+                        let mut tile_layer = TileLayer::new()
+                            .with_texture_index(local)
+                            .with_flip_raw(get_flip(gid));
+                        if let Some(info) = info.filter(|info| !info.animation.is_empty()) {
+                            let animation = *animations.entry(local).or_insert_with(|| {
+                                tilemap.animations.register_animation_with_durations(
+                                    info.animation
+                                        .iter()
+                                        .map(|(frame, ms)| (*frame, *ms as f32 / 1000.))
+                                        .collect(),
+                                    AnimationMode::Loop,
+                                )
+                            });
+                            tile_layer = tile_layer.with_animation(animation);
+                        }
+
+                        if let Some(info) = info
+                            .filter(|info| !info.class.is_empty() || !info.properties.is_empty())
+                        {
+                            tile_properties.insert(index, info.clone());
+                        }
+
+                        tilemap.storage.set(
+                            commands,
+                            index,
+                            TileBuilder::new().with_layer(0, tile_layer),
+                        );
+                    }
+
+                    let mut tilemap_commands = commands.entity(tilemap_entity);
+                    tilemap_commands.insert((tilemap, info.clone()));
+                    if state.opacity != 1. || state.tint != Vec4::ONE {
+                        tilemap_commands.insert(TilemapLayers::new(1).with_style(
+                            0,
+                            TilemapLayerStyle {
+                                opacity: state.opacity,
+                                tint: state.tint,
+                                ..Default::default()
+                            },
+                        ));
+                    }
+                    if !tile_properties.is_empty() {
+                        tilemap_commands.insert(TiledTileProperties(tile_properties));
+                    }
+                    loaded.layers.push(tilemap_entity);
+                }
+            }
+            TiledLayerContent::Objects(objects) => {
+                for (order, object) in objects.iter().enumerate() {
+                    if object.class.is_empty() {
+                        continue;
+                    }
+
+                    // Unregistered classes are either ignored or rejected above.
+                    let Some(phantom_object) = object_registry.get(&object.class) else {
+                        continue;
+                    };
+
+                    let position = translation + get_object_position(map, object);
+                    let z = z_index as f32 + order as f32 / objects.len() as f32;
+                    let mut object_commands = commands.spawn((
+                        Transform::from_translation(position.extend(z))
+                            .with_rotation(Quat::from_rotation_z(-object.rotation.to_radians())),
+                        TiledObjectId(object.id),
+                    ));
+                    loaded.objects.insert(object.id, object_commands.id());
+                    phantom_object.spawn(&mut object_commands, object, map, asset_server);
+                }
+            }
+            TiledLayerContent::Image(Some(image)) => {
+                let tint = state.tint;
+                let sprite = commands
+                    .spawn((
+                        SpriteBundle {
+                            sprite: Sprite {
+                                color: Color::rgba(tint.x, tint.y, tint.z, tint.w * state.opacity),
+                                anchor: Anchor::TopLeft,
+                                ..Default::default()
+                            },
+                            texture: asset_server.load(image.path.clone()),
+                            transform: Transform::from_translation(
+                                translation.extend(z_index as f32),
+                            ),
+                            ..Default::default()
+                        },
+                        info,
+                    ))
+                    .id();
+                loaded.layers.push(sprite);
+            }
+            TiledLayerContent::Image(None) | TiledLayerContent::Group(_) => {}
+        }
+    }
+
+    Ok(loaded)
+}
+
+fn load_tileset_texture(
+    map: &TiledMap,
+    tileset_index: usize,
+    asset_server: &AssetServer,
+    config: &TiledLoadConfig,
+) -> Option<TilemapTexture> {
+    let tileset = &map.tilesets[tileset_index];
+    let Some(image) = tileset.image.as_ref() else {
+        warn!(
+            "Tileset {:?} is a collection of images, which is not supported!",
+            tileset.name
+        );
+        return None;
+    };
+
+    if tileset.spacing != 0 || tileset.margin != 0 {
+        warn!(
+            "Tileset {:?} has spacing or margin, which is not supported!",
+            tileset.name
+        );
+    }
+
+    let desc = TilemapTextureDescriptor {
+        // Ignore the spare pixels.
+        size: image.size / tileset.tile_size * tileset.tile_size,
+        tile_size: tileset.tile_size,
+        filter_mode: config.filter_mode,
+        mode: config.texture_mode,
+    };
+    Some(TilemapTexture::new(
+        asset_server.load(image.path.clone()),
+        desc,
+        TilemapRotation::None,
+    ))
+}
+
+/// Convert the flip flags of a gid into `TileFlip` bits.
+fn get_flip(gid: u32) -> u32 {
+    let mut flip = 0;
+    if gid & FLIPPED_HORIZONTALLY_FLAG != 0 {
+        flip |= TileFlip::Horizontal as u32;
+    }
+    if gid & FLIPPED_VERTICALLY_FLAG != 0 {
+        flip |= TileFlip::Vertical as u32;
+    }
+    if gid & FLIPPED_DIAGONALLY_FLAG != 0 {
+        flip |= TileFlip::Diagonal as u32;
+    }
+    flip
+}
+
+/// Whether the row is shifted by half a tile in a hexagonal map.
+fn is_staggered(map: &TiledMap, row: i32) -> i32 {
+    match map.stagger_index {
+        TiledStaggerIndex::Odd => row.rem_euclid(2),
+        TiledStaggerIndex::Even => 1 - row.rem_euclid(2),
+    }
+}
+
+/// Convert the index of a tile in Tiled, whose y axis points down, into the index
+/// in the tilemap.
+fn get_tile_index(map: &TiledMap, index: IVec2) -> IVec2 {
+    let (col, row) = (index.x, index.y);
+    match map.orientation {
+        TiledOrientation::Isometric => IVec2::new(-row, -col),
+        TiledOrientation::Hexagonal => IVec2::new(
+            col + (is_staggered(map, row) - row - is_staggered(map, 0)).div_euclid(2),
+            -row,
+        ),
+        _ => IVec2::new(col, -row - 1),
+    }
+}
+
+/// The translation that moves the bottom left corner of each tile to where Tiled
+/// draws the image of the tile, with the top left corner of the map at the origin.
+fn get_grid_translation(map: &TiledMap) -> Vec2 {
+    let tile_size = map.tile_size.as_vec2();
+    match map.orientation {
+        TiledOrientation::Isometric => {
+            Vec2::new((map.size.y as f32 - 1.) * tile_size.x / 2., -tile_size.y)
+        }
+        TiledOrientation::Hexagonal => {
+            Vec2::new(is_staggered(map, 0) as f32 * tile_size.x / 2., -tile_size.y)
+        }
+        _ => Vec2::ZERO,
+    }
+}
+
+/// The position of the object with the top left corner of the map at the origin.
+fn get_object_position(map: &TiledMap, object: &TiledObjectInstance) -> Vec2 {
+    match map.orientation {
+        TiledOrientation::Isometric => {
+            // Both axes of isometric objects are measured in the tile height.
+            let tile_size = map.tile_size.as_vec2();
+            let pos = object.position / tile_size.y;
+            Vec2::new(
+                (pos.x - pos.y + map.size.y as f32) * tile_size.x / 2.,
+                -(pos.x + pos.y) * tile_size.y / 2.,
+            )
+        }
+        _ => Vec2::new(object.position.x, -object.position.y),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::tilemap::coordinates::index_to_mesh_origin;
+
+    fn map(orientation: TiledOrientation, stagger_index: TiledStaggerIndex) -> TiledMap {
+        TiledMap {
+            orientation,
+            size: bevy::math::UVec2::new(4, 3),
+            tile_size: bevy::math::UVec2::new(32, 16),
+            infinite: false,
+            hex_side_length: 8,
+            stagger_axis: TiledStaggerAxis::Y,
+            stagger_index,
+            background_color: None,
+            tilesets: Vec::new(),
+            layers: Vec::new(),
+            properties: Default::default(),
+        }
+    }
+
+    /// Where Tiled draws the bottom left corner of a tile with the y axis pointing up.
+    fn tiled_position(map: &TiledMap, col: i32, row: i32) -> Vec2 {
+        let (w, h) = (map.tile_size.x as f32, map.tile_size.y as f32);
+        let (c, r) = (col as f32, row as f32);
+        match map.orientation {
+            TiledOrientation::Isometric => Vec2::new(
+                map.size.y as f32 * w / 2. + (c - r) * w / 2. - w / 2.,
+                -((c + r) * h / 2. + h),
+            ),
+            TiledOrientation::Hexagonal => Vec2::new(
+                c * w + is_staggered(map, row) as f32 * w / 2.,
+                -(r * (h + map.hex_side_length as f32) / 2. + h),
+            ),
+            _ => Vec2::new(c * w, -(r + 1.) * h),
+        }
+    }
+
+    #[test]
+    fn test_tile_placement() {
+        for (orientation, ty) in [
+            (TiledOrientation::Orthogonal, TilemapType::Square),
+            (TiledOrientation::Isometric, TilemapType::Isometric),
+            (TiledOrientation::Hexagonal, TilemapType::Hexagonal(8)),
+        ] {
+            for stagger_index in [TiledStaggerIndex::Odd, TiledStaggerIndex::Even] {
+                let map = map(orientation, stagger_index);
+                for col in -2..4 {
+                    for row in -2..3 {
+                        let index = get_tile_index(&map, IVec2::new(col, row));
+                        let origin = index_to_mesh_origin(index, &ty, map.tile_size.as_vec2());
+                        assert_eq!(
+                            origin + get_grid_translation(&map),
+                            tiled_position(&map, col, row),
+                            "{:?} {:?} ({}, {})",
+                            orientation,
+                            stagger_index,
+                            col,
+                            row
+                        );
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,182 @@
+use std::{
+    io::Read,
+    path::{Component, Path, PathBuf},
+};
+
+use base64::Engine;
+use bevy::math::{IVec2, UVec2, Vec4};
+
+use super::{
+    json,
+    map::{TiledError, TiledImage, TiledMap, TiledTileset},
+    xml,
+};
+
+/// Reads the map files and the external tilesets in a folder.
+pub struct TiledReader<'a> {
+    /// The folder that contains the maps on the file system.
+    pub root: &'a Path,
+    /// The asset path of the same folder.
+    pub asset_prefix: &'a Path,
+}
+
+impl<'a> TiledReader<'a> {
+    /// Parse a `.tmx` or `.tmj` map. The path is relative to the root.
+    pub fn read_map(&self, path: &Path) -> Result<TiledMap, TiledError> {
+        let source = self.read_file(path)?;
+        let base = path.parent().unwrap_or(Path::new(""));
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("tmx") | Some("xml") => xml::parse_map(self, base, &source),
+            Some("tmj") | Some("json") => json::parse_map(self, base, &source),
+            _ => Err(TiledError::Unsupported(format!("map file {:?}", path))),
+        }
+    }
+
+    /// Parse a `.tsx` or `.tsj` tileset. The path is relative to the root.
+    pub fn read_tileset(&self, path: &Path, first_gid: u32) -> Result<TiledTileset, TiledError> {
+        let source = self.read_file(path)?;
+        let base = path.parent().unwrap_or(Path::new(""));
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("tsx") | Some("xml") => xml::parse_tileset_file(self, base, &source, first_gid),
+            Some("tsj") | Some("json") => json::parse_tileset_file(self, base, &source, first_gid),
+            _ => Err(TiledError::Unsupported(format!("tileset file {:?}", path))),
+        }
+    }
+
+    pub fn image(&self, base: &Path, source: &str, size: UVec2) -> TiledImage {
+        TiledImage {
+            path: self.asset_prefix.join(resolve_path(base, source)),
+            size,
+        }
+    }
+
+    fn read_file(&self, path: &Path) -> Result<String, TiledError> {
+        let path = self.root.join(path);
+        std::fs::read_to_string(&path).map_err(|e| TiledError::Io(path, e))
+    }
+}
+
+/// Join the path to the folder of the file that references it and remove the `..`s.
+pub fn resolve_path(base: &Path, path: &str) -> PathBuf {
+    let mut result = PathBuf::new();
+    for component in base.join(path).components() {
+        match component {
+            Component::ParentDir => {
+                result.pop();
+            }
+            Component::CurDir => {}
+            c => result.push(c),
+        }
+    }
+    result
+}
+
+/// Parse `#RRGGBB` or `#AARRGGBB`.
+pub fn parse_color(color: &str) -> Result<Vec4, TiledError> {
+    let invalid = || TiledError::InvalidValue("color", color.to_string());
+    let hex = color.trim_start_matches('#');
+    let value = u32::from_str_radix(hex, 16).map_err(|_| invalid())?;
+    let channel = |shift: u32| ((value >> shift) & 0xff) as f32 / 255.;
+
+    match hex.len() {
+        6 => Ok(Vec4::new(channel(16), channel(8), channel(0), 1.)),
+        8 => Ok(Vec4::new(channel(16), channel(8), channel(0), channel(24))),
+        _ => Err(invalid()),
+    }
+}
+
+/// Decode the data of a tile layer or a chunk into gids.
+pub fn decode_tile_data(
+    data: &str,
+    encoding: Option<&str>,
+    compression: Option<&str>,
+) -> Result<Vec<u32>, TiledError> {
+    match encoding {
+        Some("csv") => data
+            .split(',')
+            .map(|gid| gid.trim())
+            .filter(|gid| !gid.is_empty())
+            .map(|gid| {
+                gid.parse::<u32>()
+                    .map_err(|_| TiledError::InvalidValue("data", gid.to_string()))
+            })
+            .collect(),
+        Some("base64") => {
+            let bytes = base64::engine::general_purpose::STANDARD
+                .decode(data.trim())
+                .map_err(|e| TiledError::InvalidValue("data", e.to_string()))?;
+            let bytes = decompress(bytes, compression)?;
+            Ok(bytes
+                .chunks_exact(4)
+                .map(|gid| u32::from_le_bytes([gid[0], gid[1], gid[2], gid[3]]))
+                .collect())
+        }
+        Some(encoding) => Err(TiledError::Unsupported(format!("{} encoding", encoding))),
+        None => Err(TiledError::MissingAttribute("encoding")),
+    }
+}
+
+fn decompress(bytes: Vec<u8>, compression: Option<&str>) -> Result<Vec<u8>, TiledError> {
+    let mut result = Vec::new();
+    let read = match compression {
+        None | Some("") => return Ok(bytes),
+        Some("zlib") => flate2::read::ZlibDecoder::new(bytes.as_slice()).read_to_end(&mut result),
+        Some("gzip") => flate2::read::GzDecoder::new(bytes.as_slice()).read_to_end(&mut result),
+        Some(compression) => {
+            return Err(TiledError::Unsupported(format!(
+                "{} compression",
+                compression
+            )))
+        }
+    };
+
+    read.map_err(|e| TiledError::InvalidValue("data", e.to_string()))?;
+    Ok(result)
+}
+
+/// Collect the non-empty tiles of a rectangle of gids.
+pub fn collect_tiles(gids: Vec<u32>, origin: IVec2, width: u32, tiles: &mut Vec<(IVec2, u32)>) {
+    if width == 0 {
+        return;
+    }
+
+    gids.into_iter()
+        .enumerate()
+        .filter(|(_, gid)| *gid != 0)
+        .for_each(|(i, gid)| {
+            let i = i as i32;
+            let width = width as i32;
+            tiles.push((origin + IVec2::new(i % width, i / width), gid));
+        });
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_decode_tile_data() {
+        assert_eq!(
+            decode_tile_data("\n1,2,\n0,2147483651\n", Some("csv"), None).unwrap(),
+            vec![1, 2, 0, 2147483651]
+        );
+        // 1, 0, 3 as little endian u32s.
+        assert_eq!(
+            decode_tile_data("AQAAAAAAAAADAAAA", Some("base64"), None).unwrap(),
+            vec![1, 0, 3]
+        );
+        assert!(decode_tile_data("", Some("base64"), Some("zstd")).is_err());
+    }
+
+    #[test]
+    fn test_resolve_path() {
+        assert_eq!(
+            resolve_path(Path::new("maps"), "../tilesets/./terrain.tsx"),
+            PathBuf::from("tilesets/terrain.tsx")
+        );
+        assert_eq!(
+            parse_color("#80ff0000").unwrap(),
+            Vec4::new(1., 0., 0., 128. / 255.)
+        );
+    }
+}
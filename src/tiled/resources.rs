@@ -0,0 +1,115 @@
+use std::path::Path;
+
+use bevy::{
+    ecs::{
+        entity::Entity,
+        system::{Commands, Resource},
+    },
+    log::error,
+    math::Vec2,
+    reflect::Reflect,
+    render::render_resource::FilterMode,
+    utils::HashMap,
+};
+
+use crate::tilemap::map::TilemapTextureMode;
+
+use super::{
+    components::{TiledLoader, TiledUnloader},
+    map::{TiledError, TiledMap},
+    parse::TiledReader,
+};
+
+#[derive(Resource, Default, Reflect)]
+pub struct TiledLoadConfig {
+    /// The folder that contains the maps, relative to the working directory.
+    pub map_folder: String,
+    /// The asset path of `map_folder`, used to load the images of the tilesets.
+    pub asset_path_prefix: String,
+    #[reflect(ignore)]
+    pub filter_mode: FilterMode,
+    /// The mode of the textures of all the tilesets.
+    pub texture_mode: TilemapTextureMode,
+    /// The z index of the bottom layer. Each layer above it is one higher.
+    pub z_index: i32,
+    /// Skip objects whose class isn't registered instead of failing the whole map.
+    /// Objects without a class are always skipped.
+    pub ignore_unregistered_objects: bool,
+}
+
+#[derive(Resource, Default, Reflect)]
+pub struct TiledMapManager {
+    /// Map name to the parsed map.
+    #[reflect(ignore)]
+    pub(crate) maps: HashMap<String, TiledMap>,
+    pub(crate) loaded_maps: HashMap<String, Entity>,
+}
+
+impl TiledMapManager {
+    /// Load a map. The name is the path of the `.tmx` or `.tmj` file relative to
+    /// `TiledLoadConfig::map_folder`.
+    ///
+    /// The top left corner of the map is placed at `trans_ovrd` or the origin.
+    pub fn load(&mut self, commands: &mut Commands, map: String, trans_ovrd: Option<Vec2>) {
+        if self.loaded_maps.contains_key(&map) {
+            error!("Trying to load {:?} that is already loaded!", map);
+        } else {
+            let entity = commands.spawn(TiledLoader {
+                map: map.clone(),
+                trans_ovrd,
+            });
+            self.loaded_maps.insert(map, entity.id());
+        }
+    }
+
+    pub fn switch_to(&mut self, commands: &mut Commands, map: String, trans_ovrd: Option<Vec2>) {
+        if self.loaded_maps.contains_key(&map) {
+            error!("Trying to load {:?} that is already loaded!", map);
+        } else {
+            self.unload_all(commands);
+            self.load(commands, map, trans_ovrd);
+        }
+    }
+
+    pub fn unload(&mut self, commands: &mut Commands, map: String) {
+        if let Some(entity) = self.loaded_maps.remove(&map) {
+            commands.entity(entity).insert(TiledUnloader);
+        } else {
+            error!("Trying to unload {:?} that is not loaded!", map);
+        }
+    }
+
+    pub fn unload_all(&mut self, commands: &mut Commands) {
+        for (_, entity) in self.loaded_maps.drain() {
+            commands.entity(entity).insert(TiledUnloader);
+        }
+    }
+
+    #[inline]
+    pub fn is_loaded(&self, map: String) -> bool {
+        self.loaded_maps.contains_key(&map)
+    }
+
+    /// Get the parsed map. Maps are parsed when they are loaded for the first time.
+    #[inline]
+    pub fn get_cached_map(&self, map: &str) -> Option<&TiledMap> {
+        self.maps.get(map)
+    }
+
+    /// Parse the map again, so the changes in the file are used the next time it's loaded.
+    pub fn reload(&mut self, config: &TiledLoadConfig, map: String) -> Result<(), TiledError> {
+        let reader = TiledReader {
+            root: Path::new(&config.map_folder),
+            asset_prefix: Path::new(&config.asset_path_prefix),
+        };
+        let parsed = reader.read_map(Path::new(&map))?;
+        self.maps.insert(map, parsed);
+        Ok(())
+    }
+
+    /// Remove all the parsed maps.
+    #[inline]
+    pub fn clear_cache(&mut self) {
+        self.maps.clear();
+    }
+}
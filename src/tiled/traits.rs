@@ -0,0 +1,67 @@
+use std::marker::PhantomData;
+
+use bevy::{
+    asset::AssetServer,
+    ecs::{
+        bundle::Bundle,
+        system::{EntityCommands, Resource},
+    },
+    prelude::{Deref, DerefMut},
+    utils::HashMap,
+};
+
+use super::map::{TiledMap, TiledObjectInstance};
+
+/// Class to the object components.
+#[derive(Resource, Default, Deref, DerefMut)]
+pub struct TiledObjectRegistry(pub HashMap<String, Box<dyn PhantomTiledObjectTrait>>);
+
+pub trait TiledObject {
+    /// The entity already has the `Transform` of the object and its `TiledObjectId`.
+    fn initialize(
+        commands: &mut EntityCommands,
+        object: &TiledObjectInstance,
+        map: &TiledMap,
+        asset_server: &AssetServer,
+    );
+}
+
+pub struct PhantomTiledObject<T: TiledObject + Bundle> {
+    pub marker: PhantomData<T>,
+}
+
+impl<T: TiledObject + Bundle> PhantomTiledObject<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl<T: TiledObject + Bundle> Default for PhantomTiledObject<T> {
+    fn default() -> Self {
+        Self {
+            marker: PhantomData::<T>,
+        }
+    }
+}
+
+pub trait PhantomTiledObjectTrait: Send + Sync {
+    fn spawn(
+        &self,
+        commands: &mut EntityCommands,
+        object: &TiledObjectInstance,
+        map: &TiledMap,
+        asset_server: &AssetServer,
+    );
+}
+
+impl<T: TiledObject + Bundle> PhantomTiledObjectTrait for PhantomTiledObject<T> {
+    fn spawn(
+        &self,
+        commands: &mut EntityCommands,
+        object: &TiledObjectInstance,
+        map: &TiledMap,
+        asset_server: &AssetServer,
+    ) {
+        T::initialize(commands, object, map, asset_server);
+    }
+}
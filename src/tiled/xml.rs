@@ -0,0 +1,355 @@
+use std::{path::Path, str::FromStr};
+
+use bevy::{
+    log::warn,
+    math::{IVec2, UVec2, Vec2, Vec4},
+    utils::HashMap,
+};
+use roxmltree::{Document, Node};
+
+use super::{
+    map::{
+        TiledError, TiledLayer, TiledLayerContent, TiledMap, TiledObjectInstance, TiledObjectShape,
+        TiledOrientation, TiledProperties, TiledProperty, TiledStaggerAxis, TiledStaggerIndex,
+        TiledTileInfo, TiledTileset,
+    },
+    parse::{collect_tiles, decode_tile_data, parse_color, resolve_path, TiledReader},
+};
+
+pub fn parse_map(reader: &TiledReader, base: &Path, source: &str) -> Result<TiledMap, TiledError> {
+    let document = Document::parse(source)?;
+    let root = document.root_element();
+
+    let orientation = match attr(root, "orientation")? {
+        "orthogonal" => TiledOrientation::Orthogonal,
+        "isometric" => TiledOrientation::Isometric,
+        "staggered" => TiledOrientation::Staggered,
+        "hexagonal" => TiledOrientation::Hexagonal,
+        other => return Err(TiledError::InvalidValue("orientation", other.to_string())),
+    };
+
+    let mut tilesets = elements(root, "tileset")
+        .map(|node| {
+            let first_gid = attr_parse(node, "firstgid")?;
+            match node.attribute("source") {
+                Some(source) => reader.read_tileset(&resolve_path(base, source), first_gid),
+                None => parse_tileset(reader, base, node, first_gid),
+            }
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    tilesets.sort_by_key(|tileset| tileset.first_gid);
+
+    Ok(TiledMap {
+        orientation,
+        size: UVec2::new(attr_parse(root, "width")?, attr_parse(root, "height")?),
+        tile_size: UVec2::new(
+            attr_parse(root, "tilewidth")?,
+            attr_parse(root, "tileheight")?,
+        ),
+        infinite: attr_or(root, "infinite", 0u32)? != 0,
+        hex_side_length: attr_or(root, "hexsidelength", 0)?,
+        stagger_axis: match root.attribute("staggeraxis") {
+            Some("x") => TiledStaggerAxis::X,
+            _ => TiledStaggerAxis::Y,
+        },
+        stagger_index: match root.attribute("staggerindex") {
+            Some("even") => TiledStaggerIndex::Even,
+            _ => TiledStaggerIndex::Odd,
+        },
+        background_color: root
+            .attribute("backgroundcolor")
+            .map(parse_color)
+            .transpose()?,
+        tilesets,
+        layers: parse_layers(reader, base, root)?,
+        properties: parse_properties(root)?,
+    })
+}
+
+pub fn parse_tileset_file(
+    reader: &TiledReader,
+    base: &Path,
+    source: &str,
+    first_gid: u32,
+) -> Result<TiledTileset, TiledError> {
+    let document = Document::parse(source)?;
+    parse_tileset(reader, base, document.root_element(), first_gid)
+}
+
+fn parse_tileset(
+    reader: &TiledReader,
+    base: &Path,
+    node: Node,
+    first_gid: u32,
+) -> Result<TiledTileset, TiledError> {
+    let image = child(node, "image")
+        .map(|image| {
+            Ok::<_, TiledError>(reader.image(
+                base,
+                attr(image, "source")?,
+                UVec2::new(attr_parse(image, "width")?, attr_parse(image, "height")?),
+            ))
+        })
+        .transpose()?;
+
+    let tile_offset = child(node, "tileoffset")
+        .map(|offset| {
+            Ok::<_, TiledError>(Vec2::new(
+                attr_or(offset, "x", 0.)?,
+                attr_or(offset, "y", 0.)?,
+            ))
+        })
+        .transpose()?
+        .unwrap_or_default();
+
+    let mut tiles = HashMap::new();
+    for tile in elements(node, "tile") {
+        let animation = child(tile, "animation")
+            .map(|animation| {
+                elements(animation, "frame")
+                    .map(|frame| Ok((attr_parse(frame, "tileid")?, attr_parse(frame, "duration")?)))
+                    .collect::<Result<Vec<_>, TiledError>>()
+            })
+            .transpose()?
+            .unwrap_or_default();
+
+        tiles.insert(
+            attr_parse(tile, "id")?,
+            TiledTileInfo {
+                class: class(tile),
+                properties: parse_properties(tile)?,
+                animation,
+            },
+        );
+    }
+
+    Ok(TiledTileset {
+        first_gid,
+        name: attr_or(node, "name", String::new())?,
+        tile_size: UVec2::new(
+            attr_parse(node, "tilewidth")?,
+            attr_parse(node, "tileheight")?,
+        ),
+        spacing: attr_or(node, "spacing", 0)?,
+        margin: attr_or(node, "margin", 0)?,
+        tile_count: attr_or(node, "tilecount", 0)?,
+        columns: attr_or(node, "columns", 0)?,
+        image,
+        tile_offset,
+        tiles,
+        properties: parse_properties(node)?,
+    })
+}
+
+fn parse_layers(
+    reader: &TiledReader,
+    base: &Path,
+    parent: Node,
+) -> Result<Vec<TiledLayer>, TiledError> {
+    parent
+        .children()
+        .filter(|node| node.is_element())
+        .filter_map(|node| {
+            let content = match node.tag_name().name() {
+                "layer" => parse_tile_layer(node),
+                "objectgroup" => parse_objects(node).map(TiledLayerContent::Objects),
+                "imagelayer" => Ok(TiledLayerContent::Image(child(node, "image").and_then(
+                    |image| {
+                        let source = image.attribute("source")?;
+                        let size = UVec2::new(
+                            image.attribute("width")?.parse().ok()?,
+                            image.attribute("height")?.parse().ok()?,
+                        );
+                        Some(reader.image(base, source, size))
+                    },
+                ))),
+                "group" => parse_layers(reader, base, node).map(TiledLayerContent::Group),
+                _ => return None,
+            };
+            Some(content.and_then(|content| parse_layer(node, content)))
+        })
+        .collect()
+}
+
+fn parse_layer(node: Node, content: TiledLayerContent) -> Result<TiledLayer, TiledError> {
+    Ok(TiledLayer {
+        id: attr_or(node, "id", 0)?,
+        name: attr_or(node, "name", String::new())?,
+        class: class(node),
+        visible: attr_or(node, "visible", 1u32)? != 0,
+        opacity: attr_or(node, "opacity", 1.)?,
+        tint: node
+            .attribute("tintcolor")
+            .map(parse_color)
+            .transpose()?
+            .unwrap_or(Vec4::ONE),
+        offset: Vec2::new(attr_or(node, "offsetx", 0.)?, attr_or(node, "offsety", 0.)?),
+        properties: parse_properties(node)?,
+        content,
+    })
+}
+
+fn parse_tile_layer(node: Node) -> Result<TiledLayerContent, TiledError> {
+    let mut tiles = Vec::new();
+    let Some(data) = child(node, "data") else {
+        return Ok(TiledLayerContent::Tiles(tiles));
+    };
+
+    let encoding = data.attribute("encoding");
+    let compression = data.attribute("compression");
+    let decode = |node: Node| -> Result<Vec<u32>, TiledError> {
+        match encoding {
+            Some(_) => decode_tile_data(node.text().unwrap_or_default(), encoding, compression),
+            // The deprecated xml format.
+            None => elements(node, "tile")
+                .map(|tile| attr_or(tile, "gid", 0))
+                .collect(),
+        }
+    };
+
+    let chunks = elements(data, "chunk").collect::<Vec<_>>();
+    if chunks.is_empty() {
+        collect_tiles(
+            decode(data)?,
+            IVec2::ZERO,
+            attr_parse(node, "width")?,
+            &mut tiles,
+        );
+    } else {
+        for chunk in chunks {
+            collect_tiles(
+                decode(chunk)?,
+                IVec2::new(attr_parse(chunk, "x")?, attr_parse(chunk, "y")?),
+                attr_parse(chunk, "width")?,
+                &mut tiles,
+            );
+        }
+    }
+
+    Ok(TiledLayerContent::Tiles(tiles))
+}
+
+fn parse_objects(node: Node) -> Result<Vec<TiledObjectInstance>, TiledError> {
+    elements(node, "object")
+        .filter_map(|object| {
+            if object.has_attribute("template") {
+                warn!("Object templates are not supported! Skipping the object.");
+                return None;
+            }
+            Some(parse_object(object))
+        })
+        .collect()
+}
+
+fn parse_object(node: Node) -> Result<TiledObjectInstance, TiledError> {
+    let shape = if child(node, "ellipse").is_some() {
+        TiledObjectShape::Ellipse
+    } else if child(node, "point").is_some() {
+        TiledObjectShape::Point
+    } else if let Some(polygon) = child(node, "polygon") {
+        TiledObjectShape::Polygon(parse_points(attr(polygon, "points")?)?)
+    } else if let Some(polyline) = child(node, "polyline") {
+        TiledObjectShape::Polyline(parse_points(attr(polyline, "points")?)?)
+    } else if let Some(text) = child(node, "text") {
+        TiledObjectShape::Text(text.text().unwrap_or_default().to_string())
+    } else {
+        TiledObjectShape::Rectangle
+    };
+
+    Ok(TiledObjectInstance {
+        id: attr_parse(node, "id")?,
+        name: attr_or(node, "name", String::new())?,
+        class: class(node),
+        position: Vec2::new(attr_or(node, "x", 0.)?, attr_or(node, "y", 0.)?),
+        size: Vec2::new(attr_or(node, "width", 0.)?, attr_or(node, "height", 0.)?),
+        rotation: attr_or(node, "rotation", 0.)?,
+        gid: node
+            .attribute("gid")
+            .map(|gid| parse("gid", gid))
+            .transpose()?,
+        visible: attr_or(node, "visible", 1u32)? != 0,
+        shape,
+        properties: parse_properties(node)?,
+    })
+}
+
+fn parse_points(points: &str) -> Result<Vec<Vec2>, TiledError> {
+    points
+        .split_whitespace()
+        .map(|point| {
+            let (x, y) = point
+                .split_once(',')
+                .ok_or_else(|| TiledError::InvalidValue("points", point.to_string()))?;
+            Ok(Vec2::new(parse("points", x)?, parse("points", y)?))
+        })
+        .collect()
+}
+
+fn parse_properties(node: Node) -> Result<TiledProperties, TiledError> {
+    let Some(properties) = child(node, "properties") else {
+        return Ok(TiledProperties::default());
+    };
+
+    elements(properties, "property")
+        .map(|property| {
+            let value = property
+                .attribute("value")
+                .or_else(|| property.text())
+                .unwrap_or_default();
+            let value = match property.attribute("type").unwrap_or("string") {
+                "bool" => TiledProperty::Bool(value == "true"),
+                "int" => TiledProperty::Int(parse("value", value)?),
+                "float" => TiledProperty::Float(parse("value", value)?),
+                "color" if value.is_empty() => TiledProperty::Color(Vec4::ZERO),
+                "color" => TiledProperty::Color(parse_color(value)?),
+                "file" => TiledProperty::File(value.to_string()),
+                "object" => TiledProperty::Object(parse("value", value)?),
+                "class" => TiledProperty::Class(parse_properties(property)?),
+                _ => TiledProperty::String(value.to_string()),
+            };
+            Ok((attr(property, "name")?.to_string(), value))
+        })
+        .collect()
+}
+
+/// Tiled 1.9 renamed `type` to `class`.
+fn class(node: Node) -> String {
+    node.attribute("class")
+        .or_else(|| node.attribute("type"))
+        .unwrap_or_default()
+        .to_string()
+}
+
+fn elements<'a, 'input>(
+    node: Node<'a, 'input>,
+    name: &'static str,
+) -> impl Iterator<Item = Node<'a, 'input>> {
+    node.children()
+        .filter(move |child| child.has_tag_name(name))
+}
+
+fn child<'a, 'input>(node: Node<'a, 'input>, name: &'static str) -> Option<Node<'a, 'input>> {
+    elements(node, name).next()
+}
+
+fn attr<'a>(node: Node<'a, '_>, name: &'static str) -> Result<&'a str, TiledError> {
+    node.attribute(name)
+        .ok_or(TiledError::MissingAttribute(name))
+}
+
+fn attr_parse<T: FromStr>(node: Node, name: &'static str) -> Result<T, TiledError> {
+    parse(name, attr(node, name)?)
+}
+
+fn attr_or<T: FromStr>(node: Node, name: &'static str, default: T) -> Result<T, TiledError> {
+    node.attribute(name)
+        .map(|value| parse(name, value))
+        .unwrap_or(Ok(default))
+}
+
+fn parse<T: FromStr>(name: &'static str, value: &str) -> Result<T, TiledError> {
+    value
+        .trim()
+        .parse()
+        .map_err(|_| TiledError::InvalidValue(name, value.to_string()))
+}
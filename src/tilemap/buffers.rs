@@ -11,6 +11,7 @@ pub trait Tiles: Debug + Clone + Reflect {}
 
 pub type ColorTileBuffer = TileBuffer<Tile>;
 pub type TileBuilderBuffer = TileBuffer<TileBuilder>;
+pub type FogTileBuffer = TileBuffer<super::fog::FogState>;
 #[cfg(feature = "algorithm")]
 pub type PathTileBuffer = TileBuffer<super::algorithm::path::PathTile>;
 #[cfg(feature = "physics")]
@@ -0,0 +1,83 @@
+use std::ops::{Add, Div};
+
+use bevy::{
+    ecs::{entity::Entity, event::Event},
+    math::IVec2,
+};
+
+use crate::tilemap::map::TilemapType;
+
+/// Sent when the values at the corners of the tiles in a chunk changed,
+/// and the render chunk needs to be updated.
+///
+/// Each element of `corners` holds the values at the 4 vertices of a tile,
+/// in the order of bottom left, top left, top right and bottom right.
+#[derive(Event, Debug, Clone)]
+pub struct ChunkCornersUpdate<T: Send + Sync + 'static> {
+    pub tilemap: Entity,
+    pub index: IVec2,
+    pub corners: Vec<[T; 4]>,
+}
+
+/// Get the values at the corners of each tile in the chunk, using `get` to read the value of a tile.
+///
+/// If `smooth_edges` is enabled, each corner is the average of the 4 tiles sharing it.
+/// This only works for square tilemaps, as the meshes of isometric and hexagonal tiles
+/// are not aligned with the grid. The tiles of these tilemaps always have flat values.
+pub fn get_chunk_corners<T>(
+    chunk_index: IVec2,
+    chunk_size: u32,
+    ty: TilemapType,
+    smooth_edges: bool,
+    get: impl Fn(IVec2) -> T,
+) -> Vec<[T; 4]>
+where
+    T: Copy + Add<Output = T> + Div<f32, Output = T>,
+{
+    let chunk_size = chunk_size as i32;
+    let origin = chunk_index * chunk_size;
+    let smooth_edges = smooth_edges && ty == TilemapType::Square;
+
+    (0..chunk_size * chunk_size)
+        .map(|i| {
+            let index = origin + IVec2::new(i % chunk_size, i / chunk_size);
+            if !smooth_edges {
+                return [get(index); 4];
+            }
+
+            let corner = |offset: IVec2| {
+                let base = index + offset;
+                (get(base + IVec2::new(-1, -1))
+                    + get(base + IVec2::new(0, -1))
+                    + get(base + IVec2::new(-1, 0))
+                    + get(base))
+                    / 4.
+            };
+
+            [
+                corner(IVec2::new(0, 0)),
+                corner(IVec2::new(0, 1)),
+                corner(IVec2::new(1, 1)),
+                corner(IVec2::new(1, 0)),
+            ]
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_non_square_corners() {
+        let get = |index: IVec2| if index == IVec2::ONE { 1. } else { 0. };
+
+        let square = get_chunk_corners(IVec2::ZERO, 2, TilemapType::Square, true, get);
+        assert_eq!(square[3], [0.25; 4]);
+        assert_eq!(square[0], [0., 0., 0.25, 0.]);
+
+        let isometric = get_chunk_corners(IVec2::ZERO, 2, TilemapType::Isometric, true, get);
+        assert_eq!(isometric[3], [1.; 4]);
+        assert_eq!(isometric[0], [0.; 4]);
+    }
+}
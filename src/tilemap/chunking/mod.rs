@@ -1,2 +1,3 @@
 pub mod camera;
+pub mod corners;
 pub mod storage;
@@ -14,6 +14,7 @@ pub type InChunkIndex = usize;
 pub type EntityChunkedStorage = ChunkedStorage<Entity>;
 pub type ColorTileChunkedStorage = ChunkedStorage<Tile>;
 pub type TileBuilderChunkedStorage = ChunkedStorage<TileBuilder>;
+pub type FogTileChunkedStorage = ChunkedStorage<crate::tilemap::fog::FogState>;
 #[cfg(feature = "algorithm")]
 pub type PathTileChunkedStorage = ChunkedStorage<crate::tilemap::algorithm::path::PathTile>;
 #[cfg(feature = "physics")]
@@ -1,5 +1,7 @@
 use bevy::math::{IVec2, UVec2, Vec2};
 
+use crate::math::aabb::Aabb2d;
+
 use super::map::{TilemapTransform, TilemapType};
 
 /// Get the world position of the center of a slot.
@@ -30,6 +32,42 @@ pub fn index_to_world(
     })
 }
 
+/// Get the position of the mesh origin of a tile relative to the tilemap.
+/// This is the same as `get_mesh_origin` in the shaders.
+pub fn index_to_mesh_origin(index: IVec2, ty: &TilemapType, slot_size: Vec2) -> Vec2 {
+    let index = index.as_vec2();
+    match ty {
+        TilemapType::Square => index * slot_size,
+        TilemapType::Isometric => {
+            Vec2 {
+                x: (index.x - index.y),
+                y: (index.x + index.y),
+            } / 2.
+                * slot_size
+        }
+        TilemapType::Hexagonal(legs) => Vec2 {
+            x: slot_size.x * (index.x - 0.5 * index.y),
+            y: (slot_size.y + *legs as f32) / 2. * index.y,
+        },
+    }
+}
+
+/// Get the area a tile covers when it's rendered.
+pub fn get_tile_render_aabb(
+    index: IVec2,
+    ty: &TilemapType,
+    transform: &TilemapTransform,
+    render_size: Vec2,
+    anchor: Vec2,
+    slot_size: Vec2,
+) -> Aabb2d {
+    let min = index_to_mesh_origin(index, ty, slot_size) - anchor * render_size;
+    transform.transform_aabb(Aabb2d {
+        min,
+        max: min + render_size,
+    })
+}
+
 pub fn index_to_rel(
     index: IVec2,
     ty: &TilemapType,
@@ -143,3 +181,37 @@ pub fn get_tile_collider_world(
         .map(|v| v + offset)
         .collect()
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_tile_render_aabb() {
+        // A 16x32 tree anchored at the bottom center of a 16x16 slot.
+        let aabb = get_tile_render_aabb(
+            IVec2::new(1, 0),
+            &TilemapType::Square,
+            &TilemapTransform::default(),
+            Vec2::new(16., 32.),
+            Vec2::new(0.5, 0.),
+            Vec2::splat(16.),
+        );
+        assert_eq!(aabb.min, Vec2::new(8., 0.));
+        assert_eq!(aabb.max, Vec2::new(24., 32.));
+
+        let aabb = get_tile_render_aabb(
+            IVec2::new(1, 1),
+            &TilemapType::Isometric,
+            &TilemapTransform {
+                translation: Vec2::new(100., 50.),
+                ..Default::default()
+            },
+            Vec2::new(32., 48.),
+            Vec2::new(0.5, 0.25),
+            Vec2::new(32., 16.),
+        );
+        assert_eq!(aabb.min, Vec2::new(84., 54.));
+        assert_eq!(aabb.max, Vec2::new(116., 102.));
+    }
+}
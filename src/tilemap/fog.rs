@@ -0,0 +1,275 @@
+use bevy::{
+    ecs::{
+        change_detection::DetectChangesMut, component::Component, entity::Entity,
+        event::EventWriter, query::Changed, system::Query,
+    },
+    math::IVec2,
+    reflect::Reflect,
+    utils::HashSet,
+};
+
+use crate::math::TileArea;
+
+use super::{
+    buffers::{FogTileBuffer, Tiles},
+    chunking::{
+        corners::{self, ChunkCornersUpdate},
+        storage::{ChunkedStorage, FogTileChunkedStorage},
+    },
+    map::TilemapType,
+};
+
+/// The exploration state of a tile.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Reflect)]
+#[cfg_attr(feature = "serializing", derive(serde::Serialize, serde::Deserialize))]
+pub enum FogState {
+    /// Never seen. Tiles without fog data are also treated as unexplored.
+    #[default]
+    Unexplored,
+    /// Seen before but not currently in sight.
+    Explored,
+    /// Currently in sight.
+    Visible,
+}
+
+impl Tiles for FogState {}
+
+/// Sent when the fog of a chunk changed and the render chunk needs to be updated.
+/// The corners hold the brightness of the vertices of each tile.
+pub type FogChunkUpdate = ChunkCornersUpdate<f32>;
+
+/// The fog of war of a tilemap. Add this to the tilemap entity.
+///
+/// The brightness of each tile is multiplied to the final color in the fragment shader.
+#[derive(Component, Debug, Clone, Reflect)]
+#[cfg_attr(feature = "serializing", derive(serde::Serialize, serde::Deserialize))]
+pub struct FogTilemap {
+    pub(crate) storage: FogTileChunkedStorage,
+    pub(crate) unexplored_brightness: f32,
+    pub(crate) explored_brightness: f32,
+    pub(crate) smooth_edges: bool,
+    #[cfg_attr(feature = "serializing", serde(skip))]
+    pub(crate) dirty_chunks: HashSet<IVec2>,
+}
+
+impl Default for FogTilemap {
+    fn default() -> Self {
+        Self {
+            storage: ChunkedStorage::default(),
+            unexplored_brightness: 0.,
+            explored_brightness: 0.5,
+            smooth_edges: true,
+            dirty_chunks: HashSet::new(),
+        }
+    }
+}
+
+impl FogTilemap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The chunk size should be the same as the one of `TilemapStorage`.
+    pub fn new_with_chunk_size(chunk_size: u32) -> Self {
+        Self {
+            storage: ChunkedStorage::new(chunk_size),
+            ..Default::default()
+        }
+    }
+
+    /// Set the brightness of unexplored and explored tiles. Visible tiles are always `1`.
+    pub fn with_brightness(mut self, unexplored: f32, explored: f32) -> Self {
+        self.unexplored_brightness = unexplored;
+        self.explored_brightness = explored;
+        self
+    }
+
+    /// Blend the brightness of adjacent tiles at the corners of each tile.
+    /// Only square tilemaps have smooth edges.
+    pub fn with_smooth_edges(mut self, smooth_edges: bool) -> Self {
+        self.smooth_edges = smooth_edges;
+        self
+    }
+
+    #[inline]
+    pub fn get(&self, index: IVec2) -> FogState {
+        self.storage.get_elem(index).cloned().unwrap_or_default()
+    }
+
+    #[inline]
+    pub fn get_brightness(&self, index: IVec2) -> f32 {
+        match self.get(index) {
+            FogState::Unexplored => self.unexplored_brightness,
+            FogState::Explored => self.explored_brightness,
+            FogState::Visible => 1.,
+        }
+    }
+
+    pub fn set(&mut self, index: IVec2, state: FogState) {
+        self.storage.set_elem(index, state);
+        self.mark_dirty(index);
+    }
+
+    pub fn remove(&mut self, index: IVec2) -> Option<FogState> {
+        self.mark_dirty(index);
+        self.storage.remove_elem(index)
+    }
+
+    pub fn remove_chunk(&mut self, index: IVec2) -> Option<Vec<Option<FogState>>> {
+        self.dirty_chunks.insert(index);
+        self.storage.remove_chunk(index)
+    }
+
+    pub fn fill_rect(&mut self, area: TileArea, state: FogState) {
+        for y in area.origin.y..=area.dest.y {
+            for x in area.origin.x..=area.dest.x {
+                self.set(IVec2 { x, y }, state);
+            }
+        }
+    }
+
+    pub fn fill_with_buffer(&mut self, origin: IVec2, buffer: FogTileBuffer) {
+        buffer.tiles.into_iter().for_each(|(index, state)| {
+            self.set(index + origin, state);
+        });
+    }
+
+    /// Mark tiles as `Visible`.
+    #[inline]
+    pub fn reveal(&mut self, area: TileArea) {
+        self.fill_rect(area, FogState::Visible);
+    }
+
+    /// Turn all the `Visible` tiles into `Explored`.
+    ///
+    /// This is useful when you recalculate the sight every frame.
+    pub fn hide_visible(&mut self) {
+        let chunk_size = self.storage.chunk_size;
+        let mut dirty = Vec::new();
+        self.storage
+            .chunks
+            .iter_mut()
+            .for_each(|(chunk_index, chunk)| {
+                chunk
+                    .iter_mut()
+                    .enumerate()
+                    .for_each(|(in_chunk_index, state)| {
+                        if *state == Some(FogState::Visible) {
+                            *state = Some(FogState::Explored);
+                            dirty.push(
+                                *chunk_index * chunk_size as i32
+                                    + IVec2 {
+                                        x: in_chunk_index as i32 % chunk_size as i32,
+                                        y: in_chunk_index as i32 / chunk_size as i32,
+                                    },
+                            );
+                        }
+                    });
+            });
+        dirty.into_iter().for_each(|index| self.mark_dirty(index));
+    }
+
+    /// Resend the fog of every chunk to the renderer.
+    pub fn mark_all_dirty(&mut self) {
+        self.dirty_chunks.extend(self.storage.chunks.keys());
+    }
+
+    #[inline]
+    pub(crate) fn set_chunk(&mut self, index: IVec2, chunk: Vec<Option<FogState>>) {
+        self.storage.set_chunk(index, chunk);
+        self.dirty_chunks.insert(index);
+        self.dirty_chunks
+            .extend((-1..=1).flat_map(|y| (-1..=1).map(move |x| index + IVec2 { x, y })));
+    }
+
+    /// Marks the chunk of the tile and the chunks of its neighbours
+    /// as their corners may be affected.
+    fn mark_dirty(&mut self, index: IVec2) {
+        for y in -1..=1 {
+            for x in -1..=1 {
+                let (chunk_index, _) = self.storage.transform_index(index + IVec2 { x, y });
+                self.dirty_chunks.insert(chunk_index);
+            }
+        }
+    }
+
+    fn get_chunk_corners(&self, chunk_index: IVec2, ty: TilemapType) -> Vec<[f32; 4]> {
+        corners::get_chunk_corners(
+            chunk_index,
+            self.storage.chunk_size,
+            ty,
+            self.smooth_edges,
+            |index| self.get_brightness(index),
+        )
+    }
+}
+
+pub fn fog_updater(
+    mut tilemaps_query: Query<(Entity, &TilemapType, &mut FogTilemap), Changed<FogTilemap>>,
+    mut fog_update: EventWriter<FogChunkUpdate>,
+) {
+    tilemaps_query.for_each_mut(|(entity, ty, mut fog)| {
+        if fog.dirty_chunks.is_empty() {
+            return;
+        }
+
+        let dirty = std::mem::take(&mut fog.bypass_change_detection().dirty_chunks);
+        fog_update.send_batch(dirty.into_iter().map(|index| FogChunkUpdate {
+            tilemap: entity,
+            index,
+            corners: fog.get_chunk_corners(index, *ty),
+        }));
+    });
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn create_fog() -> FogTilemap {
+        let mut fog = FogTilemap::new_with_chunk_size(4).with_brightness(0., 0.5);
+        fog.set(IVec2::new(1, 1), FogState::Visible);
+        fog.set(IVec2::new(1, 2), FogState::Explored);
+        fog
+    }
+
+    #[test]
+    fn test_fog_corners() {
+        let fog = create_fog();
+        let corners = fog.get_chunk_corners(IVec2::ZERO, TilemapType::Square);
+
+        // Bottom left, top left, top right and bottom right of (1, 1).
+        assert_eq!(corners[5], [0.25, 0.375, 0.375, 0.25]);
+        assert_eq!(corners[0], [0., 0., 0.25, 0.]);
+    }
+
+    #[test]
+    fn test_fog_chunk_round_trip() {
+        let mut fog = create_fog();
+        let corners = fog.get_chunk_corners(IVec2::ZERO, TilemapType::Square);
+
+        let chunk = fog.remove_chunk(IVec2::ZERO).unwrap();
+        assert_eq!(fog.get(IVec2::new(1, 1)), FogState::Unexplored);
+
+        let mut loaded = FogTilemap::new_with_chunk_size(4).with_brightness(0., 0.5);
+        loaded.set_chunk(IVec2::ZERO, chunk);
+        assert!(loaded.dirty_chunks.contains(&IVec2::ZERO));
+        assert_eq!(
+            loaded.get_chunk_corners(IVec2::ZERO, TilemapType::Square),
+            corners
+        );
+    }
+
+    #[cfg(feature = "serializing")]
+    #[test]
+    fn test_fog_serde_round_trip() {
+        let fog = create_fog();
+        let corners = fog.get_chunk_corners(IVec2::ZERO, TilemapType::Square);
+
+        let loaded = ron::from_str::<FogTilemap>(&ron::to_string(&fog).unwrap()).unwrap();
+        assert_eq!(
+            loaded.get_chunk_corners(IVec2::ZERO, TilemapType::Square),
+            corners
+        );
+    }
+}
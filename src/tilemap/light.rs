@@ -0,0 +1,315 @@
+use std::collections::VecDeque;
+
+use bevy::{
+    ecs::{
+        change_detection::DetectChangesMut, component::Component, entity::Entity,
+        event::EventWriter, query::Changed, system::Query,
+    },
+    math::{IVec2, Vec3},
+    reflect::Reflect,
+    utils::{HashMap, HashSet},
+};
+
+use crate::math::{aabb::IAabb2d, extension::DivToFloor};
+
+use super::{
+    chunking::{
+        corners::{self, ChunkCornersUpdate},
+        storage::ChunkedStorage,
+    },
+    map::TilemapType,
+};
+
+/// A light emitted from a tile.
+#[derive(Debug, Clone, Copy, Reflect)]
+pub struct TileLight {
+    pub color: Vec3,
+    /// How many tiles the light can reach.
+    pub radius: u32,
+}
+
+impl TileLight {
+    pub fn new(color: Vec3, radius: u32) -> Self {
+        Self { color, radius }
+    }
+
+    /// The light level at `distance` tiles away from the source.
+    #[inline]
+    pub fn level_at(&self, distance: u32) -> Vec3 {
+        self.color * (1. - distance as f32 / (self.radius + 1) as f32)
+    }
+}
+
+/// Sent when the light of a chunk changed and the render chunk needs to be updated.
+/// The corners hold the light of the vertices of each tile.
+pub type LightChunkUpdate = ChunkCornersUpdate<Vec3>;
+
+/// The lighting of a tilemap. Add this to the tilemap entity.
+///
+/// Lights are flood-filled from the sources and blocked by opaque tiles.
+/// Opaque tiles still receive light, but won't pass it on.
+/// The light level of each channel is the maximum of all the lights reaching the tile.
+#[derive(Component, Debug, Clone, Reflect)]
+pub struct LightTilemap {
+    pub(crate) ambient: Vec3,
+    pub(crate) smooth_edges: bool,
+    pub(crate) sources: HashMap<IVec2, TileLight>,
+    pub(crate) opaque: ChunkedStorage<bool>,
+    pub(crate) levels: ChunkedStorage<Vec3>,
+    pub(crate) dirty_areas: Vec<IAabb2d>,
+}
+
+impl Default for LightTilemap {
+    fn default() -> Self {
+        Self {
+            ambient: Vec3::ZERO,
+            smooth_edges: true,
+            sources: HashMap::new(),
+            opaque: ChunkedStorage::default(),
+            levels: ChunkedStorage::default(),
+            dirty_areas: Vec::new(),
+        }
+    }
+}
+
+impl LightTilemap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The chunk size should be the same as the one of `TilemapStorage`.
+    pub fn new_with_chunk_size(chunk_size: u32) -> Self {
+        Self {
+            opaque: ChunkedStorage::new(chunk_size),
+            levels: ChunkedStorage::new(chunk_size),
+            ..Default::default()
+        }
+    }
+
+    /// The light level of tiles that no light can reach.
+    pub fn with_ambient(mut self, ambient: Vec3) -> Self {
+        self.ambient = ambient;
+        self
+    }
+
+    /// Blend the light of adjacent tiles at the corners of each tile.
+    /// Only square tilemaps have smooth edges.
+    pub fn with_smooth_edges(mut self, smooth_edges: bool) -> Self {
+        self.smooth_edges = smooth_edges;
+        self
+    }
+
+    /// Get the calculated light level of a tile.
+    ///
+    /// The changes you made this frame will be applied in `PostUpdate`.
+    #[inline]
+    pub fn get_level(&self, index: IVec2) -> Vec3 {
+        self.levels.get_elem(index).cloned().unwrap_or(self.ambient)
+    }
+
+    #[inline]
+    pub fn get_light(&self, index: IVec2) -> Option<&TileLight> {
+        self.sources.get(&index)
+    }
+
+    pub fn set_light(&mut self, index: IVec2, light: TileLight) {
+        let previous = self.sources.insert(index, light);
+        let radius = previous.map_or(0, |l| l.radius).max(light.radius);
+        self.mark_dirty(index, radius);
+    }
+
+    pub fn remove_light(&mut self, index: IVec2) -> Option<TileLight> {
+        let previous = self.sources.remove(&index);
+        if let Some(light) = &previous {
+            self.mark_dirty(index, light.radius);
+        }
+        previous
+    }
+
+    #[inline]
+    pub fn is_opaque(&self, index: IVec2) -> bool {
+        self.opaque.get_elem(index).cloned().unwrap_or_default()
+    }
+
+    pub fn set_opaque(&mut self, index: IVec2, opaque: bool) {
+        if self.is_opaque(index) == opaque {
+            return;
+        }
+
+        if opaque {
+            self.opaque.set_elem(index, true);
+        } else {
+            self.opaque.remove_elem(index);
+        }
+        // A blocker only affects the tiles that lights passing through it can reach.
+        self.mark_dirty(index, self.max_radius());
+    }
+
+    /// Recalculate the light of the whole tilemap.
+    pub fn mark_all_dirty(&mut self) {
+        let areas = self
+            .sources
+            .iter()
+            .map(|(index, light)| Self::light_area(*index, light.radius))
+            .chain(self.levels.chunks.keys().map(|chunk_index| IAabb2d {
+                min: *chunk_index * self.levels.chunk_size as i32,
+                max: (*chunk_index + 1) * self.levels.chunk_size as i32 - 1,
+            }))
+            .collect::<Vec<_>>();
+        self.dirty_areas.extend(areas);
+    }
+
+    #[inline]
+    fn max_radius(&self) -> u32 {
+        self.sources.values().map(|l| l.radius).max().unwrap_or(0)
+    }
+
+    #[inline]
+    fn mark_dirty(&mut self, index: IVec2, radius: u32) {
+        self.dirty_areas.push(Self::light_area(index, radius));
+    }
+
+    #[inline]
+    fn light_area(index: IVec2, radius: u32) -> IAabb2d {
+        IAabb2d {
+            min: index - radius as i32,
+            max: index + radius as i32,
+        }
+    }
+
+    /// Recalculate the light levels of the chunks touched by the dirty areas.
+    ///
+    /// Returns the chunks whose render data should be updated.
+    fn recalculate(&mut self) -> HashSet<IVec2> {
+        let chunk_size = IVec2::splat(self.levels.chunk_size as i32);
+        let mut affected = HashSet::new();
+        std::mem::take(&mut self.dirty_areas)
+            .into_iter()
+            .for_each(|area| {
+                IAabb2d {
+                    min: area.min.div_to_floor(chunk_size),
+                    max: area.max.div_to_floor(chunk_size),
+                }
+                .into_iter()
+                .for_each(|chunk_index| {
+                    affected.insert(chunk_index);
+                });
+            });
+
+        affected.iter().for_each(|chunk_index| {
+            self.levels.remove_chunk(*chunk_index);
+        });
+
+        let affected_area = affected.iter().fold(None, |acc: Option<IAabb2d>, c| {
+            let chunk_area = IAabb2d {
+                min: *c * chunk_size,
+                max: (*c + 1) * chunk_size - 1,
+            };
+            Some(acc.map_or(chunk_area, |mut a| {
+                a.expand(chunk_area);
+                a
+            }))
+        });
+        let Some(affected_area) = affected_area else {
+            return affected;
+        };
+
+        let sources = self
+            .sources
+            .iter()
+            .filter(|(index, light)| {
+                let area = Self::light_area(**index, light.radius);
+                area.min.cmple(affected_area.max).all() && area.max.cmpge(affected_area.min).all()
+            })
+            .map(|(index, light)| (*index, *light))
+            .collect::<Vec<_>>();
+
+        let mut visited = HashSet::new();
+        let mut queue = VecDeque::new();
+        for (source, light) in sources {
+            visited.clear();
+            queue.push_back((source, 0));
+            visited.insert(source);
+
+            while let Some((index, distance)) = queue.pop_front() {
+                if affected.contains(&index.div_to_floor(chunk_size)) {
+                    let level = light.level_at(distance).max(self.ambient);
+                    let current = self.levels.get_elem(index).cloned().unwrap_or(self.ambient);
+                    self.levels.set_elem(index, current.max(level));
+                }
+
+                if distance == light.radius || (index != source && self.is_opaque(index)) {
+                    continue;
+                }
+
+                for offset in [IVec2::X, IVec2::Y, IVec2::NEG_X, IVec2::NEG_Y] {
+                    let neighbor = index + offset;
+                    if visited.insert(neighbor) {
+                        queue.push_back((neighbor, distance + 1));
+                    }
+                }
+            }
+        }
+
+        // The corners of the adjacent chunks may be affected.
+        affected
+            .iter()
+            .flat_map(|c| (-1..=1).flat_map(move |y| (-1..=1).map(move |x| *c + IVec2 { x, y })))
+            .collect()
+    }
+
+    fn get_chunk_corners(&self, chunk_index: IVec2, ty: TilemapType) -> Vec<[Vec3; 4]> {
+        corners::get_chunk_corners(
+            chunk_index,
+            self.levels.chunk_size,
+            ty,
+            self.smooth_edges,
+            |index| self.get_level(index),
+        )
+    }
+}
+
+pub fn light_updater(
+    mut tilemaps_query: Query<(Entity, &TilemapType, &mut LightTilemap), Changed<LightTilemap>>,
+    mut light_update: EventWriter<LightChunkUpdate>,
+) {
+    tilemaps_query.for_each_mut(|(entity, ty, mut light)| {
+        if light.dirty_areas.is_empty() {
+            return;
+        }
+
+        let light = light.bypass_change_detection();
+        let chunks = light.recalculate();
+        light_update.send_batch(chunks.into_iter().map(|index| LightChunkUpdate {
+            tilemap: entity,
+            index,
+            corners: light.get_chunk_corners(index, *ty),
+        }));
+    });
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_light_propagation() {
+        let mut light = LightTilemap::new_with_chunk_size(4).with_ambient(Vec3::splat(0.1));
+        light.set_light(IVec2::ZERO, TileLight::new(Vec3::ONE, 3));
+        light.set_opaque(IVec2::new(0, 1), true);
+        light.recalculate();
+
+        assert_eq!(light.get_level(IVec2::ZERO), Vec3::ONE);
+        assert_eq!(light.get_level(IVec2::new(2, 0)), Vec3::splat(0.5));
+        // Opaque tiles are lit but block the light behind them.
+        assert_eq!(light.get_level(IVec2::new(0, 1)), Vec3::splat(0.75));
+        // The detour around the blocker is longer than the radius.
+        assert_eq!(light.get_level(IVec2::new(0, 2)), Vec3::splat(0.1));
+        assert_eq!(light.get_level(IVec2::new(1, 2)), Vec3::splat(0.25));
+        assert_eq!(light.get_level(IVec2::new(10, 10)), Vec3::splat(0.1));
+
+        light.remove_light(IVec2::ZERO);
+        light.recalculate();
+        assert_eq!(light.get_level(IVec2::ZERO), Vec3::splat(0.1));
+    }
+}
@@ -1,27 +1,39 @@
-use std::{f32::consts::SQRT_2, fmt::Debug};
+use std::{
+    f32::consts::SQRT_2,
+    fmt::Debug,
+    hash::{Hash, Hasher},
+};
 
 use bevy::{
     asset::Handle,
-    ecs::{component::Component, query::Changed, system::Query},
+    ecs::{
+        component::Component, event::EventWriter, query::Changed,
+        removal_detection::RemovedComponents, system::Query,
+    },
     math::{Mat2, Quat, Vec4},
-    prelude::{Assets, Commands, Entity, IVec2, Image, ResMut, UVec2, Vec2},
+    prelude::{Assets, Commands, Entity, IVec2, Image, Res, ResMut, UVec2, Vec2},
     reflect::Reflect,
     render::render_resource::{FilterMode, TextureUsages},
     sprite::TextureAtlas,
+    time::Time,
     transform::components::Transform,
     utils::{HashMap, HashSet},
 };
 
-use crate::math::{
-    aabb::{Aabb2d, IAabb2d},
-    TileArea,
+use crate::{
+    math::{
+        aabb::{Aabb2d, IAabb2d},
+        TileArea,
+    },
+    DEFAULT_LAYER_COUNT,
 };
 
 use super::{
     buffers::TileBuilderBuffer,
     chunking::storage::{ChunkedStorage, EntityChunkedStorage},
+    coordinates,
     despawn::DespawnMe,
-    tile::{TileAnimation, TileBuilder, TileUpdater},
+    tile::{AnimationMode, Tile, TileAnimation, TileAnimationFinished, TileBuilder, TileUpdater},
 };
 
 /// Defines the shape of tiles in a tilemap.
@@ -148,11 +160,17 @@ impl Into<Transform> for TilemapTransform {
     }
 }
 
+/// The textures of a tilemap.
+///
+/// A tilemap can use several textures with the same tile size. Each texture is a slot
+/// and `TileLayer::with_texture_slot` selects the slot of a layer.
 #[derive(Component, Clone, Default, Debug, Reflect)]
 pub struct TilemapTexture {
     pub(crate) texture: Handle<Image>,
     pub(crate) desc: TilemapTextureDescriptor,
     pub(crate) rotation: TilemapRotation,
+    /// The textures of the slots after the first one.
+    pub(crate) extra_textures: Vec<(Handle<Image>, TilemapTextureDescriptor)>,
 }
 
 impl TilemapTexture {
@@ -165,9 +183,26 @@ impl TilemapTexture {
             texture,
             desc,
             rotation,
+            extra_textures: Vec::new(),
         }
     }
 
+    /// Add a texture to the next slot.
+    pub fn with_texture(mut self, texture: Handle<Image>, desc: TilemapTextureDescriptor) -> Self {
+        self.add_texture(texture, desc);
+        self
+    }
+
+    /// Add a texture to the next slot and return the slot.
+    pub fn add_texture(&mut self, texture: Handle<Image>, desc: TilemapTextureDescriptor) -> usize {
+        assert_eq!(
+            desc.tile_size, self.desc.tile_size,
+            "All the textures of a tilemap must have the same tile size!"
+        );
+        self.extra_textures.push((texture, desc));
+        self.extra_textures.len()
+    }
+
     pub fn clone_weak(&self) -> Handle<Image> {
         self.texture.clone_weak()
     }
@@ -180,6 +215,71 @@ impl TilemapTexture {
         &self.texture
     }
 
+    #[inline]
+    pub fn slot_count(&self) -> usize {
+        self.extra_textures.len() + 1
+    }
+
+    pub fn get_slot(&self, slot: usize) -> Option<(&Handle<Image>, &TilemapTextureDescriptor)> {
+        if slot == 0 {
+            Some((&self.texture, &self.desc))
+        } else {
+            self.extra_textures.get(slot - 1).map(|(t, d)| (t, d))
+        }
+    }
+
+    pub fn iter_slots(&self) -> impl Iterator<Item = (&Handle<Image>, &TilemapTextureDescriptor)> {
+        std::iter::once((&self.texture, &self.desc))
+            .chain(self.extra_textures.iter().map(|(t, d)| (t, d)))
+    }
+
+    /// Convert a texture index of a slot into the index in the combined texture,
+    /// which is what animations use.
+    pub fn get_global_index(&self, slot: usize, index: u32) -> u32 {
+        self.iter_slots()
+            .take(slot)
+            .map(|(_, desc)| desc.tile_count())
+            .sum::<u32>()
+            + index
+    }
+
+    /// The size in tiles of the texture that all the slots are combined into.
+    ///
+    /// The tiles are placed in the order of the global indices.
+    pub fn get_combined_tiled_size(&self) -> UVec2 {
+        let columns = self
+            .iter_slots()
+            .map(|(_, desc)| desc.size.x / desc.tile_size.x)
+            .max()
+            .unwrap();
+        let total = self
+            .iter_slots()
+            .map(|(_, desc)| desc.tile_count())
+            .sum::<u32>();
+        UVec2::new(columns, (total + columns - 1) / columns)
+    }
+
+    /// The mode of the first slot, which is used for all the slots.
+    #[inline]
+    pub fn mode(&self) -> TilemapTextureMode {
+        self.desc.mode
+    }
+
+    /// The handle the render world uses to identify this set of textures.
+    /// This is the handle of the first texture if it's used as an atlas directly.
+    pub(crate) fn get_render_handle(&self) -> Handle<Image> {
+        if self.extra_textures.is_empty() && self.mode() == TilemapTextureMode::Atlas {
+            return self.clone_weak();
+        }
+
+        let mut hasher = bevy::utils::AHasher::default();
+        self.mode().hash(&mut hasher);
+        self.iter_slots()
+            .for_each(|(handle, _)| handle.id().hash(&mut hasher));
+        let hash = hasher.finish() as u128;
+        Handle::weak_from_u128(hash << 64 | self.slot_count() as u128)
+    }
+
     pub fn as_texture_atlas(&self) -> TextureAtlas {
         TextureAtlas::from_grid(
             self.texture.clone(),
@@ -193,22 +293,46 @@ impl TilemapTexture {
 
     /// Bevy doesn't set the `COPY_SRC` usage for images by default, so we need to do it manually.
     pub(crate) fn set_usage(&mut self, image_assets: &mut ResMut<Assets<Image>>) {
-        let Some(image) = image_assets.get(&self.clone_weak()) else {
-            return;
-        };
+        self.iter_slots().for_each(|(handle, _)| {
+            let Some(image) = image_assets.get(handle) else {
+                return;
+            };
 
-        if !image
-            .texture_descriptor
-            .usage
-            .contains(TextureUsages::COPY_SRC)
-        {
-            image_assets
-                .get_mut(&self.clone_weak())
-                .unwrap()
+            if !image
                 .texture_descriptor
                 .usage
-                .set(TextureUsages::COPY_SRC, true);
-        }
+                .contains(TextureUsages::COPY_SRC)
+            {
+                image_assets
+                    .get_mut(handle)
+                    .unwrap()
+                    .texture_descriptor
+                    .usage
+                    .set(TextureUsages::COPY_SRC, true);
+            }
+        });
+    }
+}
+
+/// How the tiles are sampled from the texture.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Reflect)]
+#[cfg_attr(feature = "serializing", derive(serde::Serialize, serde::Deserialize))]
+pub enum TilemapTextureMode {
+    /// Copy the tiles into a texture array. Each tile is a layer of the array,
+    /// so there's no bleeding between tiles.
+    Array,
+    /// Calculate the uv of the tiles on the entire texture.
+    /// This works with textures that exceed the layer limit of texture arrays.
+    Atlas,
+}
+
+impl Default for TilemapTextureMode {
+    /// `Atlas` if the `atlas` feature is enabled, otherwise `Array`.
+    fn default() -> Self {
+        #[cfg(feature = "atlas")]
+        return Self::Atlas;
+        #[cfg(not(feature = "atlas"))]
+        return Self::Array;
     }
 }
 
@@ -218,6 +342,7 @@ pub struct TilemapTextureDescriptor {
     pub(crate) tile_size: UVec2,
     #[reflect(ignore)]
     pub(crate) filter_mode: FilterMode,
+    pub(crate) mode: TilemapTextureMode,
 }
 
 impl TilemapTextureDescriptor {
@@ -234,8 +359,20 @@ impl TilemapTextureDescriptor {
             size,
             tile_size,
             filter_mode,
+            mode: TilemapTextureMode::default(),
         }
     }
+
+    pub fn with_mode(mut self, mode: TilemapTextureMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    #[inline]
+    pub fn tile_count(&self) -> u32 {
+        let tiled_size = self.size / self.tile_size;
+        tiled_size.x * tiled_size.y
+    }
 }
 
 #[derive(Component, Default, Debug, Clone, Reflect)]
@@ -264,6 +401,126 @@ impl Default for TilemapLayerOpacities {
     }
 }
 
+#[repr(u32)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Reflect)]
+#[cfg_attr(feature = "serializing", derive(serde::Serialize, serde::Deserialize))]
+pub enum LayerBlendMode {
+    #[default]
+    Normal = 0,
+    Additive = 1,
+    Multiply = 2,
+}
+
+#[derive(Debug, Clone, Copy, Reflect)]
+#[cfg_attr(feature = "serializing", derive(serde::Serialize, serde::Deserialize))]
+pub struct TilemapLayerStyle {
+    /// Multiplied with the opacity in `TilemapLayerOpacities`.
+    pub opacity: f32,
+    pub tint: Vec4,
+    pub blend_mode: LayerBlendMode,
+}
+
+impl Default for TilemapLayerStyle {
+    fn default() -> Self {
+        Self {
+            opacity: 1.,
+            tint: Vec4::ONE,
+            blend_mode: LayerBlendMode::Normal,
+        }
+    }
+}
+
+/// The layers of a tilemap. Add this to the tilemap entity if the tiles have
+/// more than `DEFAULT_LAYER_COUNT` layers or the layers need different styles.
+///
+/// Layers above the count are not rendered.
+#[derive(Component, Debug, Clone, Reflect)]
+#[cfg_attr(feature = "serializing", derive(serde::Serialize, serde::Deserialize))]
+pub struct TilemapLayers {
+    pub(crate) styles: Vec<TilemapLayerStyle>,
+}
+
+impl Default for TilemapLayers {
+    fn default() -> Self {
+        Self::new(DEFAULT_LAYER_COUNT)
+    }
+}
+
+impl TilemapLayers {
+    pub fn new(count: usize) -> Self {
+        Self {
+            styles: vec![TilemapLayerStyle::default(); count],
+        }
+    }
+
+    pub fn with_style(mut self, layer: usize, style: TilemapLayerStyle) -> Self {
+        self.set_style(layer, style);
+        self
+    }
+
+    #[inline]
+    pub fn count(&self) -> usize {
+        self.styles.len()
+    }
+
+    /// Change the number of layers. New layers use the default style.
+    pub fn set_count(&mut self, count: usize) {
+        self.styles.resize(count, TilemapLayerStyle::default());
+    }
+
+    #[inline]
+    pub fn get_style(&self, layer: usize) -> Option<&TilemapLayerStyle> {
+        self.styles.get(layer)
+    }
+
+    /// Set the style of a layer. The count is increased if the layer is out of range.
+    pub fn set_style(&mut self, layer: usize, style: TilemapLayerStyle) {
+        if layer >= self.styles.len() {
+            self.set_count(layer + 1);
+        }
+        self.styles[layer] = style;
+    }
+}
+
+/// Sort the tiles of a tilemap one by one instead of drawing the whole tilemap
+/// at `TilemapTransform::z_index`. Add this to the tilemap entity.
+///
+/// The tiles are drawn in groups with the same depth, so they can be interleaved
+/// with sprites and other `Transparent2d` items.
+#[derive(Component, Debug, Clone, Copy, PartialEq, Reflect)]
+#[cfg_attr(feature = "serializing", derive(serde::Serialize, serde::Deserialize))]
+pub enum TilemapDepthMode {
+    /// The depth of a tile is `z_index + depth - y * scale`, where `y` is the world
+    /// position of the tile origin. Give sprites `z = -y * scale` to walk behind
+    /// tiles that are lower on the screen.
+    YSort { scale: f32 },
+    /// The depth of a tile is `z_index + depth`. Set the depth using `TileBuilder::with_depth`.
+    PerTile,
+}
+
+impl TilemapDepthMode {
+    /// The part of the depth that is shared by all tiles of the tilemap.
+    #[inline]
+    pub fn get_base_depth(&self, transform: &TilemapTransform) -> f32 {
+        match self {
+            TilemapDepthMode::YSort { scale } => {
+                transform.z_index as f32 - transform.translation.y * scale
+            }
+            TilemapDepthMode::PerTile => transform.z_index as f32,
+        }
+    }
+
+    /// The part of the depth that is different for each tile.
+    /// `rel_y` is the y position of the tile relative to the tilemap translation.
+    #[inline]
+    pub fn get_tile_depth(&self, depth: f32, rel_y: f32) -> f32 {
+        match self {
+            TilemapDepthMode::YSort { scale } => depth - rel_y * scale,
+            TilemapDepthMode::PerTile => depth,
+        }
+    }
+}
+
 #[derive(Component, Default, Debug, Clone, Copy, Reflect)]
 pub struct TilemapAabbs {
     pub(crate) chunk_aabb: IAabb2d,
@@ -327,6 +584,7 @@ impl TilemapStorage {
     pub fn set(&mut self, commands: &mut Commands, index: IVec2, tile_builder: TileBuilder) {
         if let Some(previous) = self.storage.get_elem(index) {
             commands.entity(*previous).despawn();
+            self.recalculate_aabb(self.storage.transform_index(index).0);
         }
         let new_tile = tile_builder.build_component(index, &self, self.tilemap);
 
@@ -340,10 +598,12 @@ impl TilemapStorage {
     pub(crate) fn set_entity(&mut self, index: IVec2, entity: Option<Entity>) {
         if let Some(e) = entity {
             let (chunk_index, in_chunk_index) = self.storage.transform_index(index);
-            self.storage.set_elem_precise(chunk_index, in_chunk_index, e);
+            self.storage
+                .set_elem_precise(chunk_index, in_chunk_index, e);
             self.reserve(chunk_index);
         } else {
             self.storage.remove_elem(index);
+            self.recalculate_aabb(self.storage.transform_index(index).0);
         }
     }
 
@@ -373,6 +633,7 @@ impl TilemapStorage {
     /// Remove the whole chunk and despawn all the tiles in it.
     #[inline]
     pub fn remove_chunk(&mut self, commands: &mut Commands, index: IVec2) {
+        self.recalculate_aabb(index);
         if let Some(chunk) = self.storage.remove_chunk(index) {
             chunk.into_iter().filter_map(|e| e).for_each(|e| {
                 commands.entity(e).insert(DespawnMe);
@@ -423,6 +684,14 @@ impl TilemapStorage {
         }
     }
 
+    /// The tile may be larger than the slot, so the aabb may shrink after it's removed.
+    #[inline]
+    fn recalculate_aabb(&mut self, chunk_index: IVec2) {
+        if self.reserved.contains_key(&chunk_index) {
+            self.calc_queue.insert(chunk_index);
+        }
+    }
+
     /// Despawn the entire tilemap.
     #[inline]
     pub fn despawn(&mut self, commands: &mut Commands) {
@@ -577,21 +846,163 @@ impl TilemapStorage {
     }
 }
 
-#[derive(Component, Default, Debug, Clone, Reflect)]
+/// All the animation sequences of a tilemap, and the clock they are played with.
+///
+/// For each animation, the sequence buffer stores `[length, mode, fps, indices..]`.
+/// If fps is `-1`, the cumulative end time of each frame is stored after the indices
+/// as the bits of `f32`.
+///
+/// The fields missing in the saved data use the values of `TilemapAnimations::default`.
+#[derive(Component, Debug, Clone, Reflect)]
 #[cfg_attr(feature = "serializing", derive(serde::Serialize, serde::Deserialize))]
-pub struct TilemapAnimations(pub(crate) Vec<i32>);
+#[cfg_attr(feature = "serializing", serde(default))]
+pub struct TilemapAnimations {
+    pub(crate) seqs: Vec<i32>,
+    pub(crate) time: f32,
+    /// The speed of all the animations of this tilemap.
+    pub time_scale: f32,
+    /// Pause all the animations of this tilemap.
+    pub paused: bool,
+    #[cfg_attr(feature = "serializing", serde(skip))]
+    #[reflect(ignore)]
+    pub(crate) one_shots: Vec<(f32, TileAnimationFinished)>,
+}
+
+impl Default for TilemapAnimations {
+    fn default() -> Self {
+        Self {
+            seqs: Vec::new(),
+            time: 0.,
+            time_scale: 1.,
+            paused: false,
+            one_shots: Vec::new(),
+        }
+    }
+}
 
 impl TilemapAnimations {
     /// Register a tile animation so you can use it in `TileBuilder::with_animation`.
+    #[inline]
     pub fn register_animation(&mut self, fps: u32, seq: Vec<u32>) -> TileAnimation {
-        self.0.push(fps as i32);
-        let start = self.0.len() as u32;
+        self.register_animation_with_mode(fps, seq, AnimationMode::Loop)
+    }
+
+    /// Register a tile animation with the given playback mode.
+    pub fn register_animation_with_mode(
+        &mut self,
+        fps: u32,
+        seq: Vec<u32>,
+        mode: AnimationMode,
+    ) -> TileAnimation {
         let length = seq.len() as u32;
-        self.0.extend(seq.into_iter().map(|i| i as i32));
-        TileAnimation { start, length, fps }
+        self.seqs.push(length as i32);
+        self.seqs.push(mode as i32);
+        self.seqs.push(fps as i32);
+        let start = self.seqs.len() as u32;
+        self.seqs.extend(seq.into_iter().map(|i| i as i32));
+        TileAnimation {
+            start,
+            length,
+            fps,
+            offset: 0.,
+            paused: false,
+            start_time: None,
+        }
+    }
+
+    /// Register a tile animation whose frames have their own durations.
+    ///
+    /// `seq` contains the texture index and the duration in seconds of each frame.
+    pub fn register_animation_with_durations(
+        &mut self,
+        seq: Vec<(u32, f32)>,
+        mode: AnimationMode,
+    ) -> TileAnimation {
+        let length = seq.len() as u32;
+        self.seqs.push(length as i32);
+        self.seqs.push(mode as i32);
+        self.seqs.push(-1);
+        let start = self.seqs.len() as u32;
+        self.seqs.extend(seq.iter().map(|(i, _)| *i as i32));
+        let mut end = 0.;
+        self.seqs.extend(seq.iter().map(|(_, duration)| {
+            end += *duration;
+            f32::to_bits(end) as i32
+        }));
+        TileAnimation {
+            start,
+            length,
+            fps: 0,
+            offset: 0.,
+            paused: false,
+            start_time: None,
+        }
+    }
+
+    /// The elapsed time of the animations of this tilemap.
+    #[inline]
+    pub fn time(&self) -> f32 {
+        self.time
+    }
+
+    #[inline]
+    pub fn get_mode(&self, animation: &TileAnimation) -> AnimationMode {
+        self.seqs[animation.start as usize - 2]
+            .try_into()
+            .unwrap_or_default()
+    }
+
+    /// The duration of one play of the animation in seconds.
+    pub fn get_duration(&self, animation: &TileAnimation) -> f32 {
+        let start = animation.start as usize;
+        let fps = self.seqs[start - 1];
+        if fps > 0 {
+            animation.length as f32 / fps as f32
+        } else {
+            f32::from_bits(self.seqs[start + animation.length as usize * 2 - 1] as u32)
+        }
     }
 }
 
+pub fn animation_time_updater(
+    mut tilemaps_query: Query<&mut TilemapAnimations>,
+    mut animation_finished: EventWriter<TileAnimationFinished>,
+    mut removed_tiles: RemovedComponents<Tile>,
+    time: Res<Time>,
+) {
+    let removed_tiles = removed_tiles.read().collect::<HashSet<_>>();
+
+    tilemaps_query.for_each_mut(|mut animations| {
+        if !removed_tiles.is_empty()
+            && animations
+                .one_shots
+                .iter()
+                .any(|(_, e)| removed_tiles.contains(&e.tile))
+        {
+            animations
+                .one_shots
+                .retain(|(_, e)| !removed_tiles.contains(&e.tile));
+        }
+
+        if animations.paused {
+            return;
+        }
+
+        animations.time += time.delta_seconds() * animations.time_scale;
+        let now = animations.time;
+        let mut finished = Vec::new();
+        animations.one_shots.retain(|(end, e)| {
+            if *end <= now {
+                finished.push(*e);
+                false
+            } else {
+                true
+            }
+        });
+        animation_finished.send_batch(finished);
+    });
+}
+
 pub fn transform_syncer(
     mut tilemap_query: Query<(&TilemapTransform, &mut Transform), Changed<TilemapTransform>>,
 ) {
@@ -603,35 +1014,80 @@ pub fn transform_syncer(
     });
 }
 
+pub type ChunkAabbTilemapsQuery<'w, 's> = Query<
+    'w,
+    's,
+    (
+        Entity,
+        &'static mut TilemapStorage,
+        &'static TilemapType,
+        &'static TilePivot,
+        &'static TileRenderSize,
+        &'static TilemapSlotSize,
+        &'static TilemapTransform,
+    ),
+>;
+
+/// Calculate the aabbs of the queued chunks, including the tiles that are larger than the slots.
 pub fn queued_chunk_aabb_calculator(
-    mut tilemaps_query: Query<(
-        &mut TilemapStorage,
-        &TilemapType,
-        &TilePivot,
-        &TilemapSlotSize,
-        &TilemapTransform,
-    )>,
+    mut tilemaps_query: ChunkAabbTilemapsQuery,
+    tiles_query: Query<&Tile>,
+    changed_tiles_query: Query<&Tile, Changed<Tile>>,
 ) {
+    let mut oversized = HashMap::<Entity, HashSet<IVec2>>::default();
+    changed_tiles_query.for_each(|tile| {
+        if tile.render_size.is_some() || tile.anchor.is_some() {
+            oversized
+                .entry(tile.tilemap_id)
+                .or_default()
+                .insert(tile.chunk_index);
+        }
+    });
+
     tilemaps_query.par_iter_mut().for_each(
-        |(mut storage, ty, tile_pivot, slot_size, transform)| {
+        |(entity, mut storage, ty, tile_pivot, render_size, slot_size, transform)| {
+            if let Some(chunks) = oversized.get(&entity) {
+                storage.calc_queue.extend(chunks.iter().copied());
+            }
+            if storage.calc_queue.is_empty() {
+                return;
+            }
+
             let chunk_size = storage.storage.chunk_size;
             let ext = storage
                 .calc_queue
-                .drain()
+                .iter()
                 .map(|i| {
-                    (
-                        i,
-                        Aabb2d::from_tilemap(
-                            i,
-                            chunk_size,
-                            *ty,
-                            tile_pivot.0,
-                            slot_size.0,
-                            *transform,
-                        ),
-                    )
+                    let mut aabb = Aabb2d::from_tilemap(
+                        *i,
+                        chunk_size,
+                        *ty,
+                        tile_pivot.0,
+                        slot_size.0,
+                        *transform,
+                    );
+
+                    if let Some(chunk) = storage.storage.chunks.get(i) {
+                        chunk
+                            .iter()
+                            .filter_map(|e| e.and_then(|e| tiles_query.get(e).ok()))
+                            .filter(|t| t.render_size.is_some() || t.anchor.is_some())
+                            .for_each(|t| {
+                                aabb.expand(coordinates::get_tile_render_aabb(
+                                    t.index,
+                                    ty,
+                                    transform,
+                                    t.render_size.unwrap_or(render_size.0),
+                                    t.anchor.unwrap_or(tile_pivot.0),
+                                    slot_size.0,
+                                ));
+                            });
+                    }
+
+                    (*i, aabb)
                 })
                 .collect::<Vec<_>>();
+            storage.calc_queue.clear();
             storage.reserved.extend(ext);
         },
     );
@@ -682,11 +1138,93 @@ pub fn tilemap_aabb_calculator(
                 *transform,
             );
 
-            aabbs.chunk_aabb = chunk_aabb;
-            aabbs.world_aabb = Aabb2d {
+            let mut world_aabb = Aabb2d {
                 min: world_min.min,
                 max: world_max.max,
             };
+            storage
+                .storage
+                .chunks
+                .keys()
+                .filter_map(|chunk_index| storage.reserved.get(chunk_index))
+                .for_each(|aabb| world_aabb.expand(*aabb));
+
+            aabbs.chunk_aabb = chunk_aabb;
+            aabbs.world_aabb = world_aabb;
         },
     );
 }
+
+#[cfg(test)]
+mod test {
+    use bevy::{
+        ecs::{system::RunSystemOnce, world::World},
+        tasks::{ComputeTaskPool, TaskPool},
+    };
+
+    use crate::tilemap::tile::TileTexture;
+
+    use super::*;
+
+    #[test]
+    fn test_oversized_chunk_aabb() {
+        ComputeTaskPool::get_or_init(TaskPool::default);
+        let mut world = World::new();
+        let tilemap = world.spawn_empty().id();
+        let index = IVec2::new(0, 15);
+        let tile = world
+            .spawn(Tile {
+                tilemap_id: tilemap,
+                chunk_index: IVec2::ZERO,
+                in_chunk_index: 240,
+                index,
+                texture: TileTexture::Static(Vec::new()),
+                color: Vec4::ONE,
+                depth: 0.,
+                render_size: Some(Vec2::new(16., 32.)),
+                anchor: None,
+            })
+            .id();
+
+        let mut storage = TilemapStorage::new(16, tilemap);
+        storage.set_entity(index, Some(tile));
+        world.entity_mut(tilemap).insert((
+            storage,
+            TilemapType::Square,
+            TilePivot::default(),
+            TileRenderSize(Vec2::splat(16.)),
+            TilemapSlotSize(Vec2::splat(16.)),
+            TilemapTransform::default(),
+            TilemapAabbs::default(),
+        ));
+        let chunk_aabb =
+            |world: &World| world.get::<TilemapStorage>(tilemap).unwrap().reserved[&IVec2::ZERO];
+
+        // The tile at the top of the chunk sticks out by half a tile.
+        world.run_system_once(queued_chunk_aabb_calculator);
+        world.run_system_once(tilemap_aabb_calculator);
+        assert_eq!(chunk_aabb(&world).max, Vec2::new(256., 272.));
+        assert_eq!(
+            world.get::<TilemapAabbs>(tilemap).unwrap().world_aabb.max,
+            Vec2::new(256., 272.)
+        );
+
+        // And the aabb shrinks after it's removed.
+        world
+            .get_mut::<TilemapStorage>(tilemap)
+            .unwrap()
+            .set_entity(index, None);
+        world.despawn(tile);
+        world.run_system_once(queued_chunk_aabb_calculator);
+        assert_eq!(chunk_aabb(&world).max, Vec2::new(256., 256.));
+    }
+
+    #[cfg(feature = "serializing")]
+    #[test]
+    fn test_animations_serde_default() {
+        let animations = ron::from_str::<TilemapAnimations>("(seqs: [1, 0, 10, 3])").unwrap();
+        assert_eq!(animations.seqs, vec![1, 0, 10, 3]);
+        assert_eq!(animations.time_scale, 1.);
+        assert!(!animations.paused);
+    }
+}
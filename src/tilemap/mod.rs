@@ -1,13 +1,22 @@
-use bevy::app::{Plugin, PostUpdate, PreUpdate, Update};
+use bevy::{
+    app::{Plugin, PostUpdate, PreUpdate, Update},
+    ecs::schedule::IntoSystemConfigs,
+};
 
 use self::{
     chunking::camera::{CameraChunkUpdater, CameraChunkUpdation},
+    fog::{FogChunkUpdate, FogState, FogTilemap},
+    light::{LightChunkUpdate, LightTilemap, TileLight},
     map::{
-        TilePivot, TileRenderSize, TilemapAabbs, TilemapAnimations, TilemapLayerOpacities,
-        TilemapName, TilemapSlotSize, TilemapStorage, TilemapTexture, TilemapTextureDescriptor,
-        TilemapTransform, TilemapType,
+        LayerBlendMode, TilePivot, TileRenderSize, TilemapAabbs, TilemapAnimations,
+        TilemapDepthMode, TilemapLayerOpacities, TilemapLayerStyle, TilemapLayers, TilemapName,
+        TilemapSlotSize, TilemapStorage, TilemapTexture, TilemapTextureDescriptor,
+        TilemapTextureMode, TilemapTransform, TilemapType,
+    },
+    tile::{
+        AnimationMode, LayerUpdater, Tile, TileAnimation, TileAnimationFinished, TileLayer,
+        TileTexture, TileUpdater,
     },
-    tile::{LayerUpdater, Tile, TileLayer, TileTexture, TileUpdater},
 };
 
 #[cfg(feature = "algorithm")]
@@ -17,6 +26,8 @@ pub mod bundles;
 pub mod chunking;
 pub mod coordinates;
 pub mod despawn;
+pub mod fog;
+pub mod light;
 pub mod map;
 #[cfg(feature = "physics")]
 pub mod physics;
@@ -33,8 +44,9 @@ impl Plugin for EntiTilesTilemapPlugin {
             (
                 map::transform_syncer,
                 map::queued_chunk_aabb_calculator,
-                map::tilemap_aabb_calculator,
+                map::tilemap_aabb_calculator.after(map::queued_chunk_aabb_calculator),
                 tile::tile_updater,
+                map::animation_time_updater,
                 chunking::camera::camera_chunk_update,
             ),
         );
@@ -46,6 +58,9 @@ impl Plugin for EntiTilesTilemapPlugin {
                 despawn::despawn_tiles,
                 #[cfg(feature = "physics")]
                 despawn::despawn_physics_tilemaps,
+                fog::fog_updater,
+                light::light_updater,
+                tile::tile_animation_starter,
             ),
         );
 
@@ -53,7 +68,10 @@ impl Plugin for EntiTilesTilemapPlugin {
             .register_type::<LayerUpdater>()
             .register_type::<TileUpdater>()
             .register_type::<Tile>()
-            .register_type::<TileTexture>();
+            .register_type::<TileTexture>()
+            .register_type::<TileAnimation>()
+            .register_type::<AnimationMode>()
+            .register_type::<TileAnimationFinished>();
 
         app.register_type::<TilemapName>()
             .register_type::<TileRenderSize>()
@@ -61,17 +79,31 @@ impl Plugin for EntiTilesTilemapPlugin {
             .register_type::<TilemapType>()
             .register_type::<TilePivot>()
             .register_type::<TilemapLayerOpacities>()
+            .register_type::<TilemapLayers>()
+            .register_type::<TilemapLayerStyle>()
+            .register_type::<LayerBlendMode>()
             .register_type::<TilemapStorage>()
             .register_type::<TilemapAabbs>()
             .register_type::<TilemapTransform>()
             .register_type::<TilemapTexture>()
             .register_type::<TilemapTextureDescriptor>()
+            .register_type::<TilemapTextureMode>()
+            .register_type::<TilemapDepthMode>()
             .register_type::<TilemapAnimations>();
 
         app.register_type::<CameraChunkUpdation>()
             .register_type::<CameraChunkUpdater>();
 
-        app.add_event::<CameraChunkUpdation>();
+        app.register_type::<FogTilemap>()
+            .register_type::<FogState>();
+
+        app.register_type::<LightTilemap>()
+            .register_type::<TileLight>();
+
+        app.add_event::<CameraChunkUpdation>()
+            .add_event::<FogChunkUpdate>()
+            .add_event::<LightChunkUpdate>()
+            .add_event::<TileAnimationFinished>();
 
         #[cfg(feature = "algorithm")]
         app.add_plugins(algorithm::EntiTilesAlgorithmTilemapPlugin);
@@ -1,38 +1,82 @@
 use bevy::{
-    ecs::system::{ParallelCommands, Query},
+    ecs::{
+        change_detection::DetectChangesMut,
+        event::Event,
+        query::Changed,
+        system::{ParallelCommands, Query},
+    },
     math::IVec2,
-    prelude::{Component, Entity, Vec4},
+    prelude::{Component, Entity, Vec2, Vec4},
     reflect::Reflect,
-    render::render_resource::ShaderType,
 };
 
-use super::{buffers::Tiles, map::TilemapStorage};
+use super::{
+    buffers::Tiles,
+    map::{TilemapAnimations, TilemapStorage},
+};
 
 #[derive(Debug, Default, Clone, Copy, Reflect)]
 #[cfg_attr(feature = "serializing", derive(serde::Serialize, serde::Deserialize))]
 pub struct TileLayer {
     pub(crate) texture_index: i32,
+    /// The slot of `TilemapTexture` that the texture index refers to.
+    #[cfg_attr(feature = "serializing", serde(default))]
+    pub(crate) texture_slot: u32,
     pub(crate) flip: u32,
+    /// Overrides the texture index if set.
+    #[cfg_attr(feature = "serializing", serde(default))]
+    pub(crate) animation: Option<TileAnimation>,
 }
 
 impl TileLayer {
     pub fn new() -> Self {
         Self {
             texture_index: -1,
+            texture_slot: 0,
             flip: 0,
+            animation: None,
         }
     }
 
+    /// Play an animation on this layer instead of a static texture.
+    pub fn with_animation(mut self, animation: TileAnimation) -> Self {
+        self.animation = Some(animation);
+        self
+    }
+
     pub fn with_texture_index(mut self, texture_index: u32) -> Self {
         self.texture_index = texture_index as i32;
         self
     }
 
+    pub fn with_texture_slot(mut self, texture_slot: u32) -> Self {
+        self.texture_slot = texture_slot;
+        self
+    }
+
+    /// Add a flip to the layer. Call this multiple times to combine flips,
+    /// like `TileFlip::Diagonal` and `TileFlip::Horizontal`.
     pub fn with_flip(mut self, flip: TileFlip) -> Self {
         self.flip |= flip as u32;
         self
     }
 
+    /// Rotate the texture clockwise after the flips that are already set.
+    ///
+    /// The rotation is stored as flips, the same way as Tiled does it.
+    pub fn with_rotation(mut self, rotation: TileRotation) -> Self {
+        for _ in 0..rotation as u32 {
+            // Rotating 90 degrees is a diagonal flip followed by a horizontal flip.
+            let h = self.flip & 1;
+            let v = (self.flip >> 1) & 1;
+            self.flip = (self.flip & !TILE_ORIENTATION_BITS)
+                | (v ^ 1)
+                | (h << 1)
+                | ((self.flip & TileFlip::Diagonal as u32) ^ TileFlip::Diagonal as u32);
+        }
+        self
+    }
+
     #[allow(dead_code)]
     pub(crate) fn with_flip_raw(mut self, flip: u32) -> Self {
         self.flip = flip;
@@ -57,34 +101,68 @@ pub struct LayerUpdater {
 pub struct TileUpdater {
     pub layer: Option<LayerUpdater>,
     pub color: Option<Vec4>,
+    pub depth: Option<f32>,
+    /// Replace the animation of the tile. This also restarts one-shot animations.
+    pub animation: Option<TileAnimation>,
 }
 
+/// The bits of `TileLayer::flip` that change the orientation of the texture.
+const TILE_ORIENTATION_BITS: u32 = 0b1011;
+
 #[repr(u32)]
-#[derive(Debug, Clone, Copy, Reflect)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Reflect)]
 pub enum TileFlip {
     None = 0b00,
     Horizontal = 0b01,
     Vertical = 0b10,
     Both = 0b11,
+    /// Swap the x and y axes of the texture. This is applied before the other flips,
+    /// like the diagonal flip in Tiled.
+    Diagonal = 0b1000,
 }
 
-impl From<u32> for TileFlip {
-    fn from(value: u32) -> Self {
+/// Fails if the value is a combination of the diagonal flip and other flips,
+/// as they can't be represented by a single `TileFlip`.
+impl TryFrom<u32> for TileFlip {
+    type Error = u32;
+
+    fn try_from(value: u32) -> Result<Self, Self::Error> {
         match value {
-            0b00 => Self::None,
-            0b01 => Self::Horizontal,
-            0b10 => Self::Vertical,
-            0b11 => Self::Both,
-            _ => panic!("Invalid flip value! {}", value),
+            0b00 => Ok(Self::None),
+            0b01 => Ok(Self::Horizontal),
+            0b10 => Ok(Self::Vertical),
+            0b11 => Ok(Self::Both),
+            0b1000 => Ok(Self::Diagonal),
+            _ => Err(value),
         }
     }
 }
 
+/// The clockwise rotation of a tile layer. See `TileLayer::with_rotation`.
+#[repr(u32)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Reflect)]
+pub enum TileRotation {
+    #[default]
+    None = 0,
+    Cw90 = 1,
+    Cw180 = 2,
+    Cw270 = 3,
+}
+
 #[derive(Debug, Clone, Reflect)]
 #[cfg_attr(feature = "serializing", derive(serde::Serialize, serde::Deserialize))]
 pub struct TileBuilder {
     pub(crate) texture: TileTexture,
     pub(crate) color: Vec4,
+    /// Only used when the tilemap has a `TilemapDepthMode`.
+    #[cfg_attr(feature = "serializing", serde(default))]
+    pub(crate) depth: f32,
+    /// Overrides `TileRenderSize` if set.
+    #[cfg_attr(feature = "serializing", serde(default))]
+    pub(crate) render_size: Option<Vec2>,
+    /// Overrides `TilePivot` if set.
+    #[cfg_attr(feature = "serializing", serde(default))]
+    pub(crate) anchor: Option<Vec2>,
 }
 
 impl Tiles for TileBuilder {}
@@ -95,6 +173,9 @@ impl TileBuilder {
         Self {
             texture: TileTexture::Static(Vec::new()),
             color: Vec4::ONE,
+            depth: 0.,
+            render_size: None,
+            anchor: None,
         }
     }
 
@@ -103,6 +184,26 @@ impl TileBuilder {
         self
     }
 
+    /// Render the tile with a different size from the `TileRenderSize` of the tilemap,
+    /// like a 16x32 tree on a 16x16 grid. The texture is scaled to the render size.
+    pub fn with_render_size(mut self, render_size: Vec2) -> Self {
+        self.render_size = Some(render_size);
+        self
+    }
+
+    /// Render the tile with a different pivot from the `TilePivot` of the tilemap.
+    /// `[0, 0]` is the bottom left corner of the tile.
+    pub fn with_anchor(mut self, anchor: Vec2) -> Self {
+        self.anchor = Some(anchor);
+        self
+    }
+
+    /// Set the depth of the tile. See `TilemapDepthMode`.
+    pub fn with_depth(mut self, depth: f32) -> Self {
+        self.depth = depth;
+        self
+    }
+
     pub fn with_layer(mut self, index: usize, layer: TileLayer) -> Self {
         if let TileTexture::Static(ref mut tex) = self.texture {
             if tex.len() <= index {
@@ -132,16 +233,77 @@ impl TileBuilder {
             index,
             texture: self.texture.clone(),
             color: self.color,
+            depth: self.depth,
+            render_size: self.render_size,
+            anchor: self.anchor,
+        }
+    }
+}
+
+#[repr(i32)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Reflect)]
+#[cfg_attr(feature = "serializing", derive(serde::Serialize, serde::Deserialize))]
+pub enum AnimationMode {
+    /// Restart from the first frame after the last one.
+    #[default]
+    Loop = 0,
+    /// Play forwards and then backwards.
+    PingPong = 1,
+    /// Stop on the last frame and send a `TileAnimationFinished` event.
+    Once = 2,
+}
+
+impl TryFrom<i32> for AnimationMode {
+    type Error = i32;
+
+    fn try_from(value: i32) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(Self::Loop),
+            1 => Ok(Self::PingPong),
+            2 => Ok(Self::Once),
+            _ => Err(value),
         }
     }
 }
 
-#[derive(ShaderType, Debug, Clone, Copy, Reflect)]
+/// Get this using `TilemapAnimations::register_animation`.
+#[derive(Debug, Clone, Copy, Reflect)]
 #[cfg_attr(feature = "serializing", derive(serde::Serialize, serde::Deserialize))]
 pub struct TileAnimation {
     pub start: u32,
     pub length: u32,
+    /// `0` if the animation uses per-frame durations.
     pub fps: u32,
+    /// The phase offset of the animation in seconds.
+    pub offset: f32,
+    /// Freeze the animation on the frame at `offset`.
+    pub paused: bool,
+    /// The time of `TilemapAnimations` when the animation started.
+    /// This is filled when the tile is spawned. Only one-shot animations record
+    /// the actual time, others use `0` so they stay in sync.
+    pub(crate) start_time: Option<f32>,
+}
+
+impl TileAnimation {
+    pub fn with_offset(mut self, offset: f32) -> Self {
+        self.offset = offset;
+        self
+    }
+
+    pub fn with_paused(mut self, paused: bool) -> Self {
+        self.paused = paused;
+        self
+    }
+}
+
+/// Sent when a one-shot animation reaches its last frame.
+#[derive(Event, Debug, Clone, Copy, Reflect)]
+pub struct TileAnimationFinished {
+    pub tilemap: Entity,
+    pub tile: Entity,
+    pub index: IVec2,
+    /// The layer the animation is played on. `None` if it's a `TileTexture::Animated`.
+    pub layer: Option<usize>,
 }
 
 #[derive(Debug, Clone, Reflect)]
@@ -159,6 +321,9 @@ pub struct Tile {
     pub index: IVec2,
     pub texture: TileTexture,
     pub color: Vec4,
+    pub depth: f32,
+    pub render_size: Option<Vec2>,
+    pub anchor: Option<Vec2>,
 }
 
 impl Tiles for Tile {}
@@ -168,6 +333,9 @@ impl Into<TileBuilder> for Tile {
         TileBuilder {
             texture: self.texture,
             color: self.color,
+            depth: self.depth,
+            render_size: self.render_size,
+            anchor: self.anchor,
         }
     }
 }
@@ -200,8 +368,185 @@ pub fn tile_updater(
             if let Some(color) = updater.color {
                 tile.color = color;
             }
+            if let Some(depth) = updater.depth {
+                tile.depth = depth;
+            }
+            if let Some(animation) = updater.animation {
+                tile.texture = TileTexture::Animated(TileAnimation {
+                    start_time: None,
+                    ..animation
+                });
+            }
             commands.command_scope(|mut c| {
                 c.entity(entity).remove::<TileUpdater>();
             });
         });
 }
+
+/// Record the start time of newly applied animations so one-shot animations can be tracked.
+///
+/// The pending `TileAnimationFinished` events of the changed tiles are recalculated,
+/// so replaced or paused animations won't send them. Paused one-shot animations
+/// continue from the paused frame after they are resumed.
+pub fn tile_animation_starter(
+    mut tiles_query: Query<(Entity, &mut Tile), Changed<Tile>>,
+    mut tilemaps_query: Query<&mut TilemapAnimations>,
+) {
+    tiles_query.for_each_mut(|(entity, mut tile)| {
+        let Ok(animations) = tilemaps_query.get(tile.tilemap_id) else {
+            return;
+        };
+
+        let not_started = match &tile.texture {
+            TileTexture::Static(tex) => tex.iter().any(|l| {
+                l.animation
+                    .is_some_and(|a| a.start_time.is_none() && !a.paused)
+            }),
+            TileTexture::Animated(anim) => anim.start_time.is_none() && !anim.paused,
+        };
+        let is_pending = animations.one_shots.iter().any(|(_, e)| e.tile == entity);
+        if !not_started && !is_pending {
+            return;
+        }
+        let Ok(mut animations) = tilemaps_query.get_mut(tile.tilemap_id) else {
+            return;
+        };
+
+        // The tile is already marked as changed so the renderer will pick up the start time.
+        let tile = tile.bypass_change_detection();
+        let (tilemap, index) = (tile.tilemap_id, tile.index);
+        let anims = match &mut tile.texture {
+            TileTexture::Static(tex) => tex
+                .iter_mut()
+                .enumerate()
+                .filter_map(|(i, l)| l.animation.as_mut().map(|a| (Some(i), a)))
+                .collect::<Vec<_>>(),
+            TileTexture::Animated(anim) => vec![(None, anim)],
+        };
+
+        animations.one_shots.retain(|(_, e)| e.tile != entity);
+        for (layer, anim) in anims {
+            if animations.get_mode(anim) != AnimationMode::Once {
+                if !anim.paused {
+                    anim.start_time.get_or_insert(0.);
+                }
+                continue;
+            }
+
+            if anim.paused {
+                anim.start_time = None;
+                continue;
+            }
+
+            let start_time = *anim.start_time.get_or_insert(animations.time);
+            let end_time = start_time + animations.get_duration(anim) - anim.offset;
+            animations.one_shots.push((
+                end_time,
+                TileAnimationFinished {
+                    tilemap,
+                    tile: entity,
+                    index,
+                    layer,
+                },
+            ));
+        }
+    });
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_rotation() {
+        let rotated = |r| TileLayer::new().with_rotation(r).flip;
+        assert_eq!(rotated(TileRotation::None), 0b0000);
+        assert_eq!(rotated(TileRotation::Cw90), 0b1001);
+        assert_eq!(rotated(TileRotation::Cw180), 0b0011);
+        assert_eq!(rotated(TileRotation::Cw270), 0b1010);
+
+        let layer = TileLayer::new()
+            .with_flip(TileFlip::Horizontal)
+            .with_rotation(TileRotation::Cw180)
+            .with_rotation(TileRotation::Cw180);
+        assert_eq!(layer.flip, TileFlip::Horizontal as u32);
+
+        // The paused flag of the animation is kept.
+        let layer = TileLayer::new()
+            .with_flip_raw(0b100)
+            .with_rotation(TileRotation::Cw90);
+        assert_eq!(layer.flip, 0b1101);
+    }
+
+    #[test]
+    fn test_flip_conversion() {
+        let layer = TileLayer::new()
+            .with_flip(TileFlip::Diagonal)
+            .with_flip(TileFlip::Both);
+        assert_eq!(layer.flip, 0b1011);
+        assert_eq!(TileFlip::try_from(layer.flip & 0b11), Ok(TileFlip::Both));
+        assert_eq!(TileFlip::try_from(layer.flip), Err(0b1011));
+    }
+
+    #[test]
+    fn test_one_shot_tracking() {
+        use bevy::{
+            ecs::{event::Events, system::RunSystemOnce, world::World},
+            time::Time,
+        };
+
+        let mut world = World::new();
+        world.init_resource::<Time>();
+        world.init_resource::<Events<TileAnimationFinished>>();
+
+        let mut animations = TilemapAnimations::default();
+        let anim = animations.register_animation_with_mode(1, vec![0, 1], AnimationMode::Once);
+        let tilemap = world.spawn(animations).id();
+        let tile = world
+            .spawn(Tile {
+                tilemap_id: tilemap,
+                chunk_index: IVec2::ZERO,
+                in_chunk_index: 0,
+                index: IVec2::ZERO,
+                texture: TileTexture::Animated(anim),
+                color: Vec4::ONE,
+                depth: 0.,
+                render_size: None,
+                anchor: None,
+            })
+            .id();
+        let one_shots = |world: &World| {
+            world
+                .get::<TilemapAnimations>(tilemap)
+                .unwrap()
+                .one_shots
+                .iter()
+                .map(|(end, _)| *end)
+                .collect::<Vec<_>>()
+        };
+        let set_paused = |world: &mut World, paused: bool| {
+            if let TileTexture::Animated(anim) = &mut world.get_mut::<Tile>(tile).unwrap().texture {
+                anim.paused = paused;
+            }
+        };
+
+        world.run_system_once(tile_animation_starter);
+        assert_eq!(one_shots(&world), vec![2.]);
+
+        // Paused animations won't finish.
+        set_paused(&mut world, true);
+        world.run_system_once(tile_animation_starter);
+        assert!(one_shots(&world).is_empty());
+
+        // Resumed animations continue from the paused frame.
+        world.get_mut::<TilemapAnimations>(tilemap).unwrap().time = 1.;
+        set_paused(&mut world, false);
+        world.run_system_once(tile_animation_starter);
+        assert_eq!(one_shots(&world), vec![3.]);
+
+        // Despawned tiles won't finish.
+        world.despawn(tile);
+        world.run_system_once(crate::tilemap::map::animation_time_updater);
+        assert!(one_shots(&world).is_empty());
+    }
+}